@@ -0,0 +1,2 @@
+ALTER TABLE match ADD COLUMN stage TEXT;
+ALTER TABLE match ADD COLUMN groupName TEXT;
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS league (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    invite_code TEXT NOT NULL UNIQUE,
+    owner_id INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(owner_id) REFERENCES user(id)
+);
+CREATE TABLE IF NOT EXISTS league_member (
+    league_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    role TEXT NOT NULL,
+    joined_at INTEGER NOT NULL,
+    PRIMARY KEY(league_id, user_id),
+    FOREIGN KEY(league_id) REFERENCES league(id),
+    FOREIGN KEY(user_id) REFERENCES user(id)
+);
@@ -0,0 +1,11 @@
+ALTER TABLE user ADD COLUMN role TEXT NOT NULL DEFAULT 'player';
+CREATE TABLE IF NOT EXISTS role_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    old_role TEXT NOT NULL,
+    new_role TEXT NOT NULL,
+    changed_by INTEGER NOT NULL,
+    changed_at INTEGER NOT NULL,
+    FOREIGN KEY(user_id) REFERENCES user(id),
+    FOREIGN KEY(changed_by) REFERENCES user(id)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS team_strength (
+    tla TEXT PRIMARY KEY,
+    attack REAL NOT NULL,
+    defence REAL NOT NULL,
+    updated_by INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    FOREIGN KEY(updated_by) REFERENCES user(id)
+);
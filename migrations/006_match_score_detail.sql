@@ -0,0 +1,12 @@
+ALTER TABLE match ADD COLUMN duration TEXT;
+ALTER TABLE match ADD COLUMN homeScoreExtraTime INTEGER;
+ALTER TABLE match ADD COLUMN awayScoreExtraTime INTEGER;
+ALTER TABLE match ADD COLUMN homePenalties INTEGER;
+ALTER TABLE match ADD COLUMN awayPenalties INTEGER;
+CREATE TABLE IF NOT EXISTS team (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    short_name TEXT,
+    tla TEXT NOT NULL,
+    crest TEXT
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS tournament (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    rule_set TEXT NOT NULL
+);
+INSERT INTO tournament (id, slug, name, rule_set) VALUES (1, 'em2024', 'EURO 2024', 'em2024');
+ALTER TABLE match ADD COLUMN tournament_id INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,14 @@
+-- The champion of a tournament is set once it is decided. Champion picks of the
+-- default tournament stay on `user`, where the frontend keeps them.
+ALTER TABLE tournament ADD COLUMN champion TEXT;
+UPDATE tournament SET champion = 'ESP' WHERE id = 1;
+CREATE TABLE IF NOT EXISTS champion_pick (
+    tournament_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    winner TEXT NOT NULL,
+    secret_winner TEXT NOT NULL,
+    PRIMARY KEY(tournament_id, user_id),
+    FOREIGN KEY(tournament_id) REFERENCES tournament(id),
+    FOREIGN KEY(user_id) REFERENCES user(id)
+);
+ALTER TABLE league ADD COLUMN tournament_id INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,9 @@
+ALTER TABLE match ADD COLUMN venue TEXT;
+
+-- Secret for the calendar feed of a user. Unlike login tokens it does not
+-- expire, since calendar apps keep the URL; a new one replaces it.
+CREATE TABLE IF NOT EXISTS calendar_token (
+    user_id INTEGER PRIMARY KEY,
+    token TEXT NOT NULL UNIQUE,
+    created_at INTEGER NOT NULL
+);
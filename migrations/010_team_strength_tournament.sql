@@ -0,0 +1,17 @@
+-- Strengths are set per tournament, since a team is not as strong at every
+-- tournament. Existing ones belong to the default tournament.
+CREATE TABLE team_strength_by_tournament (
+    tournament_id INTEGER NOT NULL,
+    tla TEXT NOT NULL,
+    attack REAL NOT NULL,
+    defence REAL NOT NULL,
+    updated_by INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY(tournament_id, tla),
+    FOREIGN KEY(tournament_id) REFERENCES tournament(id),
+    FOREIGN KEY(updated_by) REFERENCES user(id)
+);
+INSERT INTO team_strength_by_tournament (tournament_id, tla, attack, defence, updated_by, updated_at)
+    SELECT 1, tla, attack, defence, updated_by, updated_at FROM team_strength;
+DROP TABLE team_strength;
+ALTER TABLE team_strength_by_tournament RENAME TO team_strength;
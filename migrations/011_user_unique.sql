@@ -0,0 +1,10 @@
+-- Two registrations at the same time can both pass the check for a free
+-- username or email address, so the database enforces it as well.
+-- Names that only differ in case are already taken by the oldest account;
+-- later ones get their id appended, so the indexes can be created.
+UPDATE user SET username = username || '_' || id
+    WHERE EXISTS (SELECT 1 FROM user AS older WHERE older.username = user.username COLLATE NOCASE AND older.id < user.id);
+UPDATE user SET email = 'duplicate-' || id || '-' || email
+    WHERE EXISTS (SELECT 1 FROM user AS older WHERE older.email = user.email COLLATE NOCASE AND older.id < user.id);
+CREATE UNIQUE INDEX IF NOT EXISTS user_username_unique ON user(username COLLATE NOCASE);
+CREATE UNIQUE INDEX IF NOT EXISTS user_email_unique ON user(email COLLATE NOCASE);
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A tip as the 2021 pool stored it in Firebase: `id` is the football-data.org
+/// match id, `score1` and `score2` are the goals of the home and away team.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tip {
+    pub user: String,
+    pub id: i32,
+    pub score1: i32,
+    pub score2: i32,
+}
+
+// Firebase exports a list as an array (with `null` for removed entries) or,
+// for pushed entries, as an object keyed by push id. Either may be the whole
+// export or sit under `tips`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    List(Vec<Option<Tip>>),
+    Pushed(BTreeMap<String, Tip>),
+    Nested { tips: Box<Export> },
+}
+
+impl Export {
+    fn into_tips(self) -> Vec<Tip> {
+        match self {
+            Export::List(tips) => tips.into_iter().flatten().collect(),
+            // Push ids start with the time of the push, so this keeps the order the tips were made in.
+            Export::Pushed(tips) => tips.into_values().collect(),
+            Export::Nested { tips } => tips.into_tips(),
+        }
+    }
+}
+
+/// Reads the tips of a Firebase JSON export.
+pub fn parse_export(json: &str) -> serde_json::Result<Vec<Tip>> {
+    serde_json::from_str::<Export>(json).map(Export::into_tips)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tip(user: &str, id: i32, score1: i32, score2: i32) -> Tip {
+        Tip { user: user.to_string(), id, score1, score2 }
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let tips = parse_export(r#"[
+            {"user": "ToniKroos", "id": 285418, "score1": 0, "score2": 1},
+            null,
+            {"user": "JohnDoe", "id": 285418, "score1": 1, "score2": 1}
+        ]"#).unwrap();
+
+        assert_eq!(tips, vec![tip("ToniKroos", 285418, 0, 1), tip("JohnDoe", 285418, 1, 1)]);
+    }
+
+    #[test]
+    fn test_parse_pushed_entries() {
+        let tips = parse_export(r#"{"tips": {
+            "-McWq2": {"user": "JohnDoe", "id": 285419, "score1": 3, "score2": 0},
+            "-McWp9": {"user": "ToniKroos", "id": 285418, "score1": 2, "score2": 2}
+        }}"#).unwrap();
+
+        assert_eq!(tips, vec![tip("ToniKroos", 285418, 2, 2), tip("JohnDoe", 285419, 3, 0)]);
+    }
+
+    #[test]
+    fn test_parse_invalid_export() {
+        assert!(parse_export(r#"[{"user": "JohnDoe", "id": 285418}]"#).is_err());
+        assert!(parse_export("not json").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+// Large enough for the matches of a whole tournament.
+const MAX_RESPONSE_SIZE: usize = 8 * 1024 * 1024;
+
+/// Response of football-data.org v4 `/competitions/{code}/matches`.
+/// Only the fields the importer needs are read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchesResponse {
+    pub matches: Vec<Match>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Match {
+    pub id: i32,
+    pub utcDate: String,
+    pub status: String,
+    pub stage: Option<String>,
+    pub group: Option<String>,
+    pub venue: Option<String>,
+    pub homeTeam: Team,
+    pub awayTeam: Team,
+    pub score: Score,
+}
+
+/// Knockout matches list their teams without any data until they are decided.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Team {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub shortName: Option<String>,
+    pub tla: Option<String>,
+    pub crest: Option<String>,
+}
+
+/// `fullTime` is the final score. After a penalty shoot-out it includes the
+/// shoot-out goals, which are also listed on their own in `penalties`.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Score {
+    pub duration: Option<String>,
+    pub fullTime: ScoreDetail,
+    pub extraTime: Option<ScoreDetail>,
+    pub penalties: Option<ScoreDetail>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScoreDetail {
+    pub home: Option<i32>,
+    pub away: Option<i32>,
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(std::io::Error),
+    Http(String),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(error) => write!(f, "Could not read the match data: {}", error),
+            ClientError::Http(error) => write!(f, "Could not fetch the match data: {}", error),
+            ClientError::Json(error) => write!(f, "Invalid match data: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Where the match data comes from: an exported JSON file or an HTTP endpoint
+/// answering like football-data.org (the API itself or a local stand-in).
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchSource {
+    File(PathBuf),
+    Http { url: String, token: Option<String> },
+}
+
+impl MatchSource {
+    /// URLs are fetched over HTTP, everything else is read as a file.
+    pub fn parse(source: &str, token: Option<String>) -> MatchSource {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            MatchSource::Http { url: source.to_string(), token }
+        } else {
+            MatchSource::File(PathBuf::from(source))
+        }
+    }
+
+    pub async fn fetch(&self) -> Result<Vec<Match>, ClientError> {
+        let response: MatchesResponse = match self {
+            MatchSource::File(path) => {
+                let json = std::fs::read_to_string(path).map_err(ClientError::Io)?;
+                serde_json::from_str(&json).map_err(ClientError::Json)?
+            },
+            MatchSource::Http { url, token } => {
+                let mut request = awc::Client::default().get(url);
+                if let Some(token) = token {
+                    request = request.insert_header(("X-Auth-Token", token.as_str()));
+                }
+
+                let mut response = request.send().await.map_err(|error| ClientError::Http(error.to_string()))?;
+                if !response.status().is_success() {
+                    return Err(ClientError::Http(format!("{} returned {}", url, response.status())));
+                }
+
+                let body = response.body().limit(MAX_RESPONSE_SIZE).await.map_err(|error| ClientError::Http(error.to_string()))?;
+                serde_json::from_slice(&body).map_err(ClientError::Json)?
+            },
+        };
+
+        Ok(response.matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{get, App, HttpResponse, HttpServer};
+    use super::*;
+
+    const MATCHES_JSON: &str = r#"{
+        "filters": { "season": "2024" },
+        "matches": [
+            {
+                "id": 428759,
+                "utcDate": "2024-06-16T19:00:00Z",
+                "status": "FINISHED",
+                "stage": "GROUP_STAGE",
+                "group": "GROUP_C",
+                "venue": "Arena AufSchalke",
+                "homeTeam": { "id": 780, "name": "Serbia", "shortName": "Serbia", "tla": "SRB", "crest": "https://crests.football-data.org/780.svg" },
+                "awayTeam": { "id": 770, "name": "England", "shortName": "England", "tla": "ENG", "crest": "https://crests.football-data.org/770.svg" },
+                "score": { "winner": "AWAY_TEAM", "duration": "REGULAR", "fullTime": { "home": 0, "away": 1 }, "halfTime": { "home": 0, "away": 1 } }
+            },
+            {
+                "id": 428800,
+                "utcDate": "2024-07-14T19:00:00Z",
+                "status": "TIMED",
+                "stage": "FINAL",
+                "group": null,
+                "homeTeam": { "id": null, "name": null, "shortName": null, "tla": null, "crest": null },
+                "awayTeam": { "id": null, "name": null, "shortName": null, "tla": null, "crest": null },
+                "score": { "winner": null, "duration": "REGULAR", "fullTime": { "home": null, "away": null }, "halfTime": { "home": null, "away": null } }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_source() {
+        assert_eq!(MatchSource::parse("matches.json", None), MatchSource::File(PathBuf::from("matches.json")));
+        assert_eq!(
+            MatchSource::parse("http://localhost:9000/v4/competitions/EC/matches", Some(String::from("secret"))),
+            MatchSource::Http { url: String::from("http://localhost:9000/v4/competitions/EC/matches"), token: Some(String::from("secret")) },
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_from_file() {
+        let path = std::env::temp_dir().join(format!("em2021_api_matches_{}.json", std::process::id()));
+        std::fs::write(&path, MATCHES_JSON).unwrap();
+
+        let matches = MatchSource::File(path.clone()).fetch().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].id, 428759);
+        assert_eq!(matches[0].homeTeam.tla.as_deref(), Some("SRB"));
+        assert_eq!(matches[0].score.fullTime.away, Some(1));
+        assert_eq!(matches[0].venue.as_deref(), Some("Arena AufSchalke"));
+        assert_eq!(matches[1].venue, None);
+        assert_eq!(matches[1].homeTeam.id, None);
+
+        let missing = MatchSource::File(PathBuf::from("/nonexistent/matches.json")).fetch().await;
+        assert!(matches!(missing, Err(ClientError::Io(_))));
+    }
+
+    #[get("/v4/competitions/EC/matches")]
+    async fn stand_in() -> HttpResponse {
+        HttpResponse::Ok().content_type("application/json").body(MATCHES_JSON)
+    }
+
+    #[actix_web::test]
+    async fn test_fetch_over_http() {
+        let server = HttpServer::new(|| App::new().service(stand_in)).workers(1).bind(("127.0.0.1", 0)).unwrap();
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        let source = MatchSource::parse(&format!("http://{}/v4/competitions/EC/matches", address), Some(String::from("secret")));
+        let matches = source.fetch().await.unwrap();
+        assert_eq!(matches.len(), 2);
+
+        let source = MatchSource::parse(&format!("http://{}/v4/competitions/WC/matches", address), None);
+        assert!(matches!(source.fetch().await, Err(ClientError::Http(_))));
+    }
+}
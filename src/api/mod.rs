@@ -0,0 +1,2 @@
+pub mod firebase;
+pub mod match_client;
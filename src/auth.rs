@@ -0,0 +1,210 @@
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::{web, FromRequest, HttpRequest};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::future::{ready, Ready};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::db::{self, Role};
+use crate::error::ApiError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DEFAULT_TOKEN_TTL: u64 = 30 * 24 * 60 * 60;
+
+/// The key tokens are signed with. Read once at startup and shared as app data.
+#[derive(Clone)]
+pub struct AuthSecret(String);
+
+impl AuthSecret {
+    pub fn new(secret: &str) -> Result<AuthSecret, String> {
+        if secret.trim().is_empty() {
+            return Err(String::from("AUTH_SECRET must not be empty"));
+        }
+
+        Ok(AuthSecret(secret.to_string()))
+    }
+
+    /// Reads `AUTH_SECRET`, which must be set and not empty.
+    pub fn from_env() -> Result<AuthSecret, String> {
+        let secret = env::var("AUTH_SECRET").map_err(|_| String::from("AUTH_SECRET must be set"))?;
+
+        AuthSecret::new(&secret)
+    }
+}
+
+/// The user a request was made by, taken from the `Authorization: Bearer` header.
+#[derive(Debug, Clone, Copy)]
+pub struct AuthUser {
+    pub user_id: i32,
+}
+
+impl FromRequest for AuthUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticate(req))
+    }
+}
+
+/// An authenticated user with the `tournament_admin` role.
+#[derive(Debug, Clone, Copy)]
+pub struct TournamentAdmin(pub AuthUser);
+
+impl FromRequest for TournamentAdmin {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authorize(req, Role::TournamentAdmin).map(TournamentAdmin))
+    }
+}
+
+pub fn has_role(user_id: i32, required: Role) -> bool {
+    matches!(db::get_user_role(user_id), Ok(Some(role)) if role >= required)
+}
+
+fn authenticate(req: &HttpRequest) -> Result<AuthUser, ApiError> {
+    let secret = req.app_data::<web::Data<AuthSecret>>().ok_or_else(ApiError::internal)?;
+
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| verify_token(secret, token))
+        .map(|user_id| AuthUser { user_id })
+        .ok_or_else(ApiError::unauthorized)
+}
+
+fn authorize(req: &HttpRequest, required: Role) -> Result<AuthUser, ApiError> {
+    let auth_user = authenticate(req)?;
+
+    match db::get_user_role(auth_user.user_id) {
+        Ok(Some(role)) if role >= required => Ok(auth_user),
+        Ok(_) => Err(ApiError::forbidden()),
+        Err(_) => Err(ApiError::internal()),
+    }
+}
+
+/// Issues a token of the form `<user_id>.<expires_at>.<hmac>`.
+pub fn issue_token(secret: &AuthSecret, user_id: i32) -> (String, u64) {
+    let expires_at = now() + token_ttl();
+    let payload = format!("{}.{}", user_id, expires_at);
+
+    (format!("{}.{}", payload, sign(secret, &payload)), expires_at)
+}
+
+pub fn verify_token(secret: &AuthSecret, token: &str) -> Option<i32> {
+    let (payload, signature) = token.rsplit_once('.')?;
+    let (user_id, expires_at) = payload.split_once('.')?;
+
+    mac(secret, payload).verify_slice(&hex::decode(signature).ok()?).ok()?;
+
+    if expires_at.parse::<u64>().ok()? < now() {
+        return None;
+    }
+
+    user_id.parse().ok()
+}
+
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default().hash_password(password.as_bytes(), &salt).unwrap().to_string()
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed_hash) => Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn sign(secret: &AuthSecret, payload: &str) -> String {
+    hex::encode(mac(secret, payload).finalize().into_bytes())
+}
+
+fn mac(secret: &AuthSecret, payload: &str) -> HmacSha256 {
+    // HMAC takes keys of any length.
+    let mut mac = HmacSha256::new_from_slice(secret.0.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    mac
+}
+
+fn token_ttl() -> u64 {
+    env::var("AUTH_TOKEN_TTL").ok().and_then(|ttl| ttl.parse().ok()).unwrap_or(DEFAULT_TOKEN_TTL)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret() -> AuthSecret {
+        AuthSecret::new("test-secret").unwrap()
+    }
+
+    #[test]
+    fn test_issue_and_verify_token() {
+        let (token, expires_at) = issue_token(&secret(), 7);
+
+        assert!(expires_at > now());
+        assert!(token.starts_with(&format!("7.{}.", expires_at)));
+        assert_eq!(verify_token(&secret(), &token), Some(7));
+    }
+
+    #[test]
+    fn test_verify_token_rejects_tampering() {
+        let (token, _) = issue_token(&secret(), 7);
+
+        assert_eq!(verify_token(&secret(), &token.replacen('7', "1", 1)), None);
+        assert_eq!(verify_token(&secret(), &format!("{}0", token)), None);
+        assert_eq!(verify_token(&secret(), "7.99999999999.abc"), None);
+        assert_eq!(verify_token(&secret(), "garbage"), None);
+        assert_eq!(verify_token(&AuthSecret::new("other-secret").unwrap(), &token), None);
+    }
+
+    #[test]
+    fn test_verify_token_rejects_expired_token() {
+        let payload = format!("7.{}", now() - 1);
+        let token = format!("{}.{}", payload, sign(&secret(), &payload));
+
+        assert_eq!(verify_token(&secret(), &token), None);
+    }
+
+    #[test]
+    fn test_auth_secret_must_not_be_empty() {
+        assert!(AuthSecret::new("").is_err());
+        assert!(AuthSecret::new("  ").is_err());
+        assert!(AuthSecret::new("change-me").is_ok());
+    }
+
+    #[test]
+    fn test_has_role() {
+        env::set_var("MODE", "test");
+
+        assert!(has_role(2, Role::TournamentAdmin));
+        assert!(has_role(2, Role::LeagueAdmin));
+        assert!(has_role(7, Role::LeagueAdmin));
+        assert!(!has_role(7, Role::TournamentAdmin));
+        assert!(!has_role(1, Role::LeagueAdmin));
+        assert!(!has_role(99, Role::Player));
+    }
+
+    #[test]
+    fn test_hash_and_verify_password() {
+        let password_hash = hash_password("secret");
+
+        assert!(verify_password("secret", &password_hash));
+        assert!(!verify_password("wrong", &password_hash));
+        assert!(!verify_password("secret", "not-a-hash"));
+    }
+}
@@ -0,0 +1,85 @@
+//! Imports the tips of the 2021 pool from its Firebase JSON export into the
+//! database at `DATABASE_URL`, as the tournament `em2021`:
+//!
+//!     cargo run --bin import_firebase_tips -- tips.json matches-2021.json
+//!     cargo run --bin import_firebase_tips -- tips.json https://api.football-data.org/v4/competitions/EC/matches?season=2020
+//!
+//! The matches (football-data.org v4 format) are imported first, since the
+//! tips refer to them by id. Users are matched by username; tips of unknown
+//! users or matches are listed as unmatched. Prints what changed as JSON.
+
+use serde_json::json;
+use std::env;
+use std::process::ExitCode;
+use em2021_api::api::firebase;
+use em2021_api::api::match_client::MatchSource;
+use em2021_api::service::ranking::RuleSet;
+use em2021_api::{db, service};
+
+const TOURNAMENT_SLUG: &str = "em2021";
+const TOURNAMENT_NAME: &str = "EURO 2020";
+const TOURNAMENT_RULE_SET: RuleSet = RuleSet::Classic2021;
+
+#[actix_web::main]
+async fn main() -> ExitCode {
+    dotenv::dotenv().ok();
+
+    if let Err(error) = db::migrate() {
+        eprintln!("Could not migrate the database: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    let (tips_path, source) = match (env::args().nth(1), env::args().nth(2)) {
+        (Some(tips_path), Some(source)) => (tips_path, MatchSource::parse(&source, env::var("FOOTBALL_DATA_TOKEN").ok())),
+        _ => {
+            eprintln!("Usage: import_firebase_tips <tips export> <matches file or url>");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let tips = match std::fs::read_to_string(&tips_path).map(|json| firebase::parse_export(&json)) {
+        Ok(Ok(tips)) => tips,
+        Ok(Err(error)) => {
+            eprintln!("Invalid Firebase export: {}", error);
+            return ExitCode::FAILURE;
+        },
+        Err(error) => {
+            eprintln!("Could not read the Firebase export: {}", error);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let matches = match source.fetch().await {
+        Ok(matches) => matches,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let (teams, match_records) = match service::import::to_records(&matches) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("Invalid kickoff time: {}", error);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let imported = db::tournaments::ensure_tournament(TOURNAMENT_SLUG, TOURNAMENT_NAME, TOURNAMENT_RULE_SET)
+        .and_then(|tournament| {
+            let match_report = db::matches::import_matches(tournament.id, &teams, &match_records)?;
+            let tip_report = db::tournaments::import_tips(tournament.id, &service::import::to_tip_records(&tips))?;
+            Ok((match_report, tip_report))
+        });
+
+    match imported {
+        Ok((match_report, tip_report)) => {
+            println!("{}", serde_json::to_string_pretty(&json!({ "matches": match_report, "tips": tip_report })).unwrap());
+            ExitCode::SUCCESS
+        },
+        Err(error) => {
+            eprintln!("Import failed: {}", error);
+            ExitCode::FAILURE
+        },
+    }
+}
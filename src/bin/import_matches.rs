@@ -0,0 +1,77 @@
+//! Imports fixtures and results in the football-data.org v4 format into the
+//! database at `DATABASE_URL`:
+//!
+//!     cargo run --bin import_matches -- matches.json
+//!     cargo run --bin import_matches -- https://api.football-data.org/v4/competitions/EC/matches
+//!     cargo run --bin import_matches -- matches-2022.json wm2022
+//!
+//! The matches go to the tournament with the given slug, or to the default
+//! tournament.
+//! `FOOTBALL_DATA_TOKEN` is sent as `X-Auth-Token` when fetching over HTTP.
+//! Prints what changed as JSON.
+
+use std::env;
+use std::process::ExitCode;
+use em2021_api::api::match_client::MatchSource;
+use em2021_api::{db, service};
+
+#[actix_web::main]
+async fn main() -> ExitCode {
+    dotenv::dotenv().ok();
+
+    if let Err(error) = db::migrate() {
+        eprintln!("Could not migrate the database: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    let source = match env::args().nth(1) {
+        Some(source) => MatchSource::parse(&source, env::var("FOOTBALL_DATA_TOKEN").ok()),
+        None => {
+            eprintln!("Usage: import_matches <file or url> [tournament slug]");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let tournament = match env::args().nth(2) {
+        Some(slug) => db::tournaments::get_tournament(&slug).map(|tournament| tournament.ok_or(slug)),
+        None => db::tournaments::get_default_tournament().map(Ok),
+    };
+    let tournament = match tournament {
+        Ok(Ok(tournament)) => tournament,
+        Ok(Err(slug)) => {
+            eprintln!("Unknown tournament: {}", slug);
+            return ExitCode::FAILURE;
+        },
+        Err(error) => {
+            eprintln!("Could not read the tournament: {}", error);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let matches = match source.fetch().await {
+        Ok(matches) => matches,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let (teams, match_records) = match service::import::to_records(&matches) {
+        Ok(records) => records,
+        Err(error) => {
+            eprintln!("Invalid kickoff time: {}", error);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    match db::matches::import_matches(tournament.id, &teams, &match_records) {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            ExitCode::SUCCESS
+        },
+        Err(error) => {
+            eprintln!("Import failed: {}", error);
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -0,0 +1,144 @@
+use rusqlite::{Connection, OptionalExtension, params, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::establish_connection;
+
+/// A match as listed in the calendar, played or not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarGame {
+    pub id: i32,
+    pub home_team: String,
+    pub away_team: String,
+    pub stage: Option<String>,
+    pub group: Option<String>,
+    pub venue: Option<String>,
+    pub status: String,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+    pub date: u64,
+}
+
+/// All matches of the tournament by kickoff.
+pub fn get_calendar_games(tournament_id: i32) -> SqliteResult<Vec<CalendarGame>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, homeTeam, awayTeam, stage, groupName, venue, status, homeScore, awayScore, utcDate
+         FROM match WHERE tournament_id = ?1 ORDER BY utcDate, id",
+    )?;
+
+    let game_iter = stmt.query_map([tournament_id], |row| {
+        Ok(CalendarGame {
+            id: row.get(0)?,
+            home_team: row.get(1)?,
+            away_team: row.get(2)?,
+            stage: row.get(3)?,
+            group: row.get(4)?,
+            venue: row.get(5)?,
+            status: row.get(6)?,
+            home_score: row.get(7)?,
+            away_score: row.get(8)?,
+            date: row.get(9)?,
+        })
+    })?;
+
+    game_iter.collect()
+}
+
+/// The calendar token of the user. The first call creates it.
+pub fn get_calendar_token(user_id: i32) -> SqliteResult<String> {
+    let conn = establish_connection()?;
+
+    calendar_token(&conn, user_id)
+}
+
+/// Replaces the calendar token of the user, so the old feed URL stops working.
+pub fn reset_calendar_token(user_id: i32) -> SqliteResult<String> {
+    let conn = establish_connection()?;
+
+    replace_calendar_token(&conn, user_id)
+}
+
+/// The user a calendar token belongs to.
+pub fn find_calendar_user(token: &str) -> SqliteResult<Option<i32>> {
+    let conn = establish_connection()?;
+
+    calendar_user(&conn, token)
+}
+
+fn calendar_token(conn: &Connection, user_id: i32) -> SqliteResult<String> {
+    let token: Option<String> = conn.query_row(
+        "SELECT token FROM calendar_token WHERE user_id = ?1",
+        [user_id],
+        |row| row.get(0),
+    ).optional()?;
+
+    match token {
+        Some(token) => Ok(token),
+        None => replace_calendar_token(conn, user_id),
+    }
+}
+
+fn replace_calendar_token(conn: &Connection, user_id: i32) -> SqliteResult<String> {
+    let token = hex::encode(rand::random::<[u8; 16]>());
+
+    conn.execute(
+        "INSERT INTO calendar_token (user_id, token, created_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(user_id) DO UPDATE SET token = excluded.token, created_at = excluded.created_at",
+        params![user_id, token, now()],
+    )?;
+
+    Ok(token)
+}
+
+fn calendar_user(conn: &Connection, token: &str) -> SqliteResult<Option<i32>> {
+    conn.query_row(
+        "SELECT user_id FROM calendar_token WHERE token = ?1",
+        [token],
+        |row| row.get(0),
+    ).optional()
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::db::DEFAULT_TOURNAMENT_ID;
+    use super::*;
+
+    #[test]
+    fn test_get_calendar_games() {
+        env::set_var("MODE", "test");
+        let games = get_calendar_games(DEFAULT_TOURNAMENT_ID).unwrap();
+
+        assert_eq!(games.len(), 6);
+        assert!(games.windows(2).all(|pair| pair[0].date <= pair[1].date));
+
+        let game = games.iter().find(|game| game.id == 3).unwrap();
+        assert_eq!(game.venue.as_deref(), Some("Düsseldorf Arena"));
+        assert_eq!(game.group.as_deref(), Some("GROUP_B"));
+        assert_eq!(game.home_score, None);
+
+        assert_eq!(get_calendar_games(2).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_calendar_token() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        let token = calendar_token(&conn, 1).unwrap();
+        assert_eq!(token.len(), 32);
+        assert_eq!(calendar_token(&conn, 1).unwrap(), token);
+        assert_ne!(calendar_token(&conn, 2).unwrap(), token);
+        assert_eq!(calendar_user(&conn, &token).unwrap(), Some(1));
+
+        let new_token = replace_calendar_token(&conn, 1).unwrap();
+        assert_ne!(new_token, token);
+        assert_eq!(calendar_user(&conn, &token).unwrap(), None);
+        assert_eq!(calendar_user(&conn, &new_token).unwrap(), Some(1));
+    }
+}
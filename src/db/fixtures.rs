@@ -1,9 +1,11 @@
 use rusqlite::{Connection, params};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
-use serde_json;
 use crate::service::Team;
 
+// Argon2 hash of "password" with cheap parameters, shared by all fixture users.
+const PASSWORD_HASH: &str = "$argon2id$v=19$m=1024,t=1,p=1$8uvSaoXIdiPoHogOBu7e5Q$rnMf3EXjCUYwR7vcWj3Iej81EqagKDTcAFBYy6ZDe8c";
+
 #[derive(Debug)]
 struct DbUser {
     email: String,
@@ -13,6 +15,7 @@ struct DbUser {
     department: String,
     winner: String,
     secret_winner: String,
+    role: String,
 }
 
 #[derive(Debug)]
@@ -21,11 +24,22 @@ struct DbGame {
     home_team: String,
     away_team: String,
     status: String,
+    stage: String,
+    group: Option<String>,
+    venue: Option<String>,
     utc_date: u64,
     home_score: Option<i32>,
     away_score: Option<i32>,
 }
 
+#[derive(Debug)]
+struct DbLeagueMember {
+    league_id: i32,
+    user_id: i32,
+    role: String,
+    joined_at: u64,
+}
+
 #[derive(Debug)]
 struct DbTip {
     user_id: i32,
@@ -37,7 +51,8 @@ struct DbTip {
 
 pub fn load_fixtures(conn: &Connection) {
 
-    create_tables(&conn).unwrap();
+    create_tables(conn).unwrap();
+    super::migrations::run_migrations(conn).unwrap();
 
     let users = get_users();
 
@@ -48,18 +63,84 @@ pub fn load_fixtures(conn: &Connection) {
         ("fr", Team { name: String::from("France"), tla: String::from("FRA") }),
         ("de", Team { name: String::from("Germany"), tla: String::from("GER") }),
         ("es", Team { name: String::from("Spain"), tla: String::from("ESP") }),
+        ("ar", Team { name: String::from("Argentina"), tla: String::from("ARG") }),
     ].iter().cloned().collect();
 
     let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
 
     let games = get_games(&lands, now);
 
-    insert_users(&conn, &users).unwrap();
-    insert_games(&conn, &games).unwrap();
+    insert_users(conn, &users).unwrap();
+    insert_games(conn, &games).unwrap();
 
     let tips = get_tips(now);
 
-    insert_tips(&conn, &tips).unwrap();
+    insert_tips(conn, &tips).unwrap();
+
+    conn.execute(
+        "INSERT INTO league (id, name, invite_code, owner_id, created_at) VALUES (1, 'Kabinenfunk', 'KABINE24', 2, ?1)",
+        params![now - 7 * 86400],
+    ).unwrap();
+
+    insert_league_members(conn, &get_league_members(now)).unwrap();
+
+    conn.execute(
+        "INSERT INTO role_audit (user_id, old_role, new_role, changed_by, changed_at) VALUES (7, 'player', 'league_admin', 2, ?1)",
+        params![now - 3 * 86400],
+    ).unwrap();
+
+    conn.execute(
+        "INSERT INTO calendar_token (user_id, token, created_at) VALUES (1, 'c4l3nd4rc4l3nd4rc4l3nd4rc4l3nd4r', ?1)",
+        params![now - 86400],
+    ).unwrap();
+
+    insert_past_tournament(conn, &lands, now).unwrap();
+}
+
+// A finished tournament besides the default one, with its final, two tips and
+// a champion pick.
+fn insert_past_tournament(conn: &Connection, lands: &HashMap<&str, Team>, now: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO tournament (id, slug, name, rule_set, champion) VALUES (2, 'wm2022', 'World Cup 2022', 'em2024', 'ARG')",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO match (id, homeTeam, awayTeam, status, stage, utcDate, homeScore, awayScore, tournament_id) VALUES (7, ?1, ?2, 'FINISHED', 'FINAL', ?3, 3, 3, 2)",
+        params![serde_json::to_string(&lands["ar"]).unwrap(), serde_json::to_string(&lands["fr"]).unwrap(), now - 600 * 86400],
+    )?;
+    insert_tips(conn, &[
+        DbTip { user_id: 2, match_id: 7, date: now - 601 * 86400, score_home: 2, score_away: 2 },
+        DbTip { user_id: 1, match_id: 7, date: now - 601 * 86400, score_home: 3, score_away: 3 },
+    ])?;
+    conn.execute(
+        "INSERT INTO champion_pick (tournament_id, user_id, winner, secret_winner) VALUES (2, 2, 'ARG', 'FRA')",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn get_league_members(now: u64) -> Vec<DbLeagueMember> {
+    vec![
+        DbLeagueMember {
+            league_id: 1,
+            user_id: 2,
+            role: String::from("owner"),
+            joined_at: now - 7 * 86400,
+        },
+        DbLeagueMember {
+            league_id: 1,
+            user_id: 1,
+            role: String::from("admin"),
+            joined_at: now - 6 * 86400,
+        },
+        DbLeagueMember {
+            league_id: 1,
+            user_id: 5,
+            role: String::from("member"),
+            joined_at: now - 5 * 86400,
+        },
+    ]
 }
 
 fn get_tips(now: u64) -> Vec<DbTip> {
@@ -141,6 +222,13 @@ fn get_tips(now: u64) -> Vec<DbTip> {
             score_home: 2,
             score_away: 4,
         },
+        DbTip {
+            user_id: 4,
+            match_id: 6,
+            date: now - 86400,
+            score_home: 1,
+            score_away: 0,
+        },
     ];
     tpis
 }
@@ -152,6 +240,9 @@ fn get_games(lands: &HashMap<&str, Team>, now: u64) -> Vec<DbGame> {
             home_team: serde_json::to_string(&lands["de"].clone()).unwrap(),
             away_team: serde_json::to_string(&lands["es"].clone()).unwrap(),
             status: String::from("scheduled"),
+            stage: String::from("GROUP_STAGE"),
+            group: Some(String::from("GROUP_A")),
+            venue: Some(String::from("Munich Football Arena")),
             utc_date: now - 86400, // 1 Tag vorher
             home_score: Some(2),
             away_score: Some(0),
@@ -161,6 +252,9 @@ fn get_games(lands: &HashMap<&str, Team>, now: u64) -> Vec<DbGame> {
             home_team: serde_json::to_string(&lands["pl"].clone()).unwrap(),
             away_team: serde_json::to_string(&lands["fr"].clone()).unwrap(),
             status: String::from("scheduled"),
+            stage: String::from("GROUP_STAGE"),
+            group: Some(String::from("GROUP_A")),
+            venue: Some(String::from("BVB Stadion Dortmund")),
             utc_date: now - 1800, // 30 Minuten vorher
             home_score: Some(1),
             away_score: Some(1),
@@ -170,6 +264,9 @@ fn get_games(lands: &HashMap<&str, Team>, now: u64) -> Vec<DbGame> {
             home_team: serde_json::to_string(&lands["en"].clone()).unwrap(),
             away_team: serde_json::to_string(&lands["nl"].clone()).unwrap(),
             status: String::from("scheduled"),
+            stage: String::from("GROUP_STAGE"),
+            group: Some(String::from("GROUP_B")),
+            venue: Some(String::from("Düsseldorf Arena")),
             utc_date: now + 3600, // 1 Stunde später
             home_score: None,
             away_score: None,
@@ -179,6 +276,9 @@ fn get_games(lands: &HashMap<&str, Team>, now: u64) -> Vec<DbGame> {
             home_team: serde_json::to_string(&lands["fr"].clone()).unwrap(),
             away_team: serde_json::to_string(&lands["de"].clone()).unwrap(),
             status: String::from("scheduled"),
+            stage: String::from("GROUP_STAGE"),
+            group: Some(String::from("GROUP_A")),
+            venue: Some(String::from("Olympiastadion Berlin")),
             utc_date: now + 86400, // 1 Tag später
             home_score: None,
             away_score: None,
@@ -188,10 +288,25 @@ fn get_games(lands: &HashMap<&str, Team>, now: u64) -> Vec<DbGame> {
             home_team: serde_json::to_string(&lands["en"].clone()).unwrap(),
             away_team: serde_json::to_string(&lands["pl"].clone()).unwrap(),
             status: String::from("scheduled"),
+            stage: String::from("LAST_16"),
+            group: None,
+            venue: None,
             utc_date: now + (30 * 24 * 60 * 60), // 1 Monat später
             home_score: None,
             away_score: None,
         },
+        DbGame {
+            id: 6,
+            home_team: serde_json::to_string(&lands["nl"].clone()).unwrap(),
+            away_team: serde_json::to_string(&lands["es"].clone()).unwrap(),
+            status: String::from("IN_PLAY"),
+            stage: String::from("LAST_16"),
+            group: None,
+            venue: Some(String::from("Frankfurt Arena")),
+            utc_date: now - 3000, // läuft seit 50 Minuten
+            home_score: Some(1),
+            away_score: Some(0),
+        },
     ];
     games
 }
@@ -206,6 +321,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("Langenfeld"),
             winner: String::from("DEU"),
             secret_winner: String::from("ESP"),
+            role: String::from("player"),
         },
         DbUser {
             email: String::from("toni@kroos.de"),
@@ -215,6 +331,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("Langenfeld"),
             winner: String::from("ESP"),
             secret_winner: String::from("DEU"),
+            role: String::from("tournament_admin"),
         },
         DbUser {
             email: String::from("philipp@lahm.de"),
@@ -224,6 +341,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("Langenfeld"),
             winner: String::from("ENG"),
             secret_winner: String::from("ESP"),
+            role: String::from("player"),
         },
         DbUser {
             email: String::from("lukas@podolski.pl"),
@@ -233,6 +351,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("Langenfeld"),
             winner: String::from("POL"),
             secret_winner: String::from("DEU"),
+            role: String::from("player"),
         },
         DbUser {
             email: String::from("robbie@fowler.com"),
@@ -242,6 +361,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("London"),
             winner: String::from("NLD"),
             secret_winner: String::from("ESP"),
+            role: String::from("player"),
         },
         DbUser {
             email: String::from("bobby@moore.com"),
@@ -251,6 +371,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("London"),
             winner: String::from("ENG"),
             secret_winner: String::from("DEU"),
+            role: String::from("player"),
         },
         DbUser {
             email: String::from("steve@mcmanaman.com"),
@@ -260,6 +381,7 @@ fn get_users() -> Vec<DbUser> {
             department: String::from("London"),
             winner: String::from("FRA"),
             secret_winner: String::from("ENG"),
+            role: String::from("league_admin"),
         },
     ];
     users
@@ -310,8 +432,8 @@ fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
 fn insert_users(conn: &Connection, users: &[DbUser]) -> rusqlite::Result<()> {
     for user in users {
         conn.execute(
-            "INSERT INTO user (email, first_name, last_name, username, department, winner, secretWinner) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![user.email, user.first_name, user.last_name, user.username, user.department, user.winner, user.secret_winner],
+            "INSERT INTO user (email, first_name, last_name, username, department, winner, secretWinner, password_hash, role) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![user.email, user.first_name, user.last_name, user.username, user.department, user.winner, user.secret_winner, PASSWORD_HASH, user.role],
         )?;
     }
     Ok(())
@@ -320,8 +442,8 @@ fn insert_users(conn: &Connection, users: &[DbUser]) -> rusqlite::Result<()> {
 fn insert_games(conn: &Connection, games: &[DbGame]) -> rusqlite::Result<()> {
     for game in games {
         conn.execute(
-            "INSERT INTO match (id, homeTeam, awayTeam, status, utcDate, homeScore, awayScore) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![game.id, game.home_team, game.away_team, game.status, game.utc_date, game.home_score, game.away_score],
+            "INSERT INTO match (id, homeTeam, awayTeam, status, stage, groupName, venue, utcDate, homeScore, awayScore) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![game.id, game.home_team, game.away_team, game.status, game.stage, game.group, game.venue, game.utc_date, game.home_score, game.away_score],
         )?;
     }
     Ok(())
@@ -336,3 +458,13 @@ fn insert_tips(conn: &Connection, tips: &[DbTip]) -> rusqlite::Result<()> {
     }
     Ok(())
 }
+
+fn insert_league_members(conn: &Connection, members: &[DbLeagueMember]) -> rusqlite::Result<()> {
+    for member in members {
+        conn.execute(
+            "INSERT INTO league_member (league_id, user_id, role, joined_at) VALUES (?1, ?2, ?3, ?4)",
+            params![member.league_id, member.user_id, member.role, member.joined_at],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use rand::distributions::Slice;
+use rand::Rng;
+use rusqlite::{Connection, OptionalExtension, params, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::establish_connection;
+
+const INVITE_CODE_CHARS: [char; 32] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'J', 'K', 'L', 'M', 'N', 'P', 'Q', 'R',
+    'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '2', '3', '4', '5', '6', '7', '8', '9',
+];
+const INVITE_CODE_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeagueRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl LeagueRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeagueRole::Owner => "owner",
+            LeagueRole::Admin => "admin",
+            LeagueRole::Member => "member",
+        }
+    }
+
+    pub fn can_manage_members(&self) -> bool {
+        matches!(self, LeagueRole::Owner | LeagueRole::Admin)
+    }
+
+    fn from_db(role: &str) -> LeagueRole {
+        match role {
+            "owner" => LeagueRole::Owner,
+            "admin" => LeagueRole::Admin,
+            _ => LeagueRole::Member,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct League {
+    pub id: i32,
+    pub tournament_id: i32,
+    pub name: String,
+    pub invite_code: String,
+    pub owner_id: i32,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeagueMember {
+    pub user_id: i32,
+    pub username: String,
+    pub role: LeagueRole,
+    pub joined_at: u64,
+}
+
+pub fn create_league(tournament_id: i32, name: &str, owner_id: i32) -> SqliteResult<League> {
+    let conn = establish_connection()?;
+    insert_league(&conn, tournament_id, name, owner_id)
+}
+
+/// The league if it belongs to the tournament.
+pub fn get_league(tournament_id: i32, league_id: i32) -> SqliteResult<Option<League>> {
+    let conn = establish_connection()?;
+    find_league(&conn, tournament_id, "id = ?2", league_id)
+}
+
+pub fn get_league_members(league_id: i32) -> SqliteResult<Vec<LeagueMember>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT league_member.user_id, user.username, league_member.role, league_member.joined_at
+         FROM league_member INNER JOIN user ON user.id = league_member.user_id
+         WHERE league_member.league_id = ?1
+         ORDER BY league_member.joined_at, league_member.user_id"
+    )?;
+
+    let member_iter = stmt.query_map([league_id], |row| {
+        let role: String = row.get(2)?;
+        Ok(LeagueMember {
+            user_id: row.get(0)?,
+            username: row.get(1)?,
+            role: LeagueRole::from_db(&role),
+            joined_at: row.get(3)?,
+        })
+    })?;
+
+    let mut member_list = Vec::new();
+    for member in member_iter {
+        member_list.push(member?);
+    }
+
+    Ok(member_list)
+}
+
+/// Adds the user to the league behind the invite code. Joining twice keeps the
+/// existing membership and role. Returns `None` for a code that is unknown in
+/// the tournament.
+pub fn join_league(tournament_id: i32, invite_code: &str, user_id: i32) -> SqliteResult<Option<League>> {
+    let conn = establish_connection()?;
+
+    let league = match find_league(&conn, tournament_id, "invite_code = ?2", invite_code.trim().to_uppercase())? {
+        Some(league) => league,
+        None => return Ok(None),
+    };
+
+    conn.execute(
+        "INSERT OR IGNORE INTO league_member (league_id, user_id, role, joined_at) VALUES (?1, ?2, ?3, ?4)",
+        params![league.id, user_id, LeagueRole::Member.as_str(), now()],
+    )?;
+
+    Ok(Some(league))
+}
+
+pub fn remove_league_member(league_id: i32, user_id: i32) -> SqliteResult<bool> {
+    let conn = establish_connection()?;
+
+    let removed = conn.execute(
+        "DELETE FROM league_member WHERE league_id = ?1 AND user_id = ?2",
+        params![league_id, user_id],
+    )?;
+
+    Ok(removed > 0)
+}
+
+pub fn set_league_member_role(league_id: i32, user_id: i32, role: LeagueRole) -> SqliteResult<bool> {
+    let conn = establish_connection()?;
+
+    let updated = conn.execute(
+        "UPDATE league_member SET role = ?3 WHERE league_id = ?1 AND user_id = ?2",
+        params![league_id, user_id, role.as_str()],
+    )?;
+
+    Ok(updated > 0)
+}
+
+fn insert_league(conn: &Connection, tournament_id: i32, name: &str, owner_id: i32) -> SqliteResult<League> {
+    let tx = conn.unchecked_transaction()?;
+    let created_at = now();
+
+    // Invite codes are short enough to type, so retry on the rare collision.
+    let invite_code = loop {
+        let invite_code = generate_invite_code();
+        let taken: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM league WHERE invite_code = ?1)",
+            [&invite_code],
+            |row| row.get(0),
+        )?;
+
+        if !taken {
+            break invite_code;
+        }
+    };
+
+    tx.execute(
+        "INSERT INTO league (name, invite_code, owner_id, created_at, tournament_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![name, invite_code, owner_id, created_at, tournament_id],
+    )?;
+    let league_id = tx.last_insert_rowid() as i32;
+
+    tx.execute(
+        "INSERT INTO league_member (league_id, user_id, role, joined_at) VALUES (?1, ?2, ?3, ?4)",
+        params![league_id, owner_id, LeagueRole::Owner.as_str(), created_at],
+    )?;
+    tx.commit()?;
+
+    Ok(League {
+        id: league_id,
+        tournament_id,
+        name: name.to_string(),
+        invite_code,
+        owner_id,
+        created_at,
+    })
+}
+
+fn find_league<P: rusqlite::ToSql>(conn: &Connection, tournament_id: i32, condition: &str, value: P) -> SqliteResult<Option<League>> {
+    conn.query_row(
+        &format!("SELECT id, tournament_id, name, invite_code, owner_id, created_at FROM league WHERE tournament_id = ?1 AND {}", condition),
+        params![tournament_id, value],
+        |row| {
+            Ok(League {
+                id: row.get(0)?,
+                tournament_id: row.get(1)?,
+                name: row.get(2)?,
+                invite_code: row.get(3)?,
+                owner_id: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        },
+    ).optional()
+}
+
+fn generate_invite_code() -> String {
+    let chars = Slice::new(&INVITE_CODE_CHARS).unwrap();
+    rand::thread_rng().sample_iter(&chars).take(INVITE_CODE_LENGTH).collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::db::DEFAULT_TOURNAMENT_ID;
+    use super::*;
+
+    #[test]
+    fn test_get_league() {
+        env::set_var("MODE", "test");
+        let league = get_league(DEFAULT_TOURNAMENT_ID, 1).unwrap().unwrap();
+
+        assert_eq!(league.name, "Kabinenfunk");
+        assert_eq!(league.invite_code, "KABINE24");
+        assert_eq!(league.owner_id, 2);
+
+        assert!(get_league(DEFAULT_TOURNAMENT_ID, 99).unwrap().is_none());
+        assert!(get_league(2, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_league_members() {
+        env::set_var("MODE", "test");
+        let members = get_league_members(1).unwrap();
+
+        assert_eq!(members.len(), 3);
+        assert_eq!(members[0].user_id, 2);
+        assert_eq!(members[0].username, "ToniKroos");
+        assert_eq!(members[0].role, LeagueRole::Owner);
+        assert_eq!(members[1].user_id, 1);
+        assert_eq!(members[1].role, LeagueRole::Admin);
+        assert_eq!(members[2].user_id, 5);
+        assert_eq!(members[2].role, LeagueRole::Member);
+    }
+
+    #[test]
+    fn test_join_league() {
+        env::set_var("MODE", "test");
+
+        let league = join_league(DEFAULT_TOURNAMENT_ID, " kabine24 ", 3).unwrap().unwrap();
+        assert_eq!(league.id, 1);
+
+        assert!(join_league(DEFAULT_TOURNAMENT_ID, "UNKNOWN", 3).unwrap().is_none());
+        assert!(join_league(2, "KABINE24", 3).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_insert_league_adds_owner() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        let league = insert_league(&conn, 2, "Friends", 4).unwrap();
+        assert_eq!(league.tournament_id, 2);
+        assert_eq!(league.name, "Friends");
+        assert_eq!(league.owner_id, 4);
+        assert_eq!(league.invite_code.len(), INVITE_CODE_LENGTH);
+
+        let role: String = conn.query_row(
+            "SELECT role FROM league_member WHERE league_id = ?1 AND user_id = 4",
+            [league.id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(role, "owner");
+    }
+
+    #[test]
+    fn test_invite_code_chars() {
+        // Letters and digits without the look-alikes 0, 1, I and O.
+        let expected: Vec<char> = ('A'..='Z').chain('2'..='9').filter(|c| *c != 'I' && *c != 'O').collect();
+
+        assert_eq!(INVITE_CODE_CHARS.to_vec(), expected);
+    }
+}
@@ -0,0 +1,266 @@
+use rusqlite::{Connection, OptionalExtension, params, Result as SqliteResult, ToSql};
+use serde::{Deserialize, Serialize};
+use super::establish_connection;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamRecord {
+    pub id: i32,
+    pub name: String,
+    pub short_name: Option<String>,
+    pub tla: String,
+    pub crest: Option<String>,
+}
+
+/// A match as stored in the `match` table. Teams are the JSON of a `service::Team`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchRecord {
+    pub id: i32,
+    pub home_team: String,
+    pub away_team: String,
+    pub status: String,
+    pub stage: Option<String>,
+    pub group: Option<String>,
+    pub venue: Option<String>,
+    pub utc_date: u64,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+    pub duration: Option<String>,
+    pub home_score_extra_time: Option<i32>,
+    pub away_score_extra_time: Option<i32>,
+    pub home_penalties: Option<i32>,
+    pub away_penalties: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub teams_inserted: usize,
+    pub teams_updated: usize,
+    pub matches_inserted: usize,
+    pub matches_updated: usize,
+    pub matches_unchanged: usize,
+    /// Matches whose score or status changed, in the order they were imported.
+    pub results_changed: Vec<i32>,
+    /// Matches that belong to another tournament and were left as they are.
+    pub matches_skipped: Vec<i32>,
+}
+
+/// Inserts or updates teams and matches by their id in one transaction and
+/// assigns the matches to the tournament. Importing the same data again changes nothing.
+/// A match id that belongs to another tournament is skipped.
+pub fn import_matches(tournament_id: i32, teams: &[TeamRecord], matches: &[MatchRecord]) -> SqliteResult<ImportReport> {
+    let conn = establish_connection()?;
+
+    upsert_matches(&conn, tournament_id, teams, matches)
+}
+
+pub(crate) fn upsert_matches(conn: &Connection, tournament_id: i32, teams: &[TeamRecord], matches: &[MatchRecord]) -> SqliteResult<ImportReport> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = ImportReport::default();
+
+    for team in teams {
+        let existing = tx.query_row(
+            "SELECT id, name, short_name, tla, crest FROM team WHERE id = ?1",
+            [team.id],
+            |row| {
+                Ok(TeamRecord {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    short_name: row.get(2)?,
+                    tla: row.get(3)?,
+                    crest: row.get(4)?,
+                })
+            },
+        ).optional()?;
+
+        match existing {
+            Some(existing) if existing == *team => {},
+            Some(_) => {
+                tx.execute(
+                    "UPDATE team SET name = ?2, short_name = ?3, tla = ?4, crest = ?5 WHERE id = ?1",
+                    params![team.id, team.name, team.short_name, team.tla, team.crest],
+                )?;
+                report.teams_updated += 1;
+            },
+            None => {
+                tx.execute(
+                    "INSERT INTO team (id, name, short_name, tla, crest) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![team.id, team.name, team.short_name, team.tla, team.crest],
+                )?;
+                report.teams_inserted += 1;
+            },
+        }
+    }
+
+    for game in matches {
+        let existing = tx.query_row(
+            "SELECT id, homeTeam, awayTeam, status, stage, groupName, utcDate, homeScore, awayScore, duration,
+                    homeScoreExtraTime, awayScoreExtraTime, homePenalties, awayPenalties, venue, tournament_id
+             FROM match WHERE id = ?1",
+            [game.id],
+            |row| {
+                Ok((row.get::<_, i32>(15)?, MatchRecord {
+                    id: row.get(0)?,
+                    home_team: row.get(1)?,
+                    away_team: row.get(2)?,
+                    status: row.get(3)?,
+                    stage: row.get(4)?,
+                    group: row.get(5)?,
+                    venue: row.get(14)?,
+                    utc_date: row.get(6)?,
+                    home_score: row.get(7)?,
+                    away_score: row.get(8)?,
+                    duration: row.get(9)?,
+                    home_score_extra_time: row.get(10)?,
+                    away_score_extra_time: row.get(11)?,
+                    home_penalties: row.get(12)?,
+                    away_penalties: row.get(13)?,
+                }))
+            },
+        ).optional()?;
+
+        match existing {
+            Some((owner, _)) if owner != tournament_id => report.matches_skipped.push(game.id),
+            Some((_, existing)) if existing == *game => report.matches_unchanged += 1,
+            Some((_, existing)) => {
+                tx.execute(
+                    "UPDATE match SET homeTeam = ?2, awayTeam = ?3, status = ?4, stage = ?5, groupName = ?6, utcDate = ?7,
+                        homeScore = ?8, awayScore = ?9, duration = ?10, homeScoreExtraTime = ?11, awayScoreExtraTime = ?12,
+                        homePenalties = ?13, awayPenalties = ?14, venue = ?16
+                     WHERE id = ?1 AND tournament_id = ?15",
+                    &match_params(game, &tournament_id)[..],
+                )?;
+                report.matches_updated += 1;
+
+                if existing.status != game.status || existing.home_score != game.home_score || existing.away_score != game.away_score {
+                    report.results_changed.push(game.id);
+                }
+            },
+            None => {
+                tx.execute(
+                    "INSERT INTO match (id, homeTeam, awayTeam, status, stage, groupName, utcDate, homeScore, awayScore, duration,
+                        homeScoreExtraTime, awayScoreExtraTime, homePenalties, awayPenalties, tournament_id, venue)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                    &match_params(game, &tournament_id)[..],
+                )?;
+                report.matches_inserted += 1;
+
+                if game.home_score.is_some() {
+                    report.results_changed.push(game.id);
+                }
+            },
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(report)
+}
+
+fn match_params<'a>(game: &'a MatchRecord, tournament_id: &'a i32) -> [&'a dyn ToSql; 16] {
+    [
+        &game.id, &game.home_team, &game.away_team, &game.status, &game.stage, &game.group, &game.utc_date,
+        &game.home_score, &game.away_score, &game.duration, &game.home_score_extra_time, &game.away_score_extra_time,
+        &game.home_penalties, &game.away_penalties, tournament_id, &game.venue,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::db::DEFAULT_TOURNAMENT_ID;
+    use super::*;
+
+    fn team() -> TeamRecord {
+        TeamRecord {
+            id: 770,
+            name: String::from("England"),
+            short_name: Some(String::from("England")),
+            tla: String::from("ENG"),
+            crest: None,
+        }
+    }
+
+    fn game(id: i32, status: &str, score: Option<(i32, i32)>) -> MatchRecord {
+        MatchRecord {
+            id,
+            home_team: String::from(r#"{"name":"Serbia","tla":"SRB"}"#),
+            away_team: String::from(r#"{"name":"England","tla":"ENG"}"#),
+            status: status.to_string(),
+            stage: Some(String::from("GROUP_STAGE")),
+            group: Some(String::from("GROUP_C")),
+            venue: Some(String::from("Arena AufSchalke")),
+            utc_date: 1718564400,
+            home_score: score.map(|score| score.0),
+            away_score: score.map(|score| score.1),
+            duration: Some(String::from("REGULAR")),
+            home_score_extra_time: None,
+            away_score_extra_time: None,
+            home_penalties: None,
+            away_penalties: None,
+        }
+    }
+
+    #[test]
+    fn test_upsert_matches_is_idempotent() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+        let teams = vec![team()];
+        let matches = vec![game(428759, "TIMED", None), game(428760, "FINISHED", Some((0, 1)))];
+
+        let report = upsert_matches(&conn, DEFAULT_TOURNAMENT_ID, &teams, &matches).unwrap();
+        assert_eq!(report.teams_inserted, 1);
+        assert_eq!(report.matches_inserted, 2);
+        assert_eq!(report.results_changed, vec![428760]);
+
+        let report = upsert_matches(&conn, DEFAULT_TOURNAMENT_ID, &teams, &matches).unwrap();
+        assert_eq!(report, ImportReport { matches_unchanged: 2, ..ImportReport::default() });
+    }
+
+    #[test]
+    fn test_upsert_matches_updates_results() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+        upsert_matches(&conn, DEFAULT_TOURNAMENT_ID, &[team()], &[game(428759, "TIMED", None)]).unwrap();
+
+        let renamed = TeamRecord { crest: Some(String::from("https://crests.football-data.org/770.svg")), ..team() };
+        let report = upsert_matches(&conn, DEFAULT_TOURNAMENT_ID, &[renamed], &[game(428759, "IN_PLAY", Some((1, 0)))]).unwrap();
+        assert_eq!(report.teams_updated, 1);
+        assert_eq!(report.matches_updated, 1);
+        assert_eq!(report.results_changed, vec![428759]);
+
+        let (status, home_score): (String, i32) = conn.query_row(
+            "SELECT status, homeScore FROM match WHERE id = 428759", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(status, "IN_PLAY");
+        assert_eq!(home_score, 1);
+    }
+
+    #[test]
+    fn test_upsert_matches_updates_existing_fixture() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        let report = upsert_matches(&conn, DEFAULT_TOURNAMENT_ID, &[], &[game(3, "FINISHED", Some((2, 2)))]).unwrap();
+
+        assert_eq!(report.matches_updated, 1);
+        assert_eq!(report.matches_inserted, 0);
+        assert_eq!(report.results_changed, vec![3]);
+    }
+
+    #[test]
+    fn test_upsert_matches_skips_matches_of_other_tournaments() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        let report = upsert_matches(&conn, 2, &[], &[game(3, "FINISHED", Some((2, 2))), game(8, "TIMED", None)]).unwrap();
+
+        assert_eq!(report.matches_skipped, vec![3]);
+        assert_eq!(report.matches_inserted, 1);
+        assert_eq!(report.matches_updated, 0);
+        assert!(report.results_changed.is_empty());
+
+        let (tournament_id, status): (i32, String) = conn.query_row("SELECT tournament_id, status FROM match WHERE id = 3", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!(tournament_id, DEFAULT_TOURNAMENT_ID);
+        assert_ne!(status, "FINISHED");
+    }
+}
@@ -0,0 +1,91 @@
+use rusqlite::{Connection, params, Result as SqliteResult};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The base tables (user, match, tip) are owned by em2024-frontend.
+// Everything the API adds on top of them lives in /migrations.
+const MIGRATIONS: &[(i32, &str)] = &[
+    (1, include_str!("../../migrations/001_match_stage_group.sql")),
+    (2, include_str!("../../migrations/002_league.sql")),
+    (3, include_str!("../../migrations/003_user_password.sql")),
+    (4, include_str!("../../migrations/004_user_role.sql")),
+    (5, include_str!("../../migrations/005_team_strength.sql")),
+    (6, include_str!("../../migrations/006_match_score_detail.sql")),
+    (7, include_str!("../../migrations/007_tournament.sql")),
+    (8, include_str!("../../migrations/008_tournament_scope.sql")),
+    (9, include_str!("../../migrations/009_calendar.sql")),
+    (10, include_str!("../../migrations/010_team_strength_tournament.sql")),
+    (11, include_str!("../../migrations/011_user_unique.sql")),
+];
+
+pub fn run_migrations(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migration (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    for (version, sql) in MIGRATIONS {
+        let applied: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM schema_migration WHERE version = ?1)",
+            [version],
+            |row| row.get(0),
+        )?;
+
+        if applied {
+            continue;
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(sql)?;
+        tx.execute(
+            "INSERT INTO schema_migration (version, applied_at) VALUES (?1, ?2)",
+            params![version, now],
+        )?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::db::establish_connection;
+    use super::*;
+
+    #[test]
+    fn test_run_migrations_is_idempotent() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let applied: i32 = conn.query_row("SELECT COUNT(*) FROM schema_migration", [], |row| row.get(0)).unwrap();
+        assert_eq!(applied as usize, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_user_unique_renames_case_duplicates() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE user (id INTEGER PRIMARY KEY, email TEXT NOT NULL, username TEXT NOT NULL);
+             INSERT INTO user (id, email, username) VALUES
+                (1, 'toni@example.com', 'ToniKroos'),
+                (2, 'Toni@Example.com', 'tonikroos'),
+                (3, 'thomas@example.com', 'ThomasMueller');"
+        ).unwrap();
+
+        let (_, sql) = MIGRATIONS.iter().find(|(version, _)| *version == 11).unwrap();
+        conn.execute_batch(sql).unwrap();
+
+        let users: Vec<(String, String)> = conn.prepare("SELECT email, username FROM user ORDER BY id").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<SqliteResult<_>>().unwrap();
+        assert_eq!(users[0], (String::from("toni@example.com"), String::from("ToniKroos")));
+        assert_eq!(users[1], (String::from("duplicate-2-Toni@Example.com"), String::from("tonikroos_2")));
+        assert_eq!(users[2], (String::from("thomas@example.com"), String::from("ThomasMueller")));
+    }
+}
@@ -1,11 +1,58 @@
 mod fixtures;
-
-use rusqlite::{Connection, Result as SqliteResult};
-use serde::Serialize;
+mod migrations;
+pub mod calendar;
+pub mod leagues;
+pub mod matches;
+pub mod profiles;
+pub mod tournaments;
+
+use rusqlite::{Connection, named_params, OptionalExtension, params, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
 use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
 use dotenv::dotenv;
 
-#[derive(Debug, Serialize)]
+/// The tournament that existed before there were several. Everything that is
+/// not scoped by tournament yet belongs to it.
+pub const DEFAULT_TOURNAMENT_ID: i32 = 1;
+
+// Statuses (as used by football-data.org) of a match that is being played.
+// Its score is provisional until the status changes.
+const LIVE_CONDITION: &str = "UPPER(status) IN ('IN_PLAY', 'PAUSED', 'LIVE')";
+
+/// Whether a match with this status is being played, like `LIVE_CONDITION`.
+pub fn is_live_status(status: &str) -> bool {
+    matches!(status.to_uppercase().as_str(), "IN_PLAY" | "PAUSED" | "LIVE")
+}
+
+/// Global role of a user. Each role includes the permissions of the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Player,
+    LeagueAdmin,
+    TournamentAdmin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Player => "player",
+            Role::LeagueAdmin => "league_admin",
+            Role::TournamentAdmin => "tournament_admin",
+        }
+    }
+
+    fn from_db(role: &str) -> Role {
+        match role {
+            "league_admin" => Role::LeagueAdmin,
+            "tournament_admin" => Role::TournamentAdmin,
+            _ => Role::Player,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct User {
     pub id: i32,
     pub username: String,
@@ -23,6 +70,26 @@ pub struct Tip {
     pub score_away: i32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoleAudit {
+    pub id: i32,
+    pub user_id: i32,
+    pub old_role: Role,
+    pub new_role: Role,
+    pub changed_by: i32,
+    pub changed_at: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupGame {
+    pub id: i32,
+    pub group: String,
+    pub home_team: String,
+    pub away_team: String,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct Game {
     pub id: i32,
@@ -31,6 +98,34 @@ pub struct Game {
     pub home_score: i32,
     pub away_score: i32,
     pub date: u64,
+    /// The match is still running, so the score is provisional.
+    pub is_live: bool,
+}
+
+/// A match whether it has been played or not.
+#[derive(Debug, Serialize)]
+pub struct ScheduledGame {
+    pub id: i32,
+    pub home_team: String,
+    pub away_team: String,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
+    pub date: u64,
+    pub status: String,
+}
+
+/// Goal model factors of a team set by an admin. 1.0 is an average team,
+/// a higher `attack` scores more and a higher `defence` concedes more.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamStrength {
+    pub tla: String,
+    pub attack: f64,
+    pub defence: f64,
+}
+
+/// Brings the database up to date. Run once at startup, before any connection is used.
+pub fn migrate() -> SqliteResult<()> {
+    migrations::run_migrations(&establish_connection()?)
 }
 
 pub fn establish_connection() -> SqliteResult<Connection> {
@@ -51,12 +146,16 @@ pub fn establish_connection() -> SqliteResult<Connection> {
     Ok(conn)
 }
 
-pub fn get_users() -> SqliteResult<Vec<User>> {
+/// All users with their champion picks in the tournament.
+pub fn get_users(tournament_id: i32) -> SqliteResult<Vec<User>> {
     let conn = establish_connection()?;
 
-    let mut stmt = conn.prepare("SELECT id, username, department, winner, secretWinner FROM user")?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT user.id, username, department, {} FROM user {} ORDER BY user.id",
+        tournaments::CHAMPION_PICK_COLUMNS, tournaments::CHAMPION_PICK_JOIN,
+    ))?;
 
-    let user_iter = stmt.query_map([], |row| {
+    let user_iter = stmt.query_map(named_params! { ":tournament_id": tournament_id, ":default_tournament_id": DEFAULT_TOURNAMENT_ID }, |row| {
         Ok(User {
             id: row.get(0)?,
             username: row.get(1)?,
@@ -74,6 +173,108 @@ pub fn get_users() -> SqliteResult<Vec<User>> {
     Ok(user_list)
 }
 
+/// Returns the user id and password hash for a login. Users without a password
+/// (e.g. imported from the frontend) cannot log in.
+pub fn get_user_credentials(email: &str) -> SqliteResult<Option<(i32, String)>> {
+    let conn = establish_connection()?;
+
+    conn.query_row(
+        "SELECT id, password_hash FROM user WHERE email = ?1 COLLATE NOCASE AND password_hash IS NOT NULL",
+        [email.trim()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()
+}
+
+pub fn get_user_role(user_id: i32) -> SqliteResult<Option<Role>> {
+    let conn = establish_connection()?;
+
+    let role: Option<String> = conn.query_row(
+        "SELECT role FROM user WHERE id = ?1",
+        [user_id],
+        |row| row.get(0),
+    ).optional()?;
+
+    Ok(role.map(|role| Role::from_db(&role)))
+}
+
+/// Changes the role of a user and records the change in `role_audit`.
+/// Returns the previous role, or `None` if the user does not exist.
+pub fn set_user_role(user_id: i32, role: Role, changed_by: i32) -> SqliteResult<Option<Role>> {
+    let conn = establish_connection()?;
+    let tx = conn.unchecked_transaction()?;
+
+    let old_role: Option<String> = tx.query_row(
+        "SELECT role FROM user WHERE id = ?1",
+        [user_id],
+        |row| row.get(0),
+    ).optional()?;
+
+    let old_role = match old_role {
+        Some(old_role) => Role::from_db(&old_role),
+        None => return Ok(None),
+    };
+
+    tx.execute("UPDATE user SET role = ?2 WHERE id = ?1", params![user_id, role.as_str()])?;
+    tx.execute(
+        "INSERT INTO role_audit (user_id, old_role, new_role, changed_by, changed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![user_id, old_role.as_str(), role.as_str(), changed_by, now()],
+    )?;
+    tx.commit()?;
+
+    Ok(Some(old_role))
+}
+
+pub fn get_role_audit() -> SqliteResult<Vec<RoleAudit>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare("SELECT id, user_id, old_role, new_role, changed_by, changed_at FROM role_audit ORDER BY changed_at DESC, id DESC")?;
+
+    let audit_iter = stmt.query_map([], |row| {
+        let old_role: String = row.get(2)?;
+        let new_role: String = row.get(3)?;
+        Ok(RoleAudit {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            old_role: Role::from_db(&old_role),
+            new_role: Role::from_db(&new_role),
+            changed_by: row.get(4)?,
+            changed_at: row.get(5)?,
+        })
+    })?;
+
+    let mut audit_list = Vec::new();
+    for audit in audit_iter {
+        audit_list.push(audit?);
+    }
+
+    Ok(audit_list)
+}
+
+/// Tips of all tournaments. Match ids are unique across tournaments, so they
+/// are rated by pairing them with the matches of one.
+pub fn get_tips() -> SqliteResult<Vec<Tip>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare("SELECT id, user_id, match_id, score_home, score_away FROM tip")?;
+
+    let tips_iter = stmt.query_map([], |row| {
+        Ok(Tip {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            match_id: row.get(2)?,
+            score_home: row.get(3)?,
+            score_away: row.get(4)?,
+        })
+    })?;
+
+    let mut tips_list = Vec::new();
+    for tip in tips_iter {
+        tips_list.push(tip?);
+    }
+
+    Ok(tips_list)
+}
+
 pub fn get_tips_by_user(user_id: i32) -> SqliteResult<Vec<Tip>> {
     let conn = establish_connection()?;
 
@@ -97,12 +298,56 @@ pub fn get_tips_by_user(user_id: i32) -> SqliteResult<Vec<Tip>> {
     Ok(tips_list)
 }
 
-pub fn get_past_games() -> SqliteResult<Vec<Game>> {
+pub fn get_tips_by_match(match_id: i32) -> SqliteResult<Vec<Tip>> {
     let conn = establish_connection()?;
 
-    let mut stmt = conn.prepare("SELECT id, homeTeam, awayTeam, homeScore, awayScore, utcDate FROM match WHERE homeScore >= 0 AND awayScore >= 0")?;
+    let mut stmt = conn.prepare("SELECT id, user_id, match_id, score_home, score_away FROM tip WHERE match_id = ?1")?;
 
-    let game_iter = match stmt.query_map([], |row| {
+    let tips_iter = stmt.query_map([match_id], |row| {
+        Ok(Tip {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            match_id: row.get(2)?,
+            score_home: row.get(3)?,
+            score_away: row.get(4)?,
+        })
+    })?;
+
+    let mut tips_list = Vec::new();
+    for tip in tips_iter {
+        tips_list.push(tip?);
+    }
+
+    Ok(tips_list)
+}
+
+pub fn get_game(tournament_id: i32, game_id: i32) -> SqliteResult<Option<ScheduledGame>> {
+    let conn = establish_connection()?;
+
+    conn.query_row(
+        "SELECT id, homeTeam, awayTeam, homeScore, awayScore, utcDate, status FROM match WHERE id = ?1 AND tournament_id = ?2",
+        [game_id, tournament_id],
+        |row| {
+            Ok(ScheduledGame {
+                id: row.get(0)?,
+                home_team: row.get(1)?,
+                away_team: row.get(2)?,
+                home_score: row.get(3)?,
+                away_score: row.get(4)?,
+                date: row.get(5)?,
+                status: row.get(6)?,
+            })
+        },
+    ).optional()
+}
+
+/// Matches with a final result. Running matches are left out, see `get_live_games`.
+pub fn get_past_games(tournament_id: i32) -> SqliteResult<Vec<Game>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare(&format!("SELECT id, homeTeam, awayTeam, homeScore, awayScore, utcDate FROM match WHERE tournament_id = ?1 AND homeScore >= 0 AND awayScore >= 0 AND NOT {}", LIVE_CONDITION))?;
+
+    let game_iter = match stmt.query_map([tournament_id], |row| {
         Ok(Game {
             id: row.get(0)?,
             home_team: row.get(1)?,
@@ -110,6 +355,7 @@ pub fn get_past_games() -> SqliteResult<Vec<Game>> {
             home_score: row.get(3)?,
             away_score: row.get(4)?,
             date: row.get(5)?,
+            is_live: false,
         })
     }) {
         Ok(game_iter) => game_iter,
@@ -123,6 +369,175 @@ pub fn get_past_games() -> SqliteResult<Vec<Game>> {
     }
 }
 
+/// Running matches with their current score.
+pub fn get_live_games(tournament_id: i32) -> SqliteResult<Vec<Game>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare(&format!("SELECT id, homeTeam, awayTeam, homeScore, awayScore, utcDate FROM match WHERE tournament_id = ?1 AND homeScore >= 0 AND awayScore >= 0 AND {} ORDER BY utcDate", LIVE_CONDITION))?;
+
+    let game_iter = stmt.query_map([tournament_id], |row| {
+        Ok(Game {
+            id: row.get(0)?,
+            home_team: row.get(1)?,
+            away_team: row.get(2)?,
+            home_score: row.get(3)?,
+            away_score: row.get(4)?,
+            date: row.get(5)?,
+            is_live: true,
+        })
+    })?;
+
+    let mut game_list = Vec::new();
+    for game in game_iter {
+        game_list.push(game?);
+    }
+
+    Ok(game_list)
+}
+
+/// Matches without a final result yet, including running ones, ordered by kickoff.
+pub fn get_unfinished_games(tournament_id: i32) -> SqliteResult<Vec<ScheduledGame>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare(&format!("SELECT id, homeTeam, awayTeam, homeScore, awayScore, utcDate, status FROM match WHERE tournament_id = ?1 AND (homeScore IS NULL OR awayScore IS NULL OR {}) ORDER BY utcDate", LIVE_CONDITION))?;
+
+    let game_iter = stmt.query_map([tournament_id], |row| {
+        Ok(ScheduledGame {
+            id: row.get(0)?,
+            home_team: row.get(1)?,
+            away_team: row.get(2)?,
+            home_score: row.get(3)?,
+            away_score: row.get(4)?,
+            date: row.get(5)?,
+            status: row.get(6)?,
+        })
+    })?;
+
+    let mut game_list = Vec::new();
+    for game in game_iter {
+        game_list.push(game?);
+    }
+
+    Ok(game_list)
+}
+
+/// Distinct team JSON of all home and away teams.
+pub fn get_teams(tournament_id: i32) -> SqliteResult<Vec<String>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare("SELECT homeTeam FROM match WHERE tournament_id = ?1 UNION SELECT awayTeam FROM match WHERE tournament_id = ?1")?;
+    let team_iter = stmt.query_map([tournament_id], |row| row.get(0))?;
+
+    let mut team_list = Vec::new();
+    for team in team_iter {
+        team_list.push(team?);
+    }
+
+    Ok(team_list)
+}
+
+/// Kickoff of the first match, which is when the tournament starts.
+pub fn get_first_kickoff(tournament_id: i32) -> SqliteResult<Option<u64>> {
+    let conn = establish_connection()?;
+
+    conn.query_row("SELECT MIN(utcDate) FROM match WHERE tournament_id = ?1", [tournament_id], |row| row.get(0))
+}
+
+/// Stores the score of a match, either as the current score of a running match
+/// or as the final result. Returns `false` if the match does not exist.
+pub fn set_game_result(tournament_id: i32, game_id: i32, home_score: i32, away_score: i32, live: bool) -> SqliteResult<bool> {
+    let conn = establish_connection()?;
+
+    let status = if live { "IN_PLAY" } else { "FINISHED" };
+    let updated = conn.execute(
+        "UPDATE match SET homeScore = ?2, awayScore = ?3, status = ?4 WHERE id = ?1 AND tournament_id = ?5",
+        params![game_id, home_score, away_score, status, tournament_id],
+    )?;
+
+    Ok(updated > 0)
+}
+
+/// Strengths an admin set for teams of the tournament.
+pub fn get_team_strengths(tournament_id: i32) -> SqliteResult<Vec<TeamStrength>> {
+    let conn = establish_connection()?;
+
+    team_strengths(&conn, tournament_id)
+}
+
+pub fn set_team_strength(tournament_id: i32, strength: &TeamStrength, updated_by: i32) -> SqliteResult<()> {
+    let conn = establish_connection()?;
+
+    save_team_strength(&conn, tournament_id, strength, updated_by)
+}
+
+fn team_strengths(conn: &Connection, tournament_id: i32) -> SqliteResult<Vec<TeamStrength>> {
+    let mut stmt = conn.prepare("SELECT tla, attack, defence FROM team_strength WHERE tournament_id = ?1 ORDER BY tla")?;
+
+    let strength_iter = stmt.query_map([tournament_id], |row| {
+        Ok(TeamStrength {
+            tla: row.get(0)?,
+            attack: row.get(1)?,
+            defence: row.get(2)?,
+        })
+    })?;
+
+    let mut strength_list = Vec::new();
+    for strength in strength_iter {
+        strength_list.push(strength?);
+    }
+
+    Ok(strength_list)
+}
+
+fn save_team_strength(conn: &Connection, tournament_id: i32, strength: &TeamStrength, updated_by: i32) -> SqliteResult<()> {
+    conn.execute(
+        "INSERT INTO team_strength (tournament_id, tla, attack, defence, updated_by, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(tournament_id, tla) DO UPDATE SET attack = ?3, defence = ?4, updated_by = ?5, updated_at = ?6",
+        params![tournament_id, strength.tla, strength.attack, strength.defence, updated_by, now()],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_group_games(tournament_id: i32) -> SqliteResult<Vec<GroupGame>> {
+    let conn = establish_connection()?;
+
+    group_games(&conn, tournament_id)
+}
+
+// A running match has no result yet, so its score is left out of the standings.
+fn group_games(conn: &Connection, tournament_id: i32) -> SqliteResult<Vec<GroupGame>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, groupName, homeTeam, awayTeam,
+                CASE WHEN {live} THEN NULL ELSE homeScore END,
+                CASE WHEN {live} THEN NULL ELSE awayScore END
+         FROM match WHERE tournament_id = ?1 AND groupName IS NOT NULL ORDER BY utcDate",
+        live = LIVE_CONDITION,
+    ))?;
+
+    let game_iter = stmt.query_map([tournament_id], |row| {
+        Ok(GroupGame {
+            id: row.get(0)?,
+            group: row.get(1)?,
+            home_team: row.get(2)?,
+            away_team: row.get(3)?,
+            home_score: row.get(4)?,
+            away_score: row.get(5)?,
+        })
+    })?;
+
+    let mut game_list = Vec::new();
+    for game in game_iter {
+        game_list.push(game?);
+    }
+
+    Ok(game_list)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::from_str;
@@ -132,7 +547,7 @@ mod tests {
     #[test]
     fn test_get_users() {
         env::set_var("MODE", "test");
-        let users = get_users().unwrap();
+        let users = get_users(DEFAULT_TOURNAMENT_ID).unwrap();
         assert_eq!(users.len(), 7);
 
         assert_eq!(users[0].username, "JohnDoe");
@@ -148,11 +563,66 @@ mod tests {
         assert_eq!(users[6].id, 7);
     }
 
+    #[test]
+    fn test_get_users_with_picks_of_the_tournament() {
+        env::set_var("MODE", "test");
+
+        let users = get_users(DEFAULT_TOURNAMENT_ID).unwrap();
+        assert_eq!(users[1].winner, "ESP");
+
+        let users = get_users(2).unwrap();
+        assert_eq!(users.len(), 7);
+        assert_eq!(users[1].winner, "ARG");
+        assert_eq!(users[1].secret_winner, "FRA");
+        assert_eq!(users[0].winner, "");
+    }
+
+    #[test]
+    fn test_get_user_credentials() {
+        env::set_var("MODE", "test");
+        let (user_id, password_hash) = get_user_credentials(" Toni@Kroos.de ").unwrap().unwrap();
+
+        assert_eq!(user_id, 2);
+        assert!(password_hash.starts_with("$argon2id$"));
+
+        assert!(get_user_credentials("nobody@example.com").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_user_role() {
+        env::set_var("MODE", "test");
+
+        assert_eq!(get_user_role(1).unwrap(), Some(Role::Player));
+        assert_eq!(get_user_role(2).unwrap(), Some(Role::TournamentAdmin));
+        assert_eq!(get_user_role(7).unwrap(), Some(Role::LeagueAdmin));
+        assert_eq!(get_user_role(99).unwrap(), None);
+    }
+
+    #[test]
+    fn test_set_user_role() {
+        env::set_var("MODE", "test");
+
+        assert_eq!(set_user_role(1, Role::LeagueAdmin, 2).unwrap(), Some(Role::Player));
+        assert_eq!(set_user_role(99, Role::LeagueAdmin, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_role_audit() {
+        env::set_var("MODE", "test");
+        let audit_list = get_role_audit().unwrap();
+
+        assert_eq!(audit_list.len(), 1);
+        assert_eq!(audit_list[0].user_id, 7);
+        assert_eq!(audit_list[0].old_role, Role::Player);
+        assert_eq!(audit_list[0].new_role, Role::LeagueAdmin);
+        assert_eq!(audit_list[0].changed_by, 2);
+    }
+
     #[test]
     fn test_get_tips_by_user() {
         env::set_var("MODE", "test");
         let tips = get_tips_by_user(1).unwrap();
-        assert_eq!(tips.len(), 2);
+        assert_eq!(tips.len(), 3);
 
         assert_eq!(tips[0].id, 1);
         assert_eq!(tips[0].user_id, 1);
@@ -167,11 +637,46 @@ mod tests {
         assert_eq!(tips[1].score_away, 0);
     }
 
+    #[test]
+    fn test_get_tips() {
+        env::set_var("MODE", "test");
+
+        assert_eq!(get_tips().unwrap().len(), 14);
+    }
+
+    #[test]
+    fn test_get_tips_by_match() {
+        env::set_var("MODE", "test");
+        let tips = get_tips_by_match(1).unwrap();
+
+        assert_eq!(tips.len(), 6);
+        assert!(tips.iter().all(|tip| tip.match_id == 1));
+        assert!(get_tips_by_match(3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_game() {
+        env::set_var("MODE", "test");
+
+        let game = get_game(DEFAULT_TOURNAMENT_ID, 1).unwrap().unwrap();
+        assert_eq!(from_str::<Team>(&game.home_team).unwrap().tla, "GER");
+        assert_eq!(game.home_score, Some(2));
+        assert_eq!(game.away_score, Some(0));
+
+        let game = get_game(DEFAULT_TOURNAMENT_ID, 3).unwrap().unwrap();
+        assert_eq!(game.home_score, None);
+
+        assert!(get_game(DEFAULT_TOURNAMENT_ID, 99).unwrap().is_none());
+        assert!(get_game(DEFAULT_TOURNAMENT_ID, 7).unwrap().is_none());
+        assert!(get_game(2, 7).unwrap().is_some());
+    }
+
     #[test]
     fn test_get_past_games() {
         env::set_var("MODE", "test");
-        let games = get_past_games().unwrap();
+        let games = get_past_games(DEFAULT_TOURNAMENT_ID).unwrap();
         assert_eq!(games.len(), 2);
+        assert_eq!(get_past_games(2).unwrap().len(), 1);
 
         assert_eq!(games[0].id, 1);
         assert_eq!(games[0].home_score, 2);
@@ -197,4 +702,125 @@ mod tests {
         assert_eq!(away_team.name, "France");
         assert_eq!(away_team.tla, "FRA");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_past_games_leaves_out_live_games() {
+        env::set_var("MODE", "test");
+
+        assert!(get_past_games(DEFAULT_TOURNAMENT_ID).unwrap().iter().all(|game| game.id != 6 && !game.is_live));
+    }
+
+    #[test]
+    fn test_is_live_status() {
+        assert!(is_live_status("IN_PLAY"));
+        assert!(is_live_status("paused"));
+        assert!(!is_live_status("FINISHED"));
+        assert!(!is_live_status("scheduled"));
+    }
+
+    #[test]
+    fn test_get_live_games() {
+        env::set_var("MODE", "test");
+        let games = get_live_games(DEFAULT_TOURNAMENT_ID).unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].id, 6);
+        assert_eq!(games[0].home_score, 1);
+        assert_eq!(games[0].away_score, 0);
+        assert!(games[0].is_live);
+    }
+
+    #[test]
+    fn test_get_unfinished_games() {
+        env::set_var("MODE", "test");
+        let games = get_unfinished_games(DEFAULT_TOURNAMENT_ID).unwrap();
+
+        assert_eq!(games.iter().map(|game| game.id).collect::<Vec<_>>(), vec![6, 3, 4, 5]);
+        assert_eq!(games[0].home_score, Some(1));
+        assert!(games[1..].iter().all(|game| game.home_score.is_none()));
+    }
+
+    #[test]
+    fn test_get_teams() {
+        env::set_var("MODE", "test");
+        let teams = get_teams(DEFAULT_TOURNAMENT_ID).unwrap();
+
+        assert_eq!(teams.len(), 6);
+        assert!(teams.iter().any(|team| from_str::<Team>(team).unwrap().tla == "ESP"));
+    }
+
+    #[test]
+    fn test_get_first_kickoff() {
+        env::set_var("MODE", "test");
+        let first_kickoff = get_first_kickoff(DEFAULT_TOURNAMENT_ID).unwrap().unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+        assert!(first_kickoff <= now - 86400);
+        assert!(first_kickoff > now - 86400 - 60);
+    }
+
+    #[test]
+    fn test_get_team_strengths() {
+        env::set_var("MODE", "test");
+
+        assert!(get_team_strengths(DEFAULT_TOURNAMENT_ID).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_team_strength() {
+        env::set_var("MODE", "test");
+        let strength = TeamStrength { tla: String::from("GER"), attack: 1.2, defence: 0.8 };
+
+        assert!(set_team_strength(DEFAULT_TOURNAMENT_ID, &strength, 2).is_ok());
+    }
+
+    #[test]
+    fn test_team_strengths_are_scoped_by_tournament() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        save_team_strength(&conn, DEFAULT_TOURNAMENT_ID, &TeamStrength { tla: String::from("GER"), attack: 1.2, defence: 0.8 }, 2).unwrap();
+        save_team_strength(&conn, 2, &TeamStrength { tla: String::from("GER"), attack: 0.9, defence: 1.1 }, 2).unwrap();
+        save_team_strength(&conn, DEFAULT_TOURNAMENT_ID, &TeamStrength { tla: String::from("GER"), attack: 1.5, defence: 0.7 }, 2).unwrap();
+
+        let strengths = team_strengths(&conn, DEFAULT_TOURNAMENT_ID).unwrap();
+        assert_eq!(strengths.len(), 1);
+        assert_eq!(strengths[0].attack, 1.5);
+
+        let strengths = team_strengths(&conn, 2).unwrap();
+        assert_eq!(strengths.len(), 1);
+        assert_eq!(strengths[0].attack, 0.9);
+    }
+
+    #[test]
+    fn test_get_group_games() {
+        env::set_var("MODE", "test");
+        let games = get_group_games(DEFAULT_TOURNAMENT_ID).unwrap();
+        assert_eq!(games.len(), 4);
+
+        assert_eq!(games[0].id, 1);
+        assert_eq!(games[0].group, "GROUP_A");
+        assert_eq!(games[0].home_score, Some(2));
+
+        let group_b: Vec<&GroupGame> = games.iter().filter(|game| game.group == "GROUP_B").collect();
+        assert_eq!(group_b.len(), 1);
+        assert_eq!(group_b[0].id, 3);
+        assert_eq!(group_b[0].home_score, None);
+        assert_eq!(group_b[0].away_score, None);
+    }
+
+    #[test]
+    fn test_get_group_games_without_live_score() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+        conn.execute("UPDATE match SET status = 'IN_PLAY', homeScore = 1, awayScore = 0 WHERE id = 3", []).unwrap();
+
+        let games = group_games(&conn, DEFAULT_TOURNAMENT_ID).unwrap();
+        let live = games.iter().find(|game| game.id == 3).unwrap();
+
+        assert_eq!(games.len(), 4);
+        assert_eq!(live.home_score, None);
+        assert_eq!(live.away_score, None);
+        assert_eq!(games[0].home_score, Some(2));
+    }
+}
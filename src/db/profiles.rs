@@ -0,0 +1,214 @@
+use rusqlite::{Error, ErrorCode, OptionalExtension, params, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use super::establish_connection;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: i32,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub username: String,
+    pub department: String,
+    pub winner: String,
+    pub secret_winner: String,
+}
+
+#[derive(Debug)]
+pub struct NewUser {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub username: String,
+    pub department: String,
+    pub winner: String,
+    pub secret_winner: String,
+    pub password_hash: String,
+}
+
+/// A column that must be unique among users.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TakenField {
+    Email,
+    Username,
+}
+
+pub fn get_profile(user_id: i32) -> SqliteResult<Option<Profile>> {
+    let conn = establish_connection()?;
+
+    conn.query_row(
+        "SELECT id, email, first_name, last_name, username, department, winner, secretWinner FROM user WHERE id = ?1",
+        [user_id],
+        |row| {
+            Ok(Profile {
+                id: row.get(0)?,
+                email: row.get(1)?,
+                first_name: row.get(2)?,
+                last_name: row.get(3)?,
+                username: row.get(4)?,
+                department: row.get(5)?,
+                winner: row.get(6)?,
+                secret_winner: row.get(7)?,
+            })
+        },
+    ).optional()
+}
+
+pub fn insert_user(user: &NewUser) -> SqliteResult<i32> {
+    let conn = establish_connection()?;
+
+    conn.execute(
+        "INSERT INTO user (email, first_name, last_name, username, department, winner, secretWinner, password_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![user.email, user.first_name, user.last_name, user.username, user.department, user.winner, user.secret_winner, user.password_hash],
+    )?;
+
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn update_profile(profile: &Profile) -> SqliteResult<bool> {
+    let conn = establish_connection()?;
+
+    let updated = conn.execute(
+        "UPDATE user SET first_name = ?2, last_name = ?3, username = ?4, department = ?5, winner = ?6, secretWinner = ?7 WHERE id = ?1",
+        params![profile.id, profile.first_name, profile.last_name, profile.username, profile.department, profile.winner, profile.secret_winner],
+    )?;
+
+    Ok(updated > 0)
+}
+
+/// Checks case-insensitively whether another user already has the username.
+pub fn is_username_taken(username: &str, except_user_id: Option<i32>) -> SqliteResult<bool> {
+    is_taken("username", username, except_user_id)
+}
+
+/// Checks case-insensitively whether another user already has the email address.
+pub fn is_email_taken(email: &str, except_user_id: Option<i32>) -> SqliteResult<bool> {
+    is_taken("email", email, except_user_id)
+}
+
+pub fn get_departments() -> SqliteResult<Vec<String>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare("SELECT DISTINCT department FROM user ORDER BY department")?;
+    let department_iter = stmt.query_map([], |row| row.get(0))?;
+
+    let mut department_list = Vec::new();
+    for department in department_iter {
+        department_list.push(department?);
+    }
+
+    Ok(department_list)
+}
+
+/// The unique column an insert or update failed on, when another user got the
+/// value between the check and the write.
+pub fn taken_field(error: &Error) -> Option<TakenField> {
+    match error {
+        Error::SqliteFailure(failure, Some(message)) if failure.code == ErrorCode::ConstraintViolation => {
+            if message.contains("user.email") {
+                Some(TakenField::Email)
+            } else if message.contains("user.username") {
+                Some(TakenField::Username)
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+fn is_taken(column: &str, value: &str, except_user_id: Option<i32>) -> SqliteResult<bool> {
+    let conn = establish_connection()?;
+
+    conn.query_row(
+        &format!("SELECT EXISTS(SELECT 1 FROM user WHERE {} = ?1 COLLATE NOCASE AND id != ?2)", column),
+        params![value, except_user_id.unwrap_or(0)],
+        |row| row.get(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use super::*;
+
+    #[test]
+    fn test_get_profile() {
+        env::set_var("MODE", "test");
+        let profile = get_profile(2).unwrap().unwrap();
+
+        assert_eq!(profile.email, "toni@kroos.de");
+        assert_eq!(profile.first_name, "Toni");
+        assert_eq!(profile.last_name, "Kroos");
+        assert_eq!(profile.username, "ToniKroos");
+        assert_eq!(profile.department, "Langenfeld");
+        assert_eq!(profile.winner, "ESP");
+        assert_eq!(profile.secret_winner, "DEU");
+
+        assert!(get_profile(99).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_insert_user_with_taken_username_or_email() {
+        env::set_var("MODE", "test");
+        let new_user = |email: &str, username: &str| NewUser {
+            email: email.to_string(),
+            first_name: String::from("Toni"),
+            last_name: String::from("Kroos"),
+            username: username.to_string(),
+            department: String::from("Langenfeld"),
+            winner: String::new(),
+            secret_winner: String::new(),
+            password_hash: String::from("hash"),
+        };
+
+        let error = insert_user(&new_user("new@kroos.de", "tonikroos")).unwrap_err();
+        assert_eq!(taken_field(&error), Some(TakenField::Username));
+
+        let error = insert_user(&new_user("TONI@kroos.de", "NewKroos")).unwrap_err();
+        assert_eq!(taken_field(&error), Some(TakenField::Email));
+
+        assert_eq!(taken_field(&Error::QueryReturnedNoRows), None);
+    }
+
+    #[test]
+    fn test_insert_user() {
+        env::set_var("MODE", "test");
+        let user_id = insert_user(&NewUser {
+            email: String::from("jamal@musiala.de"),
+            first_name: String::from("Jamal"),
+            last_name: String::from("Musiala"),
+            username: String::from("JamalMusiala"),
+            department: String::from("Langenfeld"),
+            winner: String::from("GER"),
+            secret_winner: String::new(),
+            password_hash: String::from("hash"),
+        }).unwrap();
+
+        assert_eq!(user_id, 8);
+    }
+
+    #[test]
+    fn test_is_username_taken() {
+        env::set_var("MODE", "test");
+
+        assert!(is_username_taken("tonikroos", None).unwrap());
+        assert!(!is_username_taken("tonikroos", Some(2)).unwrap());
+        assert!(!is_username_taken("JamalMusiala", None).unwrap());
+    }
+
+    #[test]
+    fn test_is_email_taken() {
+        env::set_var("MODE", "test");
+
+        assert!(is_email_taken("TONI@kroos.de", None).unwrap());
+        assert!(!is_email_taken("toni@kroos.de", Some(2)).unwrap());
+    }
+
+    #[test]
+    fn test_get_departments() {
+        env::set_var("MODE", "test");
+
+        assert_eq!(get_departments().unwrap(), vec!["Langenfeld", "London"]);
+    }
+}
@@ -0,0 +1,350 @@
+use rusqlite::{Connection, named_params, OptionalExtension, params, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use crate::service::ranking::RuleSet;
+use super::{establish_connection, DEFAULT_TOURNAMENT_ID};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+    pub rule_set: RuleSet,
+    /// Team code of the champion, once it is decided.
+    pub champion: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChampionPick {
+    pub winner: String,
+    pub secret_winner: String,
+}
+
+/// A tip of a user that is known by name only, as in old exports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TipRecord {
+    pub username: String,
+    pub match_id: i32,
+    pub score_home: i32,
+    pub score_away: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnmatchedReason {
+    UnknownUser,
+    UnknownMatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnmatchedTip {
+    pub tip: TipRecord,
+    pub reason: UnmatchedReason,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TipImportReport {
+    pub tips_inserted: usize,
+    pub tips_updated: usize,
+    pub tips_unchanged: usize,
+    /// Tips that were not imported, in the order they were given.
+    pub unmatched: Vec<UnmatchedTip>,
+}
+
+pub fn get_tournaments() -> SqliteResult<Vec<Tournament>> {
+    let conn = establish_connection()?;
+
+    let mut stmt = conn.prepare("SELECT id, slug, name, rule_set, champion FROM tournament ORDER BY id")?;
+    let tournament_iter = stmt.query_map([], map_tournament)?;
+
+    let mut tournament_list = Vec::new();
+    for tournament in tournament_iter {
+        tournament_list.push(tournament?);
+    }
+
+    Ok(tournament_list)
+}
+
+pub fn get_tournament(slug: &str) -> SqliteResult<Option<Tournament>> {
+    let conn = establish_connection()?;
+    find_tournament(&conn, "slug = ?1", slug)
+}
+
+/// The tournament the URLs without a tournament refer to.
+pub fn get_default_tournament() -> SqliteResult<Tournament> {
+    let conn = establish_connection()?;
+    find_tournament(&conn, "id = ?1", DEFAULT_TOURNAMENT_ID).map(|tournament| tournament.expect("the default tournament is created by a migration"))
+}
+
+/// Returns the tournament with the slug, creating it first if there is none.
+/// An existing tournament is left as it is.
+pub fn ensure_tournament(slug: &str, name: &str, rule_set: RuleSet) -> SqliteResult<Tournament> {
+    let conn = establish_connection()?;
+    insert_tournament(&conn, slug, name, rule_set)
+}
+
+/// Creates the tournament or updates the one with the same slug. Returns
+/// whether it was created.
+pub fn save_tournament(tournament: &Tournament) -> SqliteResult<(Tournament, bool)> {
+    let conn = establish_connection()?;
+
+    let updated = conn.execute(
+        "UPDATE tournament SET name = ?2, rule_set = ?3, champion = ?4 WHERE slug = ?1",
+        params![tournament.slug, tournament.name, tournament.rule_set.as_str(), tournament.champion],
+    )?;
+    if updated == 0 {
+        conn.execute(
+            "INSERT INTO tournament (slug, name, rule_set, champion) VALUES (?1, ?2, ?3, ?4)",
+            params![tournament.slug, tournament.name, tournament.rule_set.as_str(), tournament.champion],
+        )?;
+    }
+
+    let saved = find_tournament(&conn, "slug = ?1", &tournament.slug)?.expect("the tournament was just saved");
+    Ok((saved, updated == 0))
+}
+
+/// The champion picks of a user in a tournament, empty if there are none yet.
+/// `None` if the user does not exist.
+pub fn get_champion_pick(tournament_id: i32, user_id: i32) -> SqliteResult<Option<ChampionPick>> {
+    let conn = establish_connection()?;
+
+    conn.query_row(
+        &format!("SELECT {} FROM user {} WHERE user.id = :user_id", CHAMPION_PICK_COLUMNS, CHAMPION_PICK_JOIN),
+        named_params! { ":tournament_id": tournament_id, ":default_tournament_id": DEFAULT_TOURNAMENT_ID, ":user_id": user_id },
+        |row| {
+            Ok(ChampionPick {
+                winner: row.get(0)?,
+                secret_winner: row.get(1)?,
+            })
+        },
+    ).optional()
+}
+
+pub fn set_champion_pick(tournament_id: i32, user_id: i32, pick: &ChampionPick) -> SqliteResult<()> {
+    let conn = establish_connection()?;
+
+    if tournament_id == DEFAULT_TOURNAMENT_ID {
+        conn.execute(
+            "UPDATE user SET winner = ?2, secretWinner = ?3 WHERE id = ?1",
+            params![user_id, pick.winner, pick.secret_winner],
+        )?;
+    } else {
+        conn.execute(
+            "INSERT INTO champion_pick (tournament_id, user_id, winner, secret_winner) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(tournament_id, user_id) DO UPDATE SET winner = ?3, secret_winner = ?4",
+            params![tournament_id, user_id, pick.winner, pick.secret_winner],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Imports tips into the matches of a tournament in one transaction. Users are
+/// matched by username, ignoring case. A user's existing tip on a match is
+/// replaced, so importing the same tips again changes nothing.
+pub fn import_tips(tournament_id: i32, tips: &[TipRecord]) -> SqliteResult<TipImportReport> {
+    let conn = establish_connection()?;
+    upsert_tips(&conn, tournament_id, tips)
+}
+
+// The winner and secret winner picks of a user in the tournament `:tournament_id`.
+// Those of the default tournament are kept on `user`.
+pub(super) const CHAMPION_PICK_COLUMNS: &str =
+    "CASE WHEN :tournament_id = :default_tournament_id THEN user.winner ELSE COALESCE(champion_pick.winner, '') END,
+     CASE WHEN :tournament_id = :default_tournament_id THEN user.secretWinner ELSE COALESCE(champion_pick.secret_winner, '') END";
+pub(super) const CHAMPION_PICK_JOIN: &str =
+    "LEFT JOIN champion_pick ON champion_pick.user_id = user.id AND champion_pick.tournament_id = :tournament_id";
+
+fn find_tournament<P: rusqlite::ToSql>(conn: &Connection, condition: &str, value: P) -> SqliteResult<Option<Tournament>> {
+    conn.query_row(
+        &format!("SELECT id, slug, name, rule_set, champion FROM tournament WHERE {}", condition),
+        [value],
+        map_tournament,
+    ).optional()
+}
+
+fn map_tournament(row: &rusqlite::Row) -> SqliteResult<Tournament> {
+    Ok(Tournament {
+        id: row.get(0)?,
+        slug: row.get(1)?,
+        name: row.get(2)?,
+        rule_set: RuleSet::from_db(&row.get::<_, String>(3)?),
+        champion: row.get(4)?,
+    })
+}
+
+fn insert_tournament(conn: &Connection, slug: &str, name: &str, rule_set: RuleSet) -> SqliteResult<Tournament> {
+    conn.execute(
+        "INSERT OR IGNORE INTO tournament (slug, name, rule_set) VALUES (?1, ?2, ?3)",
+        params![slug, name, rule_set.as_str()],
+    )?;
+
+    find_tournament(conn, "slug = ?1", slug).map(|tournament| tournament.expect("the tournament was just inserted"))
+}
+
+fn upsert_tips(conn: &Connection, tournament_id: i32, tips: &[TipRecord]) -> SqliteResult<TipImportReport> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = TipImportReport::default();
+
+    for tip in tips {
+        let user_id: Option<i32> = tx.query_row(
+            "SELECT id FROM user WHERE username = ?1 COLLATE NOCASE ORDER BY id LIMIT 1",
+            [&tip.username],
+            |row| row.get(0),
+        ).optional()?;
+
+        // There is no time of the tip in old exports, so it counts as made at kickoff.
+        let kickoff: Option<u64> = tx.query_row(
+            "SELECT utcDate FROM match WHERE id = ?1 AND tournament_id = ?2",
+            [tip.match_id, tournament_id],
+            |row| row.get(0),
+        ).optional()?;
+
+        let (user_id, kickoff) = match (user_id, kickoff) {
+            (None, _) => {
+                report.unmatched.push(UnmatchedTip { tip: tip.clone(), reason: UnmatchedReason::UnknownUser });
+                continue;
+            },
+            (_, None) => {
+                report.unmatched.push(UnmatchedTip { tip: tip.clone(), reason: UnmatchedReason::UnknownMatch });
+                continue;
+            },
+            (Some(user_id), Some(kickoff)) => (user_id, kickoff),
+        };
+
+        let existing: Option<(i32, i32, i32)> = tx.query_row(
+            "SELECT id, score_home, score_away FROM tip WHERE user_id = ?1 AND match_id = ?2",
+            [user_id, tip.match_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).optional()?;
+
+        match existing {
+            Some((_, score_home, score_away)) if score_home == tip.score_home && score_away == tip.score_away => {
+                report.tips_unchanged += 1;
+            },
+            Some((id, _, _)) => {
+                tx.execute(
+                    "UPDATE tip SET score_home = ?2, score_away = ?3 WHERE id = ?1",
+                    params![id, tip.score_home, tip.score_away],
+                )?;
+                report.tips_updated += 1;
+            },
+            None => {
+                tx.execute(
+                    "INSERT INTO tip (user_id, match_id, date, score_home, score_away) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![user_id, tip.match_id, kickoff, tip.score_home, tip.score_away],
+                )?;
+                report.tips_inserted += 1;
+            },
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::db::matches::{upsert_matches, MatchRecord};
+    use super::*;
+
+    fn tip(username: &str, match_id: i32, score_home: i32, score_away: i32) -> TipRecord {
+        TipRecord { username: username.to_string(), match_id, score_home, score_away }
+    }
+
+    fn legacy_tournament(conn: &Connection) -> Tournament {
+        let tournament = insert_tournament(conn, "em2021", "EURO 2020", RuleSet::Classic2021).unwrap();
+        upsert_matches(conn, tournament.id, &[], &[MatchRecord {
+            id: 285418,
+            home_team: String::from(r#"{"name":"France","tla":"FRA"}"#),
+            away_team: String::from(r#"{"name":"Germany","tla":"GER"}"#),
+            status: String::from("FINISHED"),
+            stage: Some(String::from("GROUP_STAGE")),
+            group: Some(String::from("Group F")),
+            venue: Some(String::from("Football Arena Munich")),
+            utc_date: 1623783600,
+            home_score: Some(1),
+            away_score: Some(0),
+            duration: Some(String::from("REGULAR")),
+            home_score_extra_time: None,
+            away_score_extra_time: None,
+            home_penalties: None,
+            away_penalties: None,
+        }]).unwrap();
+
+        tournament
+    }
+
+    #[test]
+    fn test_get_default_tournament() {
+        env::set_var("MODE", "test");
+        let tournament = get_tournament("em2024").unwrap().unwrap();
+
+        assert_eq!(tournament.id, DEFAULT_TOURNAMENT_ID);
+        assert_eq!(tournament.rule_set, RuleSet::Em2024);
+        assert_eq!(tournament.champion.as_deref(), Some("ESP"));
+        assert_eq!(get_default_tournament().unwrap(), tournament);
+        assert_eq!(get_tournament("wm2026").unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_tournaments() {
+        env::set_var("MODE", "test");
+        let slugs: Vec<String> = get_tournaments().unwrap().into_iter().map(|tournament| tournament.slug).collect();
+
+        assert_eq!(slugs, vec!["em2024", "wm2022"]);
+    }
+
+    #[test]
+    fn test_get_champion_pick() {
+        env::set_var("MODE", "test");
+
+        assert_eq!(get_champion_pick(DEFAULT_TOURNAMENT_ID, 2).unwrap().unwrap().winner, "ESP");
+        assert_eq!(get_champion_pick(2, 2).unwrap().unwrap(), ChampionPick { winner: String::from("ARG"), secret_winner: String::from("FRA") });
+        assert_eq!(get_champion_pick(2, 1).unwrap().unwrap(), ChampionPick { winner: String::new(), secret_winner: String::new() });
+        assert_eq!(get_champion_pick(2, 99).unwrap(), None);
+    }
+
+    #[test]
+    fn test_insert_tournament_keeps_existing() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+
+        let created = insert_tournament(&conn, "em2021", "EURO 2020", RuleSet::Classic2021).unwrap();
+        let again = insert_tournament(&conn, "em2021", "EM 2021", RuleSet::Em2024).unwrap();
+
+        assert_eq!(again, created);
+        assert_eq!(created.rule_set, RuleSet::Classic2021);
+        assert_eq!(insert_tournament(&conn, "em2024", "EM 2024", RuleSet::Em2024).unwrap().id, 1);
+    }
+
+    #[test]
+    fn test_upsert_tips() {
+        env::set_var("MODE", "test");
+        let conn = establish_connection().unwrap();
+        let tournament = legacy_tournament(&conn);
+        let tips = vec![
+            tip("tonikroos", 285418, 0, 1),
+            tip("JohnDoe", 285418, 1, 0),
+            tip("JogiLoew", 285418, 2, 0),
+            tip("JohnDoe", 1, 2, 0),
+        ];
+
+        let report = upsert_tips(&conn, tournament.id, &tips).unwrap();
+        assert_eq!(report.tips_inserted, 2);
+        assert_eq!(report.unmatched, vec![
+            UnmatchedTip { tip: tip("JogiLoew", 285418, 2, 0), reason: UnmatchedReason::UnknownUser },
+            UnmatchedTip { tip: tip("JohnDoe", 1, 2, 0), reason: UnmatchedReason::UnknownMatch },
+        ]);
+
+        let (user_id, date): (i32, u64) = conn.query_row(
+            "SELECT user_id, date FROM tip WHERE match_id = 285418 AND score_away = 1", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(user_id, 2);
+        assert_eq!(date, 1623783600);
+
+        let report = upsert_tips(&conn, tournament.id, &[tip("TONIKROOS", 285418, 1, 1), tip("JohnDoe", 285418, 1, 0)]).unwrap();
+        assert_eq!(report, TipImportReport { tips_updated: 1, tips_unchanged: 1, ..TipImportReport::default() });
+    }
+}
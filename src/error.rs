@@ -0,0 +1,56 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+impl ErrorResponse {
+    pub fn new(error: &str) -> Self {
+        ErrorResponse { error: error.to_string() }
+    }
+}
+
+/// Error returned by extractors, rendered as an `ErrorResponse`.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: &'static str,
+}
+
+impl ApiError {
+    pub fn unauthorized() -> Self {
+        ApiError { status: StatusCode::UNAUTHORIZED, message: "Authentication required" }
+    }
+
+    pub fn forbidden() -> Self {
+        ApiError { status: StatusCode::FORBIDDEN, message: "Insufficient permissions" }
+    }
+
+    pub fn not_found(message: &'static str) -> Self {
+        ApiError { status: StatusCode::NOT_FOUND, message }
+    }
+
+    pub fn internal() -> Self {
+        ApiError { status: StatusCode::INTERNAL_SERVER_ERROR, message: "Internal server error" }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status).json(ErrorResponse::new(self.message))
+    }
+}
@@ -0,0 +1,275 @@
+use actix_web::web::Bytes;
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::{interval_at, Instant};
+use crate::service::changes::RankingChange;
+
+// Subscribers that fall further behind than this skip the oldest events.
+const CHANNEL_CAPACITY: usize = 64;
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    ResultUpdated {
+        /// Slug of the tournament the match belongs to.
+        tournament: String,
+        match_id: i32,
+        home_score: i32,
+        away_score: i32,
+        /// The match is still running.
+        provisional: bool,
+    },
+    RankingChanged {
+        tournament: String,
+        changes: Vec<RankingChange>,
+    },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::ResultUpdated { .. } => "result_updated",
+            Event::RankingChanged { .. } => "ranking_changed",
+        }
+    }
+
+    pub fn tournament(&self) -> &str {
+        match self {
+            Event::ResultUpdated { tournament, .. } | Event::RankingChanged { tournament, .. } => tournament,
+        }
+    }
+
+    /// Formats the event as a Server-Sent Events message.
+    pub fn to_sse(&self) -> String {
+        format!("event: {}\ndata: {}\n\n", self.name(), serde_json::to_string(self).unwrap())
+    }
+}
+
+/// What a WebSocket client can subscribe to, written as `rating`,
+/// `department:<name>`, `match:<id>` or `user:<id>`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Topic {
+    Rating,
+    Department(String),
+    Match(i32),
+    User(i32),
+}
+
+impl Topic {
+    pub fn parse(topic: &str) -> Option<Topic> {
+        match topic.split_once(':') {
+            None if topic == "rating" => Some(Topic::Rating),
+            Some(("department", department)) if !department.is_empty() => Some(Topic::Department(department.to_string())),
+            Some(("match", match_id)) => match_id.parse().ok().map(Topic::Match),
+            Some(("user", user_id)) => user_id.parse().ok().map(Topic::User),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::Rating => write!(f, "rating"),
+            Topic::Department(department) => write!(f, "department:{}", department),
+            Topic::Match(match_id) => write!(f, "match:{}", match_id),
+            Topic::User(user_id) => write!(f, "user:{}", user_id),
+        }
+    }
+}
+
+/// The topics of one connection.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    topics: BTreeSet<Topic>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, topic: Topic) {
+        self.topics.insert(topic);
+    }
+
+    pub fn unsubscribe(&mut self, topic: &Topic) {
+        self.topics.remove(topic);
+    }
+
+    pub fn topics(&self) -> Vec<String> {
+        self.topics.iter().map(Topic::to_string).collect()
+    }
+
+    /// The part of the event the connection subscribed to, if any. Results go
+    /// to the rating and the match; ranking changes are cut down to the
+    /// subscribed departments and users unless the whole rating is subscribed.
+    pub fn filter(&self, event: &Event) -> Option<Event> {
+        match event {
+            Event::ResultUpdated { match_id, .. } => {
+                (self.topics.contains(&Topic::Rating) || self.topics.contains(&Topic::Match(*match_id))).then(|| event.clone())
+            },
+            Event::RankingChanged { tournament, changes } => {
+                if self.topics.contains(&Topic::Rating) {
+                    return Some(event.clone());
+                }
+
+                let changes: Vec<RankingChange> = changes.iter()
+                    .filter(|change| self.topics.contains(&Topic::User(change.user_id))
+                        || self.topics.contains(&Topic::Department(change.department.clone())))
+                    .cloned()
+                    .collect();
+
+                (!changes.is_empty()).then(|| Event::RankingChanged { tournament: tournament.clone(), changes })
+            },
+        }
+    }
+}
+
+/// Fans out events to every connected client. Shared as app data.
+#[derive(Debug, Clone)]
+pub struct Broadcaster {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for Broadcaster {
+    fn default() -> Self {
+        Broadcaster::new()
+    }
+}
+
+impl Broadcaster {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Broadcaster { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Sends the event to all current subscribers. Without subscribers the
+    /// event is dropped.
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    /// An endless SSE body with the events of one tournament and a keep-alive
+    /// comment whenever nothing happened for a while, so proxies do not close
+    /// the connection.
+    pub fn sse_stream(&self, tournament: String) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+        let receiver = self.subscribe();
+        let keep_alive = interval_at(Instant::now() + KEEP_ALIVE_INTERVAL, KEEP_ALIVE_INTERVAL);
+
+        stream::unfold((receiver, keep_alive, tournament), |(mut receiver, mut keep_alive, tournament)| async move {
+            loop {
+                let message = tokio::select! {
+                    event = receiver.recv() => match event {
+                        Ok(event) if event.tournament() != tournament => continue,
+                        Ok(event) => event.to_sse(),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    },
+                    _ = keep_alive.tick() => String::from(": keep-alive\n\n"),
+                };
+
+                return Some((Ok(Bytes::from(message)), (receiver, keep_alive, tournament)));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+    use super::*;
+
+    #[test]
+    fn test_event_to_sse() {
+        let event = Event::ResultUpdated { tournament: String::from("em2024"), match_id: 3, home_score: 2, away_score: 1, provisional: false };
+
+        assert_eq!(
+            event.to_sse(),
+            "event: result_updated\ndata: {\"type\":\"result_updated\",\"tournament\":\"em2024\",\"match_id\":3,\"home_score\":2,\"away_score\":1,\"provisional\":false}\n\n"
+        );
+    }
+
+    fn change(user_id: i32, department: &str) -> RankingChange {
+        RankingChange {
+            user_id,
+            name: format!("user{}", user_id),
+            department: department.to_string(),
+            old_position: Some(2),
+            new_position: 1,
+            old_score: Some(4),
+            new_score: 8,
+        }
+    }
+
+    fn ranking_changed(changes: Vec<RankingChange>) -> Event {
+        Event::RankingChanged { tournament: String::from("em2024"), changes }
+    }
+
+    #[test]
+    fn test_topic_parse() {
+        assert_eq!(Topic::parse("rating"), Some(Topic::Rating));
+        assert_eq!(Topic::parse("department:London"), Some(Topic::Department(String::from("London"))));
+        assert_eq!(Topic::parse("match:3"), Some(Topic::Match(3)));
+        assert_eq!(Topic::parse("user:7"), Some(Topic::User(7)));
+        assert_eq!(Topic::parse("match:three"), None);
+        assert_eq!(Topic::parse("department:"), None);
+        assert_eq!(Topic::parse("weather"), None);
+
+        assert_eq!(Topic::Department(String::from("London")).to_string(), "department:London");
+    }
+
+    #[test]
+    fn test_subscriptions_filter() {
+        let result = Event::ResultUpdated { tournament: String::from("em2024"), match_id: 3, home_score: 1, away_score: 0, provisional: false };
+        let ranking = ranking_changed(vec![change(1, "Langenfeld"), change(5, "London"), change(7, "London")]);
+
+        let mut subscriptions = Subscriptions::default();
+        assert_eq!(subscriptions.filter(&result), None);
+        assert_eq!(subscriptions.filter(&ranking), None);
+
+        subscriptions.subscribe(Topic::Match(3));
+        subscriptions.subscribe(Topic::User(1));
+        assert_eq!(subscriptions.filter(&result), Some(result.clone()));
+        assert_eq!(subscriptions.filter(&ranking), Some(ranking_changed(vec![change(1, "Langenfeld")])));
+
+        subscriptions.unsubscribe(&Topic::User(1));
+        subscriptions.subscribe(Topic::Department(String::from("London")));
+        assert_eq!(subscriptions.filter(&ranking), Some(ranking_changed(vec![change(5, "London"), change(7, "London")])));
+        assert_eq!(subscriptions.topics(), vec!["department:London", "match:3"]);
+
+        let mut subscriptions = Subscriptions::default();
+        subscriptions.subscribe(Topic::Rating);
+        assert_eq!(subscriptions.filter(&ranking), Some(ranking.clone()));
+        assert_eq!(subscriptions.filter(&result), Some(result));
+    }
+
+    #[actix_web::test]
+    async fn test_publish_reaches_subscribers() {
+        let broadcaster = Broadcaster::new();
+        let mut receiver = broadcaster.subscribe();
+
+        broadcaster.publish(ranking_changed(Vec::new()));
+
+        assert_eq!(receiver.recv().await.unwrap(), ranking_changed(Vec::new()));
+    }
+
+    #[actix_web::test]
+    async fn test_sse_stream() {
+        let broadcaster = Broadcaster::new();
+        let mut stream = Box::pin(broadcaster.sse_stream(String::from("em2024")));
+
+        broadcaster.publish(Event::ResultUpdated { tournament: String::from("wm2022"), match_id: 7, home_score: 3, away_score: 3, provisional: false });
+        broadcaster.publish(Event::ResultUpdated { tournament: String::from("em2024"), match_id: 3, home_score: 2, away_score: 1, provisional: true });
+
+        // The event of the other tournament is skipped.
+        let message = stream.next().await.unwrap().unwrap();
+        assert!(message.starts_with(b"event: result_updated\n"));
+        assert!(String::from_utf8(message.to_vec()).unwrap().contains("\"match_id\":3"));
+    }
+}
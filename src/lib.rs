@@ -1,3 +1,10 @@
+pub mod api;
+pub mod auth;
 pub mod db;
+pub mod error;
+pub mod events;
 pub mod service;
-pub mod routes;
\ No newline at end of file
+pub mod routes;
+pub mod sync;
+pub mod tournament;
+pub mod ws;
\ No newline at end of file
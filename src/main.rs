@@ -1,17 +1,28 @@
-use actix_web::{App, HttpServer};
-
-mod db;
-mod service;
-mod routes;
+use actix_web::{web, App, HttpServer};
+use em2021_api::auth::AuthSecret;
+use em2021_api::events::Broadcaster;
+use em2021_api::{db, routes};
+use em2021_api::sync::{self, SyncConfig, SyncJob};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    HttpServer::new(|| {
+    dotenv::dotenv().ok();
+
+    db::migrate().map_err(|error| std::io::Error::other(format!("Could not migrate the database: {}", error)))?;
+
+    let auth_secret = AuthSecret::from_env().map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+    let auth_secret = web::Data::new(auth_secret);
+
+    let broadcaster = web::Data::new(Broadcaster::new());
+    let sync_job = web::Data::new(SyncJob::new(SyncConfig::from_env(), broadcaster.get_ref().clone()));
+    actix_web::rt::spawn(sync::run(sync_job.clone()));
+
+    HttpServer::new(move || {
         App::new()
-            .service(routes::status)
-            .service(routes::rating)
-            .service(routes::user_by_id)
-            .service(routes::get_past_result_by_game_id)
+            .app_data(broadcaster.clone())
+            .app_data(sync_job.clone())
+            .app_data(auth_secret.clone())
+            .configure(routes::configure)
     })
         .bind("127.0.0.1:8080")?
         .run()
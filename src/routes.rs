@@ -1,255 +1,2446 @@
-use std::collections::{HashMap, HashSet};
-use actix_web::{get, HttpResponse, Responder, Result as ActixResult, web};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{delete, get, patch, post, put, HttpRequest, HttpResponse, Responder, Result as ActixResult, web};
 use serde_derive::{Deserialize, Serialize};
 use crate::{db, service};
-use crate::service::{calculate_positions, MatchInfo, UserRating};
+use crate::auth::{self, AuthSecret, AuthUser, TournamentAdmin};
+use crate::db::Role;
+use crate::error::{ApiError, ErrorResponse};
+use crate::events::{Broadcaster, Event};
+use crate::db::leagues::{League, LeagueMember, LeagueRole};
+use crate::db::profiles::{NewUser, Profile, TakenField};
+use crate::db::tournaments::{ChampionPick, Tournament};
+use crate::service::{calculate_positions, MatchInfo, Team, UserRating};
+use crate::service::tables::{live_games, rating_table, rating_table_with_rules, RatingTable};
+use crate::service::all_time::TournamentRating;
+use crate::service::crowd::CrowdMode;
+use crate::service::ranking::RuleSet;
+use crate::service::picks::PickDistribution;
+use crate::service::simulation::HypotheticalResult;
+use crate::service::departments::DepartmentRating;
+use crate::service::groups::{GroupTable, ThirdPlacedStanding};
+use crate::sync::{SyncJob, SyncStatus};
+
+const DEFAULT_FORECAST_ITERATIONS: u32 = 10_000;
+const MAX_FORECAST_ITERATIONS: u32 = 20_000;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct StatusResponse {
     status: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RatingResponse {
-    pub global: Vec<UserRating>,
-    pub departments: HashMap<String, Vec<UserRating>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sync: Option<SyncStatus>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Response {
-    pub table: RatingResponse,
+    pub table: RatingTable,
+    /// Like `table`, but with the current scores of running matches counted.
+    pub live: RatingTable,
     daily_winner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picks: Option<PickDistribution>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserResponse {
     pub data: UserRating,
+    /// Like `data`, but with the current scores of running matches counted.
+    pub live: UserRating,
 }
 
-#[get("/rating")]
-pub async fn rating() -> ActixResult<impl Responder> {
-    let mut user_rating_list = service::get_user_rating(
-        db::get_past_games().unwrap(), db::get_users().unwrap()
-    ).unwrap();
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameTipsResponse {
+    pub tips: Vec<MatchInfo>,
+    /// Like `tips`, but with the current score if the match is running.
+    pub live: Vec<MatchInfo>,
+}
 
-    let cloned_user_rating_list = user_rating_list.clone();
-    let mut departments: HashSet<String> = HashSet::new();
-    let mut department_ratings: HashMap<String, Vec<UserRating>> = HashMap::new();
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepartmentRatingTable {
+    pub departments: Vec<DepartmentRating>,
+}
 
-    for user_rating in &cloned_user_rating_list {
-        departments.insert(user_rating.department.clone());
-    }
+#[derive(Debug, Deserialize)]
+pub struct DepartmentRatingQuery {
+    pub best: Option<usize>,
+}
 
-    for department in departments {
-        let mut department_users: Vec<UserRating> = cloned_user_rating_list.iter().filter(|user| user.department == department).cloned().collect();
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub department: Option<String>,
+}
 
-        calculate_positions(&mut department_users, true);
-        department_ratings.insert(department, department_users);
-    }
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupsResponse {
+    pub groups: Vec<GroupTable>,
+    pub third_placed: Vec<ThirdPlacedStanding>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeagueResponse {
+    pub league: League,
+    pub members: Vec<LeagueMember>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeagueRatingTable {
+    pub league: League,
+    pub rating: Vec<UserRating>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLeagueRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JoinLeagueRequest {
+    pub invite_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeagueRoleRequest {
+    pub role: LeagueRole,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleRequest {
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameResultRequest {
+    pub home_score: i32,
+    pub away_score: i32,
+    /// The match is still running and the score is provisional.
+    #[serde(default)]
+    pub live: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegistrationRequest {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub username: String,
+    pub password: String,
+    pub department: String,
+    #[serde(default)]
+    pub winner: String,
+    #[serde(default)]
+    pub secret_winner: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileUpdateRequest {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub username: Option<String>,
+    pub department: Option<String>,
+    pub winner: Option<String>,
+    pub secret_winner: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RatingQuery {
+    pub crowd: Option<CrowdMode>,
+    /// Overrides the rules of the tournament.
+    pub rules: Option<RuleSet>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    pub iterations: Option<u32>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeamStrengthRequest {
+    pub attack: f64,
+    pub defence: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulationRequest {
+    pub results: Vec<HypotheticalResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimulationResponse {
+    pub table: RatingTable,
+    pub results: Vec<HypotheticalResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PicksRequest {
+    pub winner: String,
+    pub secret_winner: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PicksResponse {
+    pub user_id: i32,
+    pub winner: Option<String>,
+    pub secret_winner: Option<String>,
+    pub locked: bool,
+    pub locks_at: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TournamentRequest {
+    pub name: String,
+    #[serde(default)]
+    pub rule_set: RuleSet,
+    pub champion: Option<String>,
+}
+
+// Path parameters are taken by name, since scoped routes also have the `slug`.
+#[derive(Debug, Deserialize)]
+pub struct UserPath {
+    pub user_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComparePath {
+    pub user_a: i32,
+    pub user_b: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GamePath {
+    pub game_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CalendarPath {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeamPath {
+    pub tla: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupPath {
+    pub group_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaguePath {
+    pub league_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeagueMemberPath {
+    pub league_id: i32,
+    pub member_id: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarResponse {
+    pub token: String,
+    /// Path of the feed of the default tournament.
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub user_id: i32,
+    pub expires_at: u64,
+}
+
+#[get("/rating")]
+pub async fn rating(tournament: Tournament, query: web::Query<RatingQuery>) -> ActixResult<impl Responder> {
+    let rules = query.rules.unwrap_or(tournament.rule_set);
+    let tips = query.crowd.map(|_| db::get_tips().unwrap());
+    let table_with_crowd = |games: Vec<db::Game>| {
+        let crowd = query.crowd.zip(tips.as_ref())
+            .map(|(crowd_mode, tips)| service::crowd::get_crowd_rating(&games, tips, crowd_mode, rules));
+
+        let mut rating_response = rating_table_with_rules(&tournament, games, rules);
+
+        // The crowd is ranked in the global table only, it belongs to no department.
+        if let Some(crowd) = crowd {
+            rating_response.global.push(crowd);
+            calculate_positions(&mut rating_response.global, rules, true);
+        }
+
+        rating_response
+    };
 
-    calculate_positions(&mut user_rating_list, true);
-    let rating_response = RatingResponse {
-        global: user_rating_list,
-        departments: department_ratings,
+    let picks = if picks_locked(tournament.id) {
+        Some(service::picks::get_pick_distribution(&db::get_users(tournament.id).unwrap()))
+    } else {
+        None
     };
 
     let response = Response {
-        table: rating_response,
+        table: table_with_crowd(db::get_past_games(tournament.id).unwrap()),
+        live: table_with_crowd(live_games(tournament.id)),
         daily_winner: None,
+        picks,
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
-#[get("/user/{user_id}")]
-pub async fn user_by_id(user_id: web::Path<i32>) -> ActixResult<impl Responder> {
-    let mut user_rating_list = service::get_user_rating(
-        db::get_past_games().unwrap(), db::get_users().unwrap()
-    ).unwrap();
+#[get("/rating/max-points")]
+pub async fn rating_max_points(tournament: Tournament) -> ActixResult<impl Responder> {
+    let users = db::get_users(tournament.id).unwrap();
+    let user_rating_list = user_rating(&tournament, db::get_past_games(tournament.id).unwrap(), users.clone());
 
-    let user_id = user_id.into_inner();
-    calculate_positions(&mut user_rating_list, false);
-    let find_user = user_rating_list.iter().find(|user| user.user_id == user_id).cloned();
+    let mut tipped_matches: HashMap<i32, HashSet<i32>> = HashMap::new();
+    for user in &users {
+        let tips = db::get_tips_by_user(user.id).unwrap();
+        tipped_matches.insert(user.id, tips.iter().map(|tip| tip.match_id).collect());
+    }
 
-    let response = match find_user {
-        Some(mut user) => {
-            user.tips.sort_by(|a, b| b.date.cmp(&a.date));
-            UserResponse { data: user }
-        },
-        None => return Ok(HttpResponse::NotFound().body("User not found")),
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let max_points_list = service::elimination::get_max_points(
+        &user_rating_list, &users, &db::get_unfinished_games(tournament.id).unwrap(), &tipped_matches,
+        tournament.rule_set, tournament.champion.as_deref(), now,
+    );
+
+    Ok(HttpResponse::Ok().json(max_points_list))
+}
+
+#[get("/forecast")]
+pub async fn forecast(tournament: Tournament, query: web::Query<ForecastQuery>) -> ActixResult<impl Responder> {
+    let iterations = query.iterations.unwrap_or(DEFAULT_FORECAST_ITERATIONS);
+    if iterations == 0 || iterations > MAX_FORECAST_ITERATIONS {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(&format!("iterations must be between 1 and {}", MAX_FORECAST_ITERATIONS))));
+    }
+
+    let seed = query.seed.unwrap_or_else(rand::random);
+    // The simulation takes a while, so it must not hold up a worker thread.
+    let forecast = web::block(move || {
+        let past_games = db::get_past_games(tournament.id).unwrap();
+        let model = service::forecast::GoalModel::new(&past_games, &db::get_team_strengths(tournament.id).unwrap()).unwrap();
+        let users = db::get_users(tournament.id).unwrap();
+
+        let mut tips_by_user = HashMap::new();
+        for user in &users {
+            tips_by_user.insert(user.id, db::get_tips_by_user(user.id).unwrap());
+        }
+
+        let user_rating_list = user_rating(&tournament, past_games, users);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        service::forecast::get_forecast(
+            &user_rating_list,
+            &db::get_unfinished_games(tournament.id).unwrap(),
+            &tips_by_user,
+            &model,
+            tournament.rule_set,
+            service::forecast::ForecastRun { iterations, seed, now },
+        ).unwrap()
+    }).await?;
+
+    Ok(HttpResponse::Ok().json(forecast))
+}
+
+#[post("/simulate")]
+pub async fn simulate(tournament: Tournament, request: web::Json<SimulationRequest>) -> ActixResult<impl Responder> {
+    let games = match service::simulation::with_hypothetical_results(
+        db::get_past_games(tournament.id).unwrap(), &db::get_unfinished_games(tournament.id).unwrap(), &request.results,
+    ) {
+        Ok(games) => games,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(error))),
+    };
+
+    let response = SimulationResponse {
+        table: rating_table(&tournament, games),
+        results: request.into_inner().results,
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
-#[get("/game/{game_id}")]
-pub async fn get_past_result_by_game_id(game_id: web::Path<String>) -> ActixResult<impl Responder> {
-    let user_rating_list = service::get_user_rating(
-        db::get_past_games().unwrap(), db::get_users().unwrap()
-    ).unwrap();
+// Ratings under the rules and with the champion of the tournament.
+fn user_rating(tournament: &Tournament, games: Vec<db::Game>, users: Vec<db::User>) -> Vec<UserRating> {
+    service::get_user_rating(games, users, tournament.rule_set, tournament.champion.as_deref()).unwrap()
+}
 
-    let game_id = game_id.into_inner();
+#[get("/rating/all-time")]
+pub async fn all_time_rating() -> ActixResult<impl Responder> {
+    let tournament_ratings = db::tournaments::get_tournaments().unwrap().into_iter()
+        .map(|tournament| {
+            let games = db::get_past_games(tournament.id).unwrap();
 
-    let tips_with_match_id: Vec<&MatchInfo> = user_rating_list.iter()
-        .flat_map(|user_rating| &user_rating.tips)
-        .filter(|tip| tip.match_id == game_id)
+            TournamentRating {
+                max_points: tournament.rule_set.max_points(games.len(), tournament.champion.as_deref()),
+                rule_set: tournament.rule_set,
+                finished: !games.is_empty() && db::get_unfinished_games(tournament.id).unwrap().is_empty(),
+                user_rating_list: user_rating(&tournament, games, db::get_users(tournament.id).unwrap()),
+                slug: tournament.slug,
+            }
+        })
         .collect();
 
-    Ok(HttpResponse::Ok().json(tips_with_match_id))
+    Ok(HttpResponse::Ok().json(service::all_time::get_all_time_rating(tournament_ratings)))
 }
 
-#[get("/")]
-pub async fn status() -> ActixResult<impl Responder> {
-    let response = StatusResponse {
-        status: String::from("works"),
+#[get("/rating.csv")]
+pub async fn rating_csv(tournament: Tournament, query: web::Query<ExportQuery>) -> ActixResult<impl Responder> {
+    let mut table = rating_table(&tournament, db::get_past_games(tournament.id).unwrap());
+    let user_rating_list = match &query.department {
+        None => table.global,
+        Some(department) => match table.departments.remove(department) {
+            Some(user_rating_list) => user_rating_list,
+            None => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("Department not found"))),
+        },
     };
 
-    Ok(HttpResponse::Ok().json(response))
+    let csv = service::export::rating_csv(&user_rating_list).unwrap();
+
+    Ok(download("text/csv; charset=utf-8", &format!("rating-{}.csv", tournament.slug), csv))
 }
 
-#[cfg(test)]
-mod tests {
-    use std::env;
-    use actix_web::{test, App};
-    use actix_web::dev::ServiceResponse;
+#[get("/rating.xlsx")]
+pub async fn rating_xlsx(tournament: Tournament) -> ActixResult<impl Responder> {
+    let table = rating_table(&tournament, db::get_past_games(tournament.id).unwrap());
+    let departments: BTreeMap<String, Vec<UserRating>> = table.departments.into_iter().collect();
 
-    use super::*;
+    let xlsx = service::export::rating_xlsx(&table.global, &departments).unwrap();
 
-    #[actix_web::test]
-    async fn test_get_user_rating() {
-        let resp = get_response_by_url("/rating").await;
+    Ok(download(
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        &format!("rating-{}.xlsx", tournament.slug),
+        xlsx,
+    ))
+}
 
-        assert!(resp.status().is_success());
+#[get("/export/tips.csv")]
+pub async fn tips_csv(tournament: Tournament) -> ActixResult<impl Responder> {
+    let user_rating_list = user_rating(&tournament, db::get_past_games(tournament.id).unwrap(), db::get_users(tournament.id).unwrap());
 
-        let result: Response = test::read_body_json(resp).await;
+    let csv = service::export::tips_csv(&user_rating_list).unwrap();
 
-        assert_eq!(result.table.departments.len(), 2);
-        assert_eq!(result.table.global.len(), 7);
+    Ok(download("text/csv; charset=utf-8", &format!("tips-{}.csv", tournament.slug), csv))
+}
 
-        let department = result.table.departments.get("Langenfeld").unwrap();
-        assert_eq!(department.len(), 4);
-        assert_eq!(department[0].name, "ToniKroos");
-        assert_eq!(department[0].department, "Langenfeld");
+fn download(content_type: &str, filename: &str, body: Vec<u8>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        .body(body)
+}
 
-        let department = result.table.departments.get("London").unwrap();
-        assert_eq!(department.len(), 3);
-        assert_eq!(department[0].name, "RobbieFowler");
-        assert_eq!(department[0].department, "London");
+#[get("/calendar.ics")]
+pub async fn calendar(tournament: Tournament) -> ActixResult<impl Responder> {
+    Ok(calendar_response(&tournament, None))
+}
 
-        let global = &result.table.global;
-        assert_eq!(global[0].name, "ToniKroos");
-        assert_eq!(global[0].department, "Langenfeld");
-        assert_eq!(global[0].score_sum, 21);
-        assert_eq!(global[0].position, 1);
-        assert_eq!(global[0].sum_win_exact, 1);
-        assert_eq!(global[0].sum_score_diff, 1);
-        assert_eq!(global[0].extra_point, 15);
-        assert_eq!(global[0].sum_team, 0);
-        assert_eq!(global[0].tips.len(), 0);
+#[get("/calendar/{token}.ics")]
+pub async fn user_calendar(tournament: Tournament, path: web::Path<CalendarPath>) -> ActixResult<impl Responder> {
+    let user_id = match db::calendar::find_calendar_user(&path.token).unwrap() {
+        Some(user_id) => user_id,
+        None => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("Calendar not found"))),
+    };
 
-        assert_eq!(global[1].name, "JohnDoe");
-        assert_eq!(global[1].department, "Langenfeld");
-        assert_eq!(global[1].score_sum, 11);
-        assert_eq!(global[1].position, 2);
-        assert_eq!(global[1].extra_point, 7);
+    let tips: HashMap<i32, db::Tip> = db::get_tips_by_user(user_id).unwrap()
+        .into_iter()
+        .map(|tip| (tip.match_id, tip))
+        .collect();
 
+    Ok(calendar_response(&tournament, Some(&tips)))
+}
 
-        assert_eq!(global[2].name, "RobbieFowler");
-        assert_eq!(global[2].department, "London");
-        assert_eq!(global[2].score_sum, 11);
-        assert_eq!(global[2].position, 2);
+#[get("/me/calendar")]
+pub async fn my_calendar(auth_user: AuthUser) -> ActixResult<impl Responder> {
+    Ok(HttpResponse::Ok().json(calendar_token_response(db::calendar::get_calendar_token(auth_user.user_id).unwrap())))
+}
 
-        assert_eq!(global[6].name, "SteveMcManaman");
-        assert_eq!(global[6].position, 5);
+#[post("/me/calendar/reset")]
+pub async fn reset_my_calendar(auth_user: AuthUser) -> ActixResult<impl Responder> {
+    Ok(HttpResponse::Ok().json(calendar_token_response(db::calendar::reset_calendar_token(auth_user.user_id).unwrap())))
+}
+
+fn calendar_token_response(token: String) -> CalendarResponse {
+    CalendarResponse { path: format!("/calendar/{}.ics", token), token }
+}
+
+fn calendar_response(tournament: &Tournament, tips: Option<&HashMap<i32, db::Tip>>) -> HttpResponse {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let games = db::calendar::get_calendar_games(tournament.id).unwrap();
+
+    HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(service::calendar::to_ics(&tournament.name, &tournament.slug, &games, tips, now))
+}
+
+#[get("/rating/departments")]
+pub async fn department_rating(tournament: Tournament, query: web::Query<DepartmentRatingQuery>) -> ActixResult<impl Responder> {
+    if query.best == Some(0) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("best must be greater than 0")));
     }
 
-    #[actix_rt::test]
-    async fn test_user_by_id_returns_user_when_exists() {
-        let resp = get_response_by_url("/user/2").await;
+    let user_rating_list = user_rating(&tournament, db::get_past_games(tournament.id).unwrap(), db::get_users(tournament.id).unwrap());
 
-        assert!(resp.status().is_success());
+    let response = DepartmentRatingTable {
+        departments: service::departments::get_department_rating(&user_rating_list, query.best),
+    };
 
-        let result: UserResponse = test::read_body_json(resp).await;
+    Ok(HttpResponse::Ok().json(response))
+}
 
-        assert_eq!(result.data.user_id, 2);
-        assert_eq!(result.data.name, "ToniKroos");
-        assert_eq!(result.data.department, "Langenfeld");
-        assert_eq!(result.data.score_sum, 21);
-        assert_eq!(result.data.position, 1);
-        assert_eq!(result.data.sum_win_exact, 1);
-        assert_eq!(result.data.extra_point, 15);
-        assert_eq!(result.data.sum_team, 0);
-        assert_eq!(result.data.tips.len(), 2);
+#[post("/login")]
+pub async fn login(secret: web::Data<AuthSecret>, request: web::Json<LoginRequest>) -> ActixResult<impl Responder> {
+    let credentials = db::get_user_credentials(&request.email).unwrap();
 
-        assert_eq!(result.data.tips[0].match_id, "2".to_string());
-        assert_eq!(result.data.tips[0].team1.name, "Poland");
-        assert_eq!(result.data.tips[0].team2.name, "France");
+    match credentials {
+        Some((user_id, password_hash)) if auth::verify_password(&request.password, &password_hash) => {
+            let (token, expires_at) = auth::issue_token(&secret, user_id);
+            Ok(HttpResponse::Ok().json(LoginResponse { token, user_id, expires_at }))
+        },
+        _ => Ok(HttpResponse::Unauthorized().json(ErrorResponse::new("Invalid email or password"))),
+    }
+}
 
-        assert_eq!(result.data.tips[1].score, 2);
+#[post("/users")]
+pub async fn register(request: web::Json<RegistrationRequest>) -> ActixResult<impl Responder> {
+    let request = request.into_inner();
+    let new_user = NewUser {
+        email: request.email.trim().to_string(),
+        first_name: request.first_name.trim().to_string(),
+        last_name: request.last_name.trim().to_string(),
+        username: request.username.trim().to_string(),
+        department: request.department.trim().to_string(),
+        winner: request.winner.trim().to_string(),
+        secret_winner: request.secret_winner.trim().to_string(),
+        password_hash: String::new(),
+    };
+
+    let validation = service::profiles::validate_email(&new_user.email)
+        .and_then(|_| service::profiles::validate_username(&new_user.username))
+        .and_then(|_| service::profiles::validate_name(&new_user.first_name))
+        .and_then(|_| service::profiles::validate_name(&new_user.last_name))
+        .and_then(|_| service::profiles::validate_password(&request.password));
+    if let Err(error) = validation {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(error)));
     }
 
-    #[actix_rt::test]
-    async fn user_by_id_returns_not_found_when_user_does_not_exist() {
-        let resp = get_response_by_url("/user/99999").await;
+    if !service::profiles::allowed_departments().unwrap().contains(&new_user.department) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("Unknown department")));
+    }
 
-        assert_eq!(resp.status(), 404);
+    if !new_user.winner.is_empty() || !new_user.secret_winner.is_empty() {
+        if let Some(response) = check_picks(db::DEFAULT_TOURNAMENT_ID, &new_user.winner, &new_user.secret_winner) {
+            return Ok(response);
+        }
     }
 
-    #[actix_web::test]
-    async fn test_get_past_result_by_game_id() {
-        let resp = get_response_by_url("/game/2").await;
+    if db::profiles::is_email_taken(&new_user.email, None).unwrap() {
+        return Ok(HttpResponse::Conflict().json(ErrorResponse::new("Email address is already registered")));
+    }
 
-        assert!(resp.status().is_success());
+    if db::profiles::is_username_taken(&new_user.username, None).unwrap() {
+        return Ok(HttpResponse::Conflict().json(ErrorResponse::new("Username is already taken")));
+    }
 
-        let result: Vec<MatchInfo> = test::read_body_json(resp).await;
+    let new_user = NewUser { password_hash: auth::hash_password(&request.password), ..new_user };
+    let user_id = match db::profiles::insert_user(&new_user) {
+        Ok(user_id) => user_id,
+        Err(error) => return taken_response(&error),
+    };
 
-        assert!(!result.is_empty());
+    let profile = Profile {
+        id: user_id,
+        email: new_user.email,
+        first_name: new_user.first_name,
+        last_name: new_user.last_name,
+        username: new_user.username,
+        department: new_user.department,
+        winner: new_user.winner,
+        secret_winner: new_user.secret_winner,
+    };
 
-        assert_eq!(result.len(), 7);
-        assert_eq!(result[0].match_id, "2".to_string());
-        assert_eq!(result[0].user, "JohnDoe");
-        assert_eq!(result[0].score, 0);
+    Ok(HttpResponse::Created().json(profile))
+}
 
-        assert_eq!(result[1].match_id, "2".to_string());
-        assert_eq!(result[1].user, "ToniKroos");
-        assert_eq!(result[1].score, 4);
+#[get("/me/profile")]
+pub async fn my_profile(auth_user: AuthUser) -> ActixResult<impl Responder> {
+    match db::profiles::get_profile(auth_user.user_id).unwrap() {
+        Some(profile) => Ok(HttpResponse::Ok().json(profile)),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found"))),
+    }
+}
 
-        assert_eq!(result[2].match_id, "2".to_string());
-        assert_eq!(result[2].team1.name, "Poland");
-        assert_eq!(result[2].team2.name, "France");
-        assert_eq!(result[2].score, 1);
-        assert_eq!(result[2].tip_home, Some(2));
-        assert_eq!(result[2].tip_away, Some(2));
-        assert_eq!(result[2].score_home, Some(1));
-        assert_eq!(result[2].score_away, Some(1));
+#[patch("/me")]
+pub async fn update_me(auth_user: AuthUser, request: web::Json<ProfileUpdateRequest>) -> ActixResult<impl Responder> {
+    let mut profile = match db::profiles::get_profile(auth_user.user_id).unwrap() {
+        Some(profile) => profile,
+        None => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found"))),
+    };
+    let request = request.into_inner();
+    let trimmed = |value: Option<String>| value.map(|value| value.trim().to_string());
+
+    if let Some(first_name) = trimmed(request.first_name) {
+        if let Err(error) = service::profiles::validate_name(&first_name) {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(error)));
+        }
+        profile.first_name = first_name;
     }
 
-    #[actix_web::test]
-    async fn test_status() {
-        let resp = get_response_by_url("/").await;
+    if let Some(last_name) = trimmed(request.last_name) {
+        if let Err(error) = service::profiles::validate_name(&last_name) {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(error)));
+        }
+        profile.last_name = last_name;
+    }
 
-        assert!(resp.status().is_success());
+    if let Some(username) = trimmed(request.username) {
+        if let Err(error) = service::profiles::validate_username(&username) {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse::new(error)));
+        }
+        if db::profiles::is_username_taken(&username, Some(profile.id)).unwrap() {
+            return Ok(HttpResponse::Conflict().json(ErrorResponse::new("Username is already taken")));
+        }
+        profile.username = username;
+    }
 
-        let result: serde_json::Value = test::read_body_json(resp).await;
-        assert_eq!(result.get("status").unwrap().as_str().unwrap(), "works");
+    if let Some(department) = trimmed(request.department) {
+        if !service::profiles::allowed_departments().unwrap().contains(&department) {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("Unknown department")));
+        }
+        profile.department = department;
     }
 
-    async fn get_response_by_url(url: &str) -> ServiceResponse {
-        env::set_var("MODE", "test");
+    let winner = trimmed(request.winner).unwrap_or_else(|| profile.winner.clone());
+    let secret_winner = trimmed(request.secret_winner).unwrap_or_else(|| profile.secret_winner.clone());
+    if winner != profile.winner || secret_winner != profile.secret_winner {
+        if let Some(response) = check_picks(db::DEFAULT_TOURNAMENT_ID, &winner, &secret_winner) {
+            return Ok(response);
+        }
+        profile.winner = winner;
+        profile.secret_winner = secret_winner;
+    }
 
-        let app = test::init_service(
-            App::new()
-                .service(get_past_result_by_game_id)
-                .service(status)
-                .service(user_by_id)
-                .service(rating)
-        ).await;
+    if let Err(error) = db::profiles::update_profile(&profile) {
+        return taken_response(&error);
+    }
+
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+// A username or email address that was taken after the check is a conflict as well.
+fn taken_response(error: &rusqlite::Error) -> ActixResult<HttpResponse> {
+    match db::profiles::taken_field(error) {
+        Some(TakenField::Email) => Ok(HttpResponse::Conflict().json(ErrorResponse::new("Email address is already registered"))),
+        Some(TakenField::Username) => Ok(HttpResponse::Conflict().json(ErrorResponse::new("Username is already taken"))),
+        None => Err(ApiError::internal().into()),
+    }
+}
+
+#[get("/teams")]
+pub async fn teams(tournament: Tournament) -> ActixResult<impl Responder> {
+    let team_list: Vec<Team> = service::picks::get_participating_teams(&db::get_teams(tournament.id).unwrap()).unwrap();
 
-        let req = test::TestRequest::get()
-            .uri(url)
-            .to_request();
+    Ok(HttpResponse::Ok().json(team_list))
+}
+
+#[get("/me/picks")]
+pub async fn my_picks(tournament: Tournament, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    Ok(picks_response(tournament.id, auth_user.user_id, true))
+}
+
+#[put("/me/picks")]
+pub async fn update_my_picks(tournament: Tournament, auth_user: AuthUser, request: web::Json<PicksRequest>) -> ActixResult<impl Responder> {
+    if db::tournaments::get_champion_pick(tournament.id, auth_user.user_id).unwrap().is_none() {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found")));
+    }
+
+    let pick = ChampionPick {
+        winner: request.winner.trim().to_string(),
+        secret_winner: request.secret_winner.trim().to_string(),
+    };
+    if let Some(response) = check_picks(tournament.id, &pick.winner, &pick.secret_winner) {
+        return Ok(response);
+    }
+
+    db::tournaments::set_champion_pick(tournament.id, auth_user.user_id, &pick).unwrap();
+
+    Ok(HttpResponse::Ok().json(PicksResponse {
+        user_id: auth_user.user_id,
+        winner: Some(pick.winner),
+        secret_winner: Some(pick.secret_winner),
+        locked: false,
+        locks_at: db::get_first_kickoff(tournament.id).unwrap(),
+    }))
+}
+
+#[get("/user/{user_id}/picks")]
+pub async fn user_picks(tournament: Tournament, path: web::Path<UserPath>, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let user_id = path.user_id;
+
+    Ok(picks_response(tournament.id, user_id, user_id == auth_user.user_id))
+}
+
+// Picks of other users stay hidden until they are locked.
+fn picks_response(tournament_id: i32, user_id: i32, is_own: bool) -> HttpResponse {
+    let pick = match db::tournaments::get_champion_pick(tournament_id, user_id).unwrap() {
+        Some(pick) => pick,
+        None => return HttpResponse::NotFound().json(ErrorResponse::new("User not found")),
+    };
+
+    let locked = picks_locked(tournament_id);
+    let visible = is_own || locked;
+
+    HttpResponse::Ok().json(PicksResponse {
+        user_id,
+        winner: visible.then_some(pick.winner),
+        secret_winner: visible.then_some(pick.secret_winner),
+        locked,
+        locks_at: db::get_first_kickoff(tournament_id).unwrap(),
+    })
+}
+
+// The error response if the picks cannot be saved.
+fn check_picks(tournament_id: i32, winner: &str, secret_winner: &str) -> Option<HttpResponse> {
+    let team_list = service::picks::get_participating_teams(&db::get_teams(tournament_id).unwrap()).unwrap();
+
+    if let Err(error) = service::picks::validate_pick(winner, &team_list).and_then(|_| service::picks::validate_pick(secret_winner, &team_list)) {
+        return Some(HttpResponse::BadRequest().json(ErrorResponse::new(error)));
+    }
+
+    if picks_locked(tournament_id) {
+        return Some(HttpResponse::Forbidden().json(ErrorResponse::new("Champion picks are locked since the tournament started")));
+    }
+
+    None
+}
+
+fn picks_locked(tournament_id: i32) -> bool {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    service::profiles::picks_locked(db::get_first_kickoff(tournament_id).unwrap(), now)
+}
+
+#[get("/me")]
+pub async fn me(tournament: Tournament, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    Ok(user_response(&tournament, auth_user.user_id))
+}
+
+#[get("/user/{user_id}")]
+pub async fn user_by_id(tournament: Tournament, path: web::Path<UserPath>, _auth_user: AuthUser) -> ActixResult<impl Responder> {
+    Ok(user_response(&tournament, path.user_id))
+}
+
+#[get("/user/{user_id}/stats")]
+pub async fn user_stats(tournament: Tournament, path: web::Path<UserPath>, _auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let user_id = path.user_id;
+    let users: Vec<db::User> = db::get_users(tournament.id).unwrap().into_iter().filter(|user| user.id == user_id).collect();
+
+    let user_rating_list = user_rating(&tournament, db::get_past_games(tournament.id).unwrap(), users);
+
+    match user_rating_list.first() {
+        Some(user_rating) => Ok(HttpResponse::Ok().json(service::stats::get_user_stats(user_rating, tournament.rule_set))),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found"))),
+    }
+}
+
+#[get("/compare/{user_a}/{user_b}")]
+pub async fn compare(tournament: Tournament, path: web::Path<ComparePath>, _auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let ComparePath { user_a, user_b } = path.into_inner();
+    let user_rating_list = user_rating(&tournament, db::get_past_games(tournament.id).unwrap(), db::get_users(tournament.id).unwrap());
+
+    let find_user = |user_id: i32| user_rating_list.iter().find(|user| user.user_id == user_id);
+    let (rating_a, rating_b) = match (find_user(user_a), find_user(user_b)) {
+        (Some(rating_a), Some(rating_b)) => (rating_a, rating_b),
+        _ => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found"))),
+    };
+
+    // Only matches that kicked off have revealed tips.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let started: Vec<db::ScheduledGame> = db::get_unfinished_games(tournament.id).unwrap().into_iter()
+        .filter(|game| game.date <= now)
+        .collect();
+
+    let comparison = service::compare::compare_users(
+        rating_a,
+        rating_b,
+        &started,
+        &db::get_tips_by_user(user_a).unwrap(),
+        &db::get_tips_by_user(user_b).unwrap(),
+    ).unwrap();
+
+    Ok(HttpResponse::Ok().json(comparison))
+}
+
+fn user_response(tournament: &Tournament, user_id: i32) -> HttpResponse {
+    let users = db::get_users(tournament.id).unwrap();
+    let find_user = |games: Vec<db::Game>| {
+        let mut user_rating_list = user_rating(tournament, games, users.clone());
+
+        calculate_positions(&mut user_rating_list, tournament.rule_set, false);
+        user_rating_list.into_iter().find(|user| user.user_id == user_id).map(|mut user| {
+            user.tips.sort_by_key(|tip| Reverse(tip.date));
+            user
+        })
+    };
+
+    let response = match (find_user(db::get_past_games(tournament.id).unwrap()), find_user(live_games(tournament.id))) {
+        (Some(data), Some(live)) => UserResponse { data, live },
+        _ => return HttpResponse::NotFound().json(ErrorResponse::new("User not found")),
+    };
+
+    HttpResponse::Ok().json(response)
+}
+
+#[get("/game/{game_id}")]
+pub async fn get_past_result_by_game_id(tournament: Tournament, path: web::Path<GamePath>, _auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let users = db::get_users(tournament.id).unwrap();
+    let game_id = path.game_id.to_string();
+    let tips_with_match_id = |games: Vec<db::Game>| -> Vec<MatchInfo> {
+        user_rating(&tournament, games, users.clone()).into_iter()
+            .flat_map(|user_rating| user_rating.tips)
+            .filter(|tip| tip.match_id == game_id)
+            .collect()
+    };
+
+    Ok(HttpResponse::Ok().json(GameTipsResponse {
+        tips: tips_with_match_id(db::get_past_games(tournament.id).unwrap()),
+        live: tips_with_match_id(live_games(tournament.id)),
+    }))
+}
+
+#[get("/game/{game_id}/stats")]
+pub async fn game_stats(tournament: Tournament, path: web::Path<GamePath>) -> ActixResult<impl Responder> {
+    let game = match db::get_game(tournament.id, path.game_id).unwrap() {
+        Some(game) => game,
+        None => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("Game not found"))),
+    };
+
+    // Tips of other users are only revealed at kickoff.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    if game.date > now {
+        return Ok(HttpResponse::Forbidden().json(ErrorResponse::new("Tips are revealed at kickoff")));
+    }
+
+    let tips = db::get_tips_by_match(game.id).unwrap();
+
+    Ok(HttpResponse::Ok().json(service::stats::get_game_stats(&game, &tips, tournament.rule_set)))
+}
+
+#[get("/events")]
+pub async fn events(tournament: Tournament, broadcaster: web::Data<Broadcaster>) -> ActixResult<impl Responder> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(broadcaster.sse_stream(tournament.slug.clone())))
+}
+
+#[get("/ws")]
+pub async fn ws(tournament: Tournament, req: HttpRequest, body: web::Payload, broadcaster: web::Data<Broadcaster>) -> ActixResult<HttpResponse> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+    actix_web::rt::spawn(crate::ws::run_session(session, stream, broadcaster.subscribe(), tournament.slug.clone()));
+
+    Ok(response)
+}
+
+#[get("/groups")]
+pub async fn groups(tournament: Tournament) -> ActixResult<impl Responder> {
+    let group_tables = service::groups::get_group_tables(&db::get_group_games(tournament.id).unwrap()).unwrap();
+    let third_placed = service::groups::rank_third_placed(&group_tables);
+
+    let response = GroupsResponse {
+        groups: group_tables,
+        third_placed,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[get("/groups/{group_id}")]
+pub async fn group_by_id(tournament: Tournament, path: web::Path<GroupPath>) -> ActixResult<impl Responder> {
+    let group_tables = service::groups::get_group_tables(&db::get_group_games(tournament.id).unwrap()).unwrap();
+
+    let group_id = path.into_inner().group_id.to_uppercase();
+    let group_name = if group_id.starts_with("GROUP_") { group_id } else { format!("GROUP_{}", group_id) };
+
+    match group_tables.into_iter().find(|table| table.group == group_name) {
+        Some(group_table) => Ok(HttpResponse::Ok().json(group_table)),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("Group not found"))),
+    }
+}
+
+#[post("/league")]
+pub async fn create_league(tournament: Tournament, auth_user: AuthUser, request: web::Json<CreateLeagueRequest>) -> ActixResult<impl Responder> {
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("League name must have between 1 and 64 characters")));
+    }
+
+    if !db::get_users(tournament.id).unwrap().iter().any(|user| user.id == auth_user.user_id) {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found")));
+    }
+
+    let league = db::leagues::create_league(tournament.id, name, auth_user.user_id).unwrap();
+
+    Ok(HttpResponse::Created().json(league))
+}
+
+#[post("/league/join")]
+pub async fn join_league(tournament: Tournament, auth_user: AuthUser, request: web::Json<JoinLeagueRequest>) -> ActixResult<impl Responder> {
+    match db::leagues::join_league(tournament.id, &request.invite_code, auth_user.user_id).unwrap() {
+        Some(league) => Ok(HttpResponse::Ok().json(league)),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("League not found"))),
+    }
+}
+
+#[get("/league/{league_id}")]
+pub async fn league_by_id(tournament: Tournament, path: web::Path<LeaguePath>, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let league_id = path.league_id;
+
+    let league = match db::leagues::get_league(tournament.id, league_id).unwrap() {
+        Some(league) => league,
+        None => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("League not found"))),
+    };
+
+    if acting_league_role(tournament.id, league_id, auth_user.user_id).is_none() {
+        return Ok(HttpResponse::Forbidden().json(ErrorResponse::new("Only members can see the league")));
+    }
+
+    let response = LeagueResponse {
+        league,
+        members: db::leagues::get_league_members(league_id).unwrap(),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[get("/league/{league_id}/rating")]
+pub async fn league_rating(tournament: Tournament, path: web::Path<LeaguePath>, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let league_id = path.league_id;
+
+    let league = match db::leagues::get_league(tournament.id, league_id).unwrap() {
+        Some(league) => league,
+        None => return Ok(HttpResponse::NotFound().json(ErrorResponse::new("League not found"))),
+    };
+
+    if acting_league_role(tournament.id, league_id, auth_user.user_id).is_none() {
+        return Ok(HttpResponse::Forbidden().json(ErrorResponse::new("Only members can see the league")));
+    }
+
+    let member_ids: HashSet<i32> = db::leagues::get_league_members(league_id).unwrap()
+        .iter()
+        .map(|member| member.user_id)
+        .collect();
+    let members = db::get_users(tournament.id).unwrap()
+        .into_iter()
+        .filter(|user| member_ids.contains(&user.id))
+        .collect();
+
+    let mut user_rating_list = user_rating(&tournament, db::get_past_games(tournament.id).unwrap(), members);
+    calculate_positions(&mut user_rating_list, tournament.rule_set, true);
+
+    let response = LeagueRatingTable {
+        league,
+        rating: user_rating_list,
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[post("/league/{league_id}/leave")]
+pub async fn leave_league(tournament: Tournament, path: web::Path<LeaguePath>, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let league_id = path.league_id;
+
+    match find_league_role(tournament.id, league_id, auth_user.user_id) {
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("League member not found"))),
+        Some(LeagueRole::Owner) => Ok(HttpResponse::Conflict().json(ErrorResponse::new("The owner cannot leave the league"))),
+        Some(_) => {
+            db::leagues::remove_league_member(league_id, auth_user.user_id).unwrap();
+            Ok(HttpResponse::NoContent().finish())
+        },
+    }
+}
+
+#[put("/league/{league_id}/member/{member_id}")]
+pub async fn update_league_member(tournament: Tournament, path: web::Path<LeagueMemberPath>, auth_user: AuthUser, request: web::Json<LeagueRoleRequest>) -> ActixResult<impl Responder> {
+    let LeagueMemberPath { league_id, member_id } = path.into_inner();
+
+    if acting_league_role(tournament.id, league_id, auth_user.user_id) != Some(LeagueRole::Owner) {
+        return Ok(HttpResponse::Forbidden().json(ErrorResponse::new("Only the owner can change roles")));
+    }
+
+    if request.role == LeagueRole::Owner {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("A league has exactly one owner")));
+    }
+
+    match find_league_role(tournament.id, league_id, member_id) {
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("League member not found"))),
+        Some(LeagueRole::Owner) => Ok(HttpResponse::Conflict().json(ErrorResponse::new("The owner role cannot be changed"))),
+        Some(_) => {
+            db::leagues::set_league_member_role(league_id, member_id, request.role).unwrap();
+            Ok(HttpResponse::NoContent().finish())
+        },
+    }
+}
+
+#[delete("/league/{league_id}/member/{member_id}")]
+pub async fn remove_league_member(tournament: Tournament, path: web::Path<LeagueMemberPath>, auth_user: AuthUser) -> ActixResult<impl Responder> {
+    let LeagueMemberPath { league_id, member_id } = path.into_inner();
+
+    let acting_role = match acting_league_role(tournament.id, league_id, auth_user.user_id) {
+        Some(role) if role.can_manage_members() => role,
+        _ => return Ok(HttpResponse::Forbidden().json(ErrorResponse::new("Only owners and admins can remove members"))),
+    };
+
+    match find_league_role(tournament.id, league_id, member_id) {
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("League member not found"))),
+        Some(LeagueRole::Owner) => Ok(HttpResponse::Conflict().json(ErrorResponse::new("The owner cannot be removed"))),
+        Some(LeagueRole::Admin) if acting_role != LeagueRole::Owner => Ok(HttpResponse::Forbidden().json(ErrorResponse::new("Only the owner can remove admins"))),
+        Some(_) => {
+            db::leagues::remove_league_member(league_id, member_id).unwrap();
+            Ok(HttpResponse::NoContent().finish())
+        },
+    }
+}
+
+fn find_league_role(tournament_id: i32, league_id: i32, user_id: i32) -> Option<LeagueRole> {
+    db::leagues::get_league(tournament_id, league_id).unwrap()?;
+
+    db::leagues::get_league_members(league_id).unwrap()
+        .into_iter()
+        .find(|member| member.user_id == user_id)
+        .map(|member| member.role)
+}
+
+// League admins moderate every league as if they owned it.
+fn acting_league_role(tournament_id: i32, league_id: i32, user_id: i32) -> Option<LeagueRole> {
+    if auth::has_role(user_id, Role::LeagueAdmin) && db::leagues::get_league(tournament_id, league_id).unwrap().is_some() {
+        return Some(LeagueRole::Owner);
+    }
+
+    find_league_role(tournament_id, league_id, user_id)
+}
+
+#[put("/user/{user_id}/role")]
+pub async fn admin_set_user_role(user_id: web::Path<i32>, admin: TournamentAdmin, request: web::Json<RoleRequest>) -> ActixResult<impl Responder> {
+    let user_id = user_id.into_inner();
+    let TournamentAdmin(auth_user) = admin;
+
+    if user_id == auth_user.user_id {
+        return Ok(HttpResponse::Conflict().json(ErrorResponse::new("You cannot change your own role")));
+    }
+
+    match db::set_user_role(user_id, request.role, auth_user.user_id).unwrap() {
+        Some(_) => Ok(HttpResponse::NoContent().finish()),
+        None => Ok(HttpResponse::NotFound().json(ErrorResponse::new("User not found"))),
+    }
+}
+
+#[get("/role-audit")]
+pub async fn admin_role_audit(_admin: TournamentAdmin) -> ActixResult<impl Responder> {
+    Ok(HttpResponse::Ok().json(db::get_role_audit().unwrap()))
+}
+
+#[put("/team-strength/{tla}")]
+pub async fn admin_set_team_strength(tournament: Tournament, path: web::Path<TeamPath>, admin: TournamentAdmin, request: web::Json<TeamStrengthRequest>) -> ActixResult<impl Responder> {
+    let TournamentAdmin(auth_user) = admin;
+
+    if !(request.attack > 0.0 && request.defence > 0.0) {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("Attack and defence must be greater than 0")));
+    }
+
+    let team_list = service::picks::get_participating_teams(&db::get_teams(tournament.id).unwrap()).unwrap();
+    let tla = path.into_inner().tla.to_uppercase();
+    if !team_list.iter().any(|team| team.tla == tla) {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse::new("Team not found")));
+    }
+
+    let strength = db::TeamStrength { tla, attack: request.attack, defence: request.defence };
+    db::set_team_strength(tournament.id, &strength, auth_user.user_id).unwrap();
+
+    Ok(HttpResponse::Ok().json(strength))
+}
+
+#[put("/game/{game_id}/result")]
+pub async fn admin_set_game_result(
+    tournament: Tournament,
+    path: web::Path<GamePath>,
+    _admin: TournamentAdmin,
+    request: web::Json<GameResultRequest>,
+    broadcaster: web::Data<Broadcaster>,
+) -> ActixResult<impl Responder> {
+    if request.home_score < 0 || request.away_score < 0 {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("Scores must not be negative")));
+    }
+
+    let match_id = path.game_id;
+    // Changes are taken from the live table, so followers see provisional moves as well.
+    let before = rating_table(&tournament, live_games(tournament.id)).global;
+
+    if !db::set_game_result(tournament.id, match_id, request.home_score, request.away_score, request.live).unwrap() {
+        return Ok(HttpResponse::NotFound().json(ErrorResponse::new("Game not found")));
+    }
+
+    broadcaster.publish(Event::ResultUpdated {
+        tournament: tournament.slug.clone(),
+        match_id,
+        home_score: request.home_score,
+        away_score: request.away_score,
+        provisional: request.live,
+    });
+
+    let changes = service::changes::get_ranking_changes(&before, &rating_table(&tournament, live_games(tournament.id)).global);
+    if !changes.is_empty() {
+        broadcaster.publish(Event::RankingChanged { tournament: tournament.slug.clone(), changes });
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/sync")]
+pub async fn admin_sync(_admin: TournamentAdmin, sync_job: web::Data<SyncJob>) -> ActixResult<impl Responder> {
+    if !sync_job.is_configured() {
+        return Ok(HttpResponse::ServiceUnavailable().json(ErrorResponse::new("No sync source configured")));
+    }
+
+    match sync_job.sync().await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(error) => Ok(HttpResponse::BadGateway().json(ErrorResponse::new(&error))),
+    }
+}
+
+#[get("/tournaments")]
+pub async fn tournaments() -> ActixResult<impl Responder> {
+    Ok(HttpResponse::Ok().json(db::tournaments::get_tournaments().unwrap()))
+}
+
+#[put("/tournaments/{slug}")]
+pub async fn admin_save_tournament(slug: web::Path<String>, _admin: TournamentAdmin, request: web::Json<TournamentRequest>) -> ActixResult<impl Responder> {
+    let slug = slug.into_inner().trim().to_lowercase();
+    if slug.is_empty() || !slug.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("The slug may only contain letters, digits and dashes")));
+    }
+
+    let request = request.into_inner();
+    let name = request.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse::new("Tournament name must have between 1 and 64 characters")));
+    }
+
+    let tournament = Tournament {
+        id: 0,
+        slug,
+        name,
+        rule_set: request.rule_set,
+        champion: request.champion.map(|champion| champion.trim().to_uppercase()).filter(|champion| !champion.is_empty()),
+    };
+
+    match db::tournaments::save_tournament(&tournament).unwrap() {
+        (tournament, true) => Ok(HttpResponse::Created().json(tournament)),
+        (tournament, false) => Ok(HttpResponse::Ok().json(tournament)),
+    }
+}
+
+/// Routes about one tournament. They are served for the default tournament at
+/// the root and for every tournament under `/tournaments/{slug}`.
+pub fn tournament_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(rating)
+        .service(department_rating)
+        .service(rating_max_points)
+        .service(rating_csv)
+        .service(rating_xlsx)
+        .service(tips_csv)
+        .service(calendar)
+        .service(user_calendar)
+        .service(forecast)
+        .service(simulate)
+        .service(user_by_id)
+        .service(user_stats)
+        .service(compare)
+        .service(teams)
+        .service(my_picks)
+        .service(update_my_picks)
+        .service(user_picks)
+        .service(me)
+        .service(get_past_result_by_game_id)
+        .service(game_stats)
+        .service(groups)
+        .service(group_by_id)
+        .service(events)
+        .service(ws)
+        .service(create_league)
+        .service(join_league)
+        .service(league_by_id)
+        .service(league_rating)
+        .service(leave_league)
+        .service(update_league_member)
+        .service(remove_league_member);
+}
+
+/// Admin routes about one tournament, served under `/admin` like `tournament_routes`.
+pub fn tournament_admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(admin_set_game_result)
+        .service(admin_set_team_strength);
+}
+
+/// All routes. An `/admin` scope takes every request below it, so the admin
+/// routes of the default tournament are registered together with the global ones.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(status)
+        .service(login)
+        .service(register)
+        .service(my_profile)
+        .service(update_me)
+        .service(my_calendar)
+        .service(reset_my_calendar)
+        .service(tournaments)
+        .service(all_time_rating)
+        .configure(tournament_routes)
+        .service(
+            web::scope("/tournaments/{slug}")
+                .configure(tournament_routes)
+                .service(web::scope("/admin").configure(tournament_admin_routes))
+        )
+        .service(
+            web::scope("/admin")
+                .service(admin_set_user_role)
+                .service(admin_role_audit)
+                .service(admin_sync)
+                .service(admin_save_tournament)
+                .configure(tournament_admin_routes)
+        );
+}
+
+#[get("/")]
+pub async fn status(sync_job: web::Data<SyncJob>) -> ActixResult<impl Responder> {
+    let response = StatusResponse {
+        status: String::from("works"),
+        sync: sync_job.status(),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use actix_web::{test, App};
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::header;
+    use crate::service::compare::Comparison;
+    use crate::service::all_time::AllTimeRating;
+    use crate::service::elimination::MaxPoints;
+    use crate::service::forecast::Forecast;
+    use crate::service::stats::{GameStats, UserStats};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_get_user_rating() {
+        let resp = get_response_by_url("/rating").await;
+
+        assert!(resp.status().is_success());
+
+        let result: Response = test::read_body_json(resp).await;
+
+        assert_eq!(result.table.departments.len(), 2);
+        assert_eq!(result.table.global.len(), 7);
+
+        let department = result.table.departments.get("Langenfeld").unwrap();
+        assert_eq!(department.len(), 4);
+        assert_eq!(department[0].name, "ToniKroos");
+        assert_eq!(department[0].department, "Langenfeld");
+
+        let department = result.table.departments.get("London").unwrap();
+        assert_eq!(department.len(), 3);
+        assert_eq!(department[0].name, "RobbieFowler");
+        assert_eq!(department[0].department, "London");
+
+        let global = &result.table.global;
+        assert_eq!(global[0].name, "ToniKroos");
+        assert_eq!(global[0].department, "Langenfeld");
+        assert_eq!(global[0].score_sum, 21);
+        assert_eq!(global[0].position, 1);
+        assert_eq!(global[0].sum_win_exact, 1);
+        assert_eq!(global[0].sum_score_diff, 1);
+        assert_eq!(global[0].extra_point, 15);
+        assert_eq!(global[0].sum_team, 0);
+        assert_eq!(global[0].tips.len(), 0);
+
+        assert_eq!(global[1].name, "JohnDoe");
+        assert_eq!(global[1].department, "Langenfeld");
+        assert_eq!(global[1].score_sum, 11);
+        assert_eq!(global[1].position, 2);
+        assert_eq!(global[1].extra_point, 7);
+
+
+        assert_eq!(global[2].name, "RobbieFowler");
+        assert_eq!(global[2].department, "London");
+        assert_eq!(global[2].score_sum, 11);
+        assert_eq!(global[2].position, 2);
+
+        assert_eq!(global[6].name, "SteveMcManaman");
+        assert_eq!(global[6].position, 5);
+
+        let picks = result.picks.unwrap();
+        assert_eq!(picks.winner[0].team, "ENG");
+        assert_eq!(picks.winner[0].count, 2);
+        assert_eq!(picks.secret_winner[0].team, "DEU");
+        assert_eq!(picks.secret_winner[0].count, 3);
+    }
+
+    #[actix_web::test]
+    async fn test_department_rating() {
+        let resp = get_response_by_url("/rating/departments").await;
+
+        assert!(resp.status().is_success());
+
+        let result: DepartmentRatingTable = test::read_body_json(resp).await;
+
+        assert_eq!(result.departments.len(), 2);
+        assert_eq!(result.departments[0].department, "Langenfeld");
+        assert_eq!(result.departments[0].position, 1);
+        assert_eq!(result.departments[0].member_count, 4);
+        assert_eq!(result.departments[1].department, "London");
+        assert_eq!(result.departments[1].member_count, 3);
+    }
+
+    #[actix_web::test]
+    async fn test_department_rating_with_best_members() {
+        let resp = get_response_by_url("/rating/departments?best=1").await;
+
+        assert!(resp.status().is_success());
+
+        let result: DepartmentRatingTable = test::read_body_json(resp).await;
+
+        assert_eq!(result.departments[0].department, "Langenfeld");
+        assert_eq!(result.departments[0].score, 21);
+        assert_eq!(result.departments[0].counted_members, 1);
+        assert_eq!(result.departments[1].score, 11);
+
+        let resp = get_response_by_url("/rating/departments?best=0").await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_login() {
+        let resp = send_json(test::TestRequest::post(), "/login", serde_json::json!({"email": "toni@kroos.de", "password": "password"})).await;
+
+        assert!(resp.status().is_success());
+
+        let result: LoginResponse = test::read_body_json(resp).await;
+        assert_eq!(result.user_id, 2);
+        assert_eq!(auth::verify_token(&test_secret(), &result.token), Some(2));
+
+        let resp = send_json(test::TestRequest::post(), "/login", serde_json::json!({"email": "toni@kroos.de", "password": "wrong"})).await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = send_json(test::TestRequest::post(), "/login", serde_json::json!({"email": "nobody@example.com", "password": "password"})).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    fn registration() -> serde_json::Value {
+        serde_json::json!({
+            "email": "jamal@musiala.de",
+            "first_name": "Jamal",
+            "last_name": "Musiala",
+            "username": "JamalMusiala",
+            "password": "wunderkind",
+            "department": "Langenfeld"
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_register() {
+        let resp = send_json(test::TestRequest::post(), "/users", registration()).await;
+
+        assert_eq!(resp.status(), 201);
+
+        let result: Profile = test::read_body_json(resp).await;
+        assert_eq!(result.id, 8);
+        assert_eq!(result.username, "JamalMusiala");
+        assert_eq!(result.department, "Langenfeld");
+        assert_eq!(result.winner, "");
+    }
+
+    #[rstest::rstest]
+    #[case("email", "jamal", 400)]
+    #[case("username", "J", 400)]
+    #[case("password", "short", 400)]
+    #[case("first_name", " ", 400)]
+    #[case("department", "Narnia", 400)]
+    #[case("email", "TONI@kroos.de", 409)]
+    #[case("username", "tonikroos", 409)]
+    #[case("winner", "ITA", 400)]
+    #[case("winner", "ESP", 403)]
+    #[actix_web::test]
+    async fn test_register_rejects_invalid_data(#[case] field: &str, #[case] value: &str, #[case] expected_status: u16) {
+        let mut body = registration();
+        body[field] = serde_json::json!(value);
+
+        let resp = send_json(test::TestRequest::post(), "/users", body).await;
+
+        assert_eq!(resp.status(), expected_status);
+    }
+
+    #[actix_web::test]
+    async fn test_my_profile() {
+        let resp = send_request(as_user(test::TestRequest::get(), 2).uri("/me/profile")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: Profile = test::read_body_json(resp).await;
+        assert_eq!(result.email, "toni@kroos.de");
+        assert_eq!(result.winner, "ESP");
+    }
+
+    #[actix_web::test]
+    async fn test_update_me() {
+        let body = serde_json::json!({"username": "Toni8", "department": "London", "winner": "ESP", "secret_winner": "DEU"});
+        let resp = send_json(as_user(test::TestRequest::patch(), 2), "/me", body).await;
+
+        assert!(resp.status().is_success());
+
+        let result: Profile = test::read_body_json(resp).await;
+        assert_eq!(result.username, "Toni8");
+        assert_eq!(result.department, "London");
+        assert_eq!(result.first_name, "Toni");
+        assert_eq!(result.winner, "ESP");
+    }
+
+    #[actix_web::test]
+    async fn test_update_me_rejects_invalid_data() {
+        let resp = send_json(as_user(test::TestRequest::patch(), 2), "/me", serde_json::json!({"username": "JohnDoe"})).await;
+        assert_eq!(resp.status(), 409);
+
+        let resp = send_json(as_user(test::TestRequest::patch(), 2), "/me", serde_json::json!({"department": "Narnia"})).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::patch(), 2), "/me", serde_json::json!({"secret_winner": "ENG"})).await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = send_json(test::TestRequest::patch(), "/me", serde_json::json!({"username": "Toni8"})).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[rstest::rstest]
+    #[case("mode", 4)]
+    #[case("median", 5)]
+    #[actix_web::test]
+    async fn test_get_user_rating_with_crowd(#[case] crowd_mode: &str, #[case] crowd_score: i32) {
+        let resp = get_response_by_url(&format!("/rating?crowd={}", crowd_mode)).await;
+
+        assert!(resp.status().is_success());
+
+        let result: Response = test::read_body_json(resp).await;
+        assert_eq!(result.table.global.len(), 8);
+
+        let crowd = result.table.global.iter().find(|user| user.is_crowd).unwrap();
+        assert_eq!(crowd.name, "Crowd");
+        assert_eq!(crowd.score_sum, crowd_score);
+        assert!(crowd.position > 0);
+        assert!(result.table.departments.values().flatten().all(|user| !user.is_crowd));
+    }
+
+    #[actix_web::test]
+    async fn test_get_user_rating_with_unknown_crowd_mode() {
+        let resp = get_response_by_url("/rating?crowd=average").await;
+
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_user_rating_with_classic_rules() {
+        let resp = get_response_by_url("/rating?rules=classic2021").await;
+
+        assert!(resp.status().is_success());
+
+        let result: Response = test::read_body_json(resp).await;
+        let toni = result.table.global.iter().find(|user| user.name == "ToniKroos").unwrap();
+
+        // 3:1 on 2:0 and the exact 1:1, without a champion bonus.
+        assert_eq!(toni.extra_point, 0);
+        assert_eq!(toni.score_sum, 5);
+        assert_eq!(toni.sum_win_exact, 1);
+        assert_eq!(toni.sum_score_diff, 1);
+
+        let resp = get_response_by_url("/rating?rules=wm1954").await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_user_rating_live_table() {
+        let resp = get_response_by_url("/rating").await;
+
+        let result: Response = test::read_body_json(resp).await;
+        let confirmed = result.table.global.iter().find(|user| user.name == "LukasPodolski").unwrap();
+        let live = result.live.global.iter().find(|user| user.name == "LukasPodolski").unwrap();
+
+        // The exact tip on the running match 6 counts in the live table only.
+        assert_eq!(live.score_sum, confirmed.score_sum + 4);
+        assert_eq!(live.sum_win_exact, confirmed.sum_win_exact + 1);
+        assert_eq!(result.live.global.len(), 7);
+        assert_eq!(result.live.departments.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_max_points() {
+        let resp = get_response_by_url("/rating/max-points").await;
+
+        assert!(resp.status().is_success());
+
+        let result: Vec<MaxPoints> = test::read_body_json(resp).await;
+        assert_eq!(result.len(), 7);
+        // The running match 6 is only open for LukasPodolski, who tipped it before kickoff.
+        assert!(result.iter().all(|max_points| max_points.open_matches == if max_points.name == "LukasPodolski" { 4 } else { 3 }));
+        // The champion is decided, so only the open matches are left to catch up with the leader.
+        assert_eq!(result.iter().filter(|max_points| max_points.can_win_global).count(), 4);
+        let lukas = result.iter().find(|max_points| max_points.name == "LukasPodolski").unwrap();
+        assert_eq!(lukas.max_possible_points, 16);
+        assert!(!lukas.can_win_global);
+
+        let toni = result.iter().find(|max_points| max_points.name == "ToniKroos").unwrap();
+        assert_eq!(toni.max_possible_points, toni.score_sum + 12);
+    }
+
+    #[actix_web::test]
+    async fn test_forecast() {
+        let resp = get_response_by_url("/forecast?iterations=200&seed=7").await;
+
+        assert!(resp.status().is_success());
+
+        let result: Forecast = test::read_body_json(resp).await;
+        assert_eq!(result.seed, 7);
+        assert_eq!(result.iterations, 200);
+        assert_eq!(result.users.len(), 7);
+        // Nobody tipped the open matches yet, so the current leader stays first.
+        assert_eq!(result.users[0].name, "ToniKroos");
+        assert_eq!(result.users[0].first, 1.0);
+        assert!(result.users.iter().all(|user| user.top_10 == 1.0));
+
+        let resp = get_response_by_url("/forecast?iterations=0").await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = get_response_by_url("/forecast?iterations=20001").await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_set_team_strength() {
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/team-strength/ger", serde_json::json!({"attack": 1.4, "defence": 0.7})).await;
+        assert!(resp.status().is_success());
+
+        let result: db::TeamStrength = test::read_body_json(resp).await;
+        assert_eq!(result.tla, "GER");
+        assert_eq!(result.attack, 1.4);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/team-strength/GER", serde_json::json!({"attack": 0.0, "defence": 0.7})).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/team-strength/ITA", serde_json::json!({"attack": 1.0, "defence": 1.0})).await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 1), "/admin/team-strength/GER", serde_json::json!({"attack": 1.0, "defence": 1.0})).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_simulate() {
+        let body = serde_json::json!({"results": [
+            {"match_id": 3, "home_score": 1, "away_score": 0},
+            {"match_id": 4, "home_score": 2, "away_score": 2}
+        ]});
+        let resp = send_json(test::TestRequest::post(), "/simulate", body).await;
+
+        assert!(resp.status().is_success());
+
+        let result: SimulationResponse = test::read_body_json(resp).await;
+        assert_eq!(result.results.len(), 2);
+        assert_eq!(result.table.global.len(), 7);
+        assert!(result.table.global.iter().all(|user| user.tips.is_empty()));
+        assert_eq!(result.table.global[0].name, "ToniKroos");
+        assert_eq!(result.table.departments["London"].len(), 3);
+    }
+
+    #[rstest::rstest]
+    #[case(serde_json::json!({"results": [{"match_id": 1, "home_score": 0, "away_score": 0}]}))]
+    #[case(serde_json::json!({"results": [{"match_id": 99, "home_score": 0, "away_score": 0}]}))]
+    #[case(serde_json::json!({"results": [{"match_id": 3, "home_score": -1, "away_score": 0}]}))]
+    #[case(serde_json::json!({"results": [{"match_id": 3, "home_score": 1, "away_score": 0}, {"match_id": 3, "home_score": 0, "away_score": 0}]}))]
+    #[actix_web::test]
+    async fn test_simulate_rejects_invalid_results(#[case] body: serde_json::Value) {
+        let resp = send_json(test::TestRequest::post(), "/simulate", body).await;
+
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_teams() {
+        let resp = get_response_by_url("/teams").await;
+
+        assert!(resp.status().is_success());
+
+        let result: Vec<Team> = test::read_body_json(resp).await;
+        assert_eq!(result.len(), 6);
+        assert_eq!(result[0].name, "England");
+    }
+
+    #[actix_web::test]
+    async fn test_picks_are_visible_after_lock() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/2/picks")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: PicksResponse = test::read_body_json(resp).await;
+        assert!(result.locked);
+        assert_eq!(result.winner, Some(String::from("ESP")));
+        assert_eq!(result.secret_winner, Some(String::from("DEU")));
+
+        let resp = send_request(as_user(test::TestRequest::get(), 2).uri("/me/picks")).await;
+        let result: PicksResponse = test::read_body_json(resp).await;
+        assert_eq!(result.user_id, 2);
+        assert_eq!(result.winner, Some(String::from("ESP")));
+
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/99999/picks")).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_update_my_picks() {
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/me/picks", serde_json::json!({"winner": "ITA", "secret_winner": ""})).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/me/picks", serde_json::json!({"winner": "ENG", "secret_winner": "NED"})).await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = send_json(test::TestRequest::put(), "/me/picks", serde_json::json!({"winner": "ENG", "secret_winner": "NED"})).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_me() {
+        let resp = send_request(as_user(test::TestRequest::get(), 5).uri("/me")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: UserResponse = test::read_body_json(resp).await;
+        assert_eq!(result.data.user_id, 5);
+        assert_eq!(result.data.name, "RobbieFowler");
+        assert_eq!(result.data.tips.len(), 2);
+
+        let resp = get_response_by_url("/me").await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = send_request(test::TestRequest::get().uri("/me").insert_header((header::AUTHORIZATION, "Bearer 5.99999999999.00"))).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_rt::test]
+    async fn test_user_by_id_requires_authentication() {
+        let resp = get_response_by_url("/user/2").await;
+
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_rt::test]
+    async fn test_user_by_id_returns_user_when_exists() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/2")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: UserResponse = test::read_body_json(resp).await;
+
+        assert_eq!(result.data.user_id, 2);
+        assert_eq!(result.data.name, "ToniKroos");
+        assert_eq!(result.data.department, "Langenfeld");
+        assert_eq!(result.data.score_sum, 21);
+        assert_eq!(result.data.position, 1);
+        assert_eq!(result.data.sum_win_exact, 1);
+        assert_eq!(result.data.extra_point, 15);
+        assert_eq!(result.data.sum_team, 0);
+        assert_eq!(result.data.tips.len(), 2);
+
+        assert_eq!(result.data.tips[0].match_id, "2".to_string());
+        assert_eq!(result.data.tips[0].team1.name, "Poland");
+        assert_eq!(result.data.tips[0].team2.name, "France");
+
+        assert_eq!(result.data.tips[1].score, 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_user_by_id_counts_running_matches_in_live() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/2")).await;
+
+        let result: UserResponse = test::read_body_json(resp).await;
+
+        assert!(result.data.tips.iter().all(|tip| !tip.provisional));
+        assert_eq!(result.live.tips.len(), 3);
+        assert_eq!(result.live.tips[1].match_id, "6".to_string());
+        assert!(result.live.tips[1].provisional);
+        assert_eq!(result.live.tips[1].tip_home, None);
+    }
+
+    #[actix_rt::test]
+    async fn user_by_id_returns_not_found_when_user_does_not_exist() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/99999")).await;
+
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_game_stats() {
+        let resp = get_response_by_url("/game/1/stats").await;
+
+        assert!(resp.status().is_success());
+
+        let result: GameStats = test::read_body_json(resp).await;
+        assert_eq!(result.match_id, 1);
+        assert_eq!(result.tip_count, 6);
+        assert_eq!(result.common_scores[0].tip_home, 2);
+        assert_eq!(result.common_scores[0].tip_away, 0);
+        assert_eq!(result.common_scores[0].count, 2);
+
+        let points = result.points.unwrap();
+        assert_eq!(points.win_exact, 2);
+        assert_eq!(points.win_score_diff, 1);
+        assert_eq!(points.win_team, 1);
+        assert_eq!(points.no_win_team, 2);
+    }
+
+    #[actix_web::test]
+    async fn test_game_stats_without_points_while_live() {
+        let resp = get_response_by_url("/game/6/stats").await;
+
+        assert!(resp.status().is_success());
+
+        let result: GameStats = test::read_body_json(resp).await;
+        assert_eq!(result.match_id, 6);
+        assert!(result.points.is_none());
+    }
+
+    #[rstest::rstest]
+    #[case("/game/3/stats", 403)]
+    #[case("/game/99/stats", 404)]
+    #[actix_web::test]
+    async fn test_game_stats_unavailable(#[case] url: &str, #[case] expected_status: u16) {
+        let resp = get_response_by_url(url).await;
+
+        assert_eq!(resp.status(), expected_status);
+    }
+
+    #[actix_web::test]
+    async fn test_user_stats() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/2/stats")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: UserStats = test::read_body_json(resp).await;
+        assert_eq!(result.user_id, 2);
+        assert_eq!(result.tip_count, 2);
+        assert_eq!(result.missing_tips, 0);
+        assert_eq!(result.hit_rates.win_exact.count, 1);
+        assert_eq!(result.hit_rates.win_score_diff.count, 1);
+        assert_eq!(result.average_points, 3.0);
+        assert_eq!(result.current_streak, 2);
+        assert_eq!(result.longest_streak, 2);
+
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/7/stats")).await;
+        let result: UserStats = test::read_body_json(resp).await;
+        assert_eq!(result.tip_count, 0);
+        assert_eq!(result.missing_tips, 2);
+
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/user/99999/stats")).await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = get_response_by_url("/user/2/stats").await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_compare() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/compare/2/1")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: Comparison = test::read_body_json(resp).await;
+        assert_eq!(result.user_a, 2);
+        assert_eq!(result.user_b, 1);
+        assert_eq!(result.score_a, 6);
+        assert_eq!(result.score_b, 4);
+        assert_eq!(result.wins_a, 1);
+        assert_eq!(result.wins_b, 1);
+        assert_eq!(result.draws, 0);
+        assert_eq!(result.games.len(), 2);
+        assert_eq!(result.games[0].match_id, "1");
+        assert_eq!(result.games[0].balance, -2);
+        assert_eq!(result.games[1].winner, Some(2));
+        assert_eq!(result.balance, 2);
+        assert!(result.differing_tips.is_empty());
+
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/compare/2/99999")).await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = get_response_by_url("/compare/2/1").await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_get_past_result_by_game_id() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/game/2")).await;
+
+        assert!(resp.status().is_success());
+
+        let response: GameTipsResponse = test::read_body_json(resp).await;
+        let result = response.tips;
+
+        assert!(!result.is_empty());
+
+        assert_eq!(result.len(), 7);
+        assert_eq!(result[0].match_id, "2".to_string());
+        assert_eq!(result[0].user, "JohnDoe");
+        assert_eq!(result[0].score, 0);
+
+        assert_eq!(result[1].match_id, "2".to_string());
+        assert_eq!(result[1].user, "ToniKroos");
+        assert_eq!(result[1].score, 4);
+
+        assert_eq!(result[2].match_id, "2".to_string());
+        assert_eq!(result[2].team1.name, "Poland");
+        assert_eq!(result[2].team2.name, "France");
+        assert_eq!(result[2].score, 1);
+        assert_eq!(result[2].tip_home, Some(2));
+        assert_eq!(result[2].tip_away, Some(2));
+        assert_eq!(result[2].score_home, Some(1));
+        assert_eq!(result[2].score_away, Some(1));
+        assert_eq!(response.live.len(), 7);
+    }
+
+    #[actix_web::test]
+    async fn test_get_past_result_by_game_id_requires_authentication() {
+        let resp = get_response_by_url("/game/2").await;
+
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_get_running_result_by_game_id() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/game/6")).await;
+
+        let result: GameTipsResponse = test::read_body_json(resp).await;
+
+        assert!(result.tips.is_empty());
+        assert_eq!(result.live.len(), 7);
+        assert!(result.live.iter().all(|tip| tip.provisional && tip.score_home == Some(1)));
+    }
+
+    #[actix_web::test]
+    async fn test_groups() {
+        let resp = get_response_by_url("/groups").await;
+
+        assert!(resp.status().is_success());
+
+        let result: GroupsResponse = test::read_body_json(resp).await;
+
+        assert_eq!(result.groups.len(), 2);
+        assert_eq!(result.groups[0].group, "GROUP_A");
+        assert_eq!(result.groups[0].standings.len(), 4);
+        assert_eq!(result.groups[0].standings[0].team.tla, "GER");
+        assert_eq!(result.groups[0].standings[0].points, 3);
+        assert_eq!(result.groups[0].standings[3].team.tla, "ESP");
+        assert_eq!(result.groups[0].standings[3].goal_difference, -2);
+
+        assert_eq!(result.groups[1].group, "GROUP_B");
+        assert_eq!(result.groups[1].standings[0].played, 0);
+
+        assert_eq!(result.third_placed.len(), 1);
+        assert_eq!(result.third_placed[0].group, "GROUP_A");
+    }
+
+    #[actix_web::test]
+    async fn test_group_by_id() {
+        let resp = get_response_by_url("/groups/a").await;
+
+        assert!(resp.status().is_success());
+
+        let result: GroupTable = test::read_body_json(resp).await;
+        assert_eq!(result.group, "GROUP_A");
+        assert_eq!(result.standings[1].team.tla, "FRA");
+        assert_eq!(result.standings[2].team.tla, "POL");
+
+        let resp = get_response_by_url("/groups/GROUP_X").await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_create_league() {
+        let resp = send_json(as_user(test::TestRequest::post(), 3), "/league", serde_json::json!({"name": " Friends "})).await;
+
+        assert_eq!(resp.status(), 201);
+
+        let result: League = test::read_body_json(resp).await;
+        assert_eq!(result.name, "Friends");
+        assert_eq!(result.owner_id, 3);
+        assert_eq!(result.invite_code.len(), 8);
+
+        let resp = send_json(as_user(test::TestRequest::post(), 3), "/league", serde_json::json!({"name": " "})).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::post(), 99999), "/league", serde_json::json!({"name": "Ghosts"})).await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = send_json(test::TestRequest::post(), "/league", serde_json::json!({"name": "Anonymous"})).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_join_league() {
+        let resp = send_json(as_user(test::TestRequest::post(), 3), "/league/join", serde_json::json!({"invite_code": "kabine24"})).await;
+
+        assert!(resp.status().is_success());
+
+        let result: League = test::read_body_json(resp).await;
+        assert_eq!(result.id, 1);
+
+        let resp = send_json(as_user(test::TestRequest::post(), 3), "/league/join", serde_json::json!({"invite_code": "NOPE"})).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_league_by_id() {
+        let resp = send_request(as_user(test::TestRequest::get(), 5).uri("/league/1")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: LeagueResponse = test::read_body_json(resp).await;
+        assert_eq!(result.league.name, "Kabinenfunk");
+        assert_eq!(result.members.len(), 3);
+
+        let resp = send_request(as_user(test::TestRequest::get(), 5).uri("/league/99")).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_league_requires_membership() {
+        for uri in ["/league/1", "/league/1/rating"] {
+            let resp = get_response_by_url(uri).await;
+            assert_eq!(resp.status(), 401);
+
+            let resp = send_request(as_user(test::TestRequest::get(), 3).uri(uri)).await;
+            assert_eq!(resp.status(), 403);
+
+            // League admins moderate every league.
+            let resp = send_request(as_user(test::TestRequest::get(), 7).uri(uri)).await;
+            assert!(resp.status().is_success());
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_league_rating() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/league/1/rating")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: LeagueRatingTable = test::read_body_json(resp).await;
+        assert_eq!(result.league.id, 1);
+        assert_eq!(result.rating.len(), 3);
+        assert_eq!(result.rating[0].name, "ToniKroos");
+        assert_eq!(result.rating[0].position, 1);
+        assert_eq!(result.rating[1].position, 2);
+        assert_eq!(result.rating[2].position, 2);
+        assert!(result.rating[0].tips.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_leave_league() {
+        let resp = send_request(as_user(test::TestRequest::post(), 5).uri("/league/1/leave")).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_request(as_user(test::TestRequest::post(), 2).uri("/league/1/leave")).await;
+        assert_eq!(resp.status(), 409);
+
+        let resp = send_request(as_user(test::TestRequest::post(), 7).uri("/league/1/leave")).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_update_league_member() {
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/league/1/member/5", serde_json::json!({"role": "admin"})).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 1), "/league/1/member/5", serde_json::json!({"role": "admin"})).await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/league/1/member/5", serde_json::json!({"role": "owner"})).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/league/1/member/7", serde_json::json!({"role": "admin"})).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_remove_league_member() {
+        let resp = send_request(as_user(test::TestRequest::delete(), 1).uri("/league/1/member/5")).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_request(as_user(test::TestRequest::delete(), 5).uri("/league/1/member/1")).await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = send_request(as_user(test::TestRequest::delete(), 1).uri("/league/1/member/2")).await;
+        assert_eq!(resp.status(), 409);
+
+        let resp = send_request(as_user(test::TestRequest::delete(), 2).uri("/league/1/member/1")).await;
+        assert_eq!(resp.status(), 204);
+    }
+
+    #[actix_web::test]
+    async fn test_league_admin_manages_foreign_league() {
+        let resp = send_json(as_user(test::TestRequest::put(), 7), "/league/1/member/5", serde_json::json!({"role": "admin"})).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_request(as_user(test::TestRequest::delete(), 7).uri("/league/1/member/1")).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_request(as_user(test::TestRequest::delete(), 7).uri("/league/99/member/1")).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_set_user_role() {
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/user/1/role", serde_json::json!({"role": "league_admin"})).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/user/2/role", serde_json::json!({"role": "player"})).await;
+        assert_eq!(resp.status(), 409);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/user/99999/role", serde_json::json!({"role": "player"})).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_routes_are_forbidden_for_players() {
+        let resp = send_json(as_user(test::TestRequest::put(), 7), "/admin/user/1/role", serde_json::json!({"role": "tournament_admin"})).await;
+        assert_eq!(resp.status(), 403);
+
+        let result: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(result.error, "Insufficient permissions");
+
+        let resp = send_json(as_user(test::TestRequest::put(), 1), "/admin/game/3/result", serde_json::json!({"home_score": 1, "away_score": 0})).await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = get_response_by_url("/admin/role-audit").await;
+        assert_eq!(resp.status(), 401);
+
+        let result: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(result.error, "Authentication required");
+    }
+
+    #[actix_web::test]
+    async fn test_admin_role_audit() {
+        let resp = send_request(as_user(test::TestRequest::get(), 2).uri("/admin/role-audit")).await;
+
+        assert!(resp.status().is_success());
+
+        let result: Vec<db::RoleAudit> = test::read_body_json(resp).await;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].user_id, 7);
+        assert_eq!(result[0].new_role, Role::LeagueAdmin);
+    }
+
+    #[actix_web::test]
+    async fn test_events() {
+        let resp = get_response_by_url("/events").await;
+
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/event-stream");
+
+        let resp = get_response_by_url("/tournaments/wm2022/events").await;
+        assert!(resp.status().is_success());
+
+        let resp = get_response_by_url("/tournaments/unknown/events").await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_ws_upgrades_connection() {
+        let request = test::TestRequest::get()
+            .uri("/ws")
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="));
+        let resp = send_request(request).await;
+
+        assert_eq!(resp.status(), 101);
+
+        let resp = get_response_by_url("/ws").await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_set_game_result_publishes_event() {
+        let broadcaster = web::Data::new(Broadcaster::new());
+        let mut receiver = broadcaster.subscribe();
+
+        let request = as_user(test::TestRequest::put(), 2)
+            .uri("/admin/game/3/result")
+            .set_json(serde_json::json!({"home_score": 1, "away_score": 0}));
+        let resp = send_request_with_broadcaster(request, broadcaster.clone()).await;
+        assert_eq!(resp.status(), 204);
+
+        assert_eq!(receiver.recv().await.unwrap(), Event::ResultUpdated { tournament: String::from("em2024"), match_id: 3, home_score: 1, away_score: 0, provisional: false });
+
+        let request = as_user(test::TestRequest::put(), 2)
+            .uri("/admin/game/99/result")
+            .set_json(serde_json::json!({"home_score": 1, "away_score": 0}));
+        send_request_with_broadcaster(request, broadcaster).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_admin_set_game_result() {
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/game/3/result", serde_json::json!({"home_score": 1, "away_score": 0})).await;
+        assert_eq!(resp.status(), 204);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/game/3/result", serde_json::json!({"home_score": -1, "away_score": 0})).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/game/99/result", serde_json::json!({"home_score": 1, "away_score": 0})).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_status() {
+        let resp = get_response_by_url("/").await;
+
+        assert!(resp.status().is_success());
+
+        let result: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(result.get("status").unwrap().as_str().unwrap(), "works");
+        assert!(result.get("sync").is_none());
+    }
+
+    fn failing_sync_job() -> web::Data<SyncJob> {
+        let config = crate::sync::SyncConfig {
+            source: crate::api::match_client::MatchSource::File(std::path::PathBuf::from("/nonexistent/matches.json")),
+            live_interval: std::time::Duration::from_secs(60),
+            idle_interval: std::time::Duration::from_secs(3600),
+        };
+        web::Data::new(SyncJob::new(Some(config), Broadcaster::new()))
+    }
+
+    #[actix_web::test]
+    async fn test_status_shows_sync_status() {
+        let sync_job = failing_sync_job();
+        let _ = sync_job.sync().await;
+
+        let resp = send_request_with_app_data(test::TestRequest::get().uri("/"), web::Data::new(Broadcaster::new()), sync_job).await;
+
+        let result: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(result["sync"]["consecutive_failures"], 1);
+        assert!(result["sync"]["last_error"].is_string());
+    }
+
+    #[actix_web::test]
+    async fn test_admin_sync() {
+        let resp = send_request(as_user(test::TestRequest::post(), 2).uri("/admin/sync")).await;
+        assert_eq!(resp.status(), 503);
+
+        let request = as_user(test::TestRequest::post(), 2).uri("/admin/sync");
+        let resp = send_request_with_app_data(request, web::Data::new(Broadcaster::new()), failing_sync_job()).await;
+        assert_eq!(resp.status(), 502);
+
+        let resp = send_request(as_user(test::TestRequest::post(), 1).uri("/admin/sync")).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_all_time_rating() {
+        let resp = get_response_by_url("/rating/all-time").await;
+        assert!(resp.status().is_success());
+
+        let result: Vec<AllTimeRating> = test::read_body_json(resp).await;
+        assert_eq!(result.len(), 6);
+
+        let first = &result[0];
+        assert_eq!(first.name, "ToniKroos");
+        assert_eq!(first.position, 1);
+        assert_eq!(first.tournaments, 2);
+        assert_eq!(first.score_sum, 37);
+        assert_eq!(first.titles, 1);
+        assert_eq!(first.podiums, 1);
+
+        let wm2022 = first.results.iter().find(|result| result.tournament == "wm2022").unwrap();
+        assert_eq!(wm2022.position, 1);
+        assert_eq!(wm2022.max_points, 19);
+        assert_eq!(wm2022.normalized_score, 84.2);
+        assert!(wm2022.finished);
+        assert!(!first.results.iter().find(|result| result.tournament == "em2024").unwrap().finished);
+
+        let john = result.iter().find(|all_time| all_time.name == "JohnDoe").unwrap();
+        assert_eq!(john.score_sum, 15);
+        assert_eq!(john.titles, 0);
+        assert_eq!(john.podiums, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_rating_csv() {
+        let resp = get_response_by_url("/rating.csv").await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/csv; charset=utf-8");
+        assert_eq!(resp.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"rating-em2024.csv\"");
+
+        let body = test::read_body(resp).await;
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = csv.trim_start_matches('\u{feff}').lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0], "position,name,user_id,department,score_sum,sum_win_exact,sum_score_diff,sum_team,extra_point");
+        assert_eq!(lines[1], "1,ToniKroos,2,Langenfeld,21,1,1,0,15");
+    }
+
+    #[actix_web::test]
+    async fn test_rating_csv_by_department() {
+        let resp = get_response_by_url("/rating.csv?department=London").await;
+        assert!(resp.status().is_success());
+
+        let body = test::read_body(resp).await;
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(1).unwrap().starts_with("1,RobbieFowler,"));
+
+        let resp = get_response_by_url("/rating.csv?department=Paris").await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_rating_xlsx() {
+        let resp = get_response_by_url("/tournaments/wm2022/rating.xlsx").await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(header::CONTENT_DISPOSITION).unwrap(), "attachment; filename=\"rating-wm2022.xlsx\"");
+
+        let body = test::read_body(resp).await;
+        assert!(body.starts_with(b"PK"));
+    }
+
+    #[actix_web::test]
+    async fn test_tips_csv() {
+        let resp = get_response_by_url("/tournaments/wm2022/export/tips.csv").await;
+        assert!(resp.status().is_success());
+
+        let body = test::read_body(resp).await;
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = csv.trim_start_matches('\u{feff}').lines().collect();
+        assert_eq!(lines[0], "match_id,user,user_id,score,team1,team2,tip_home,tip_away,score_home,score_away,date,provisional");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("7,JohnDoe,1,4,"));
+        assert!(lines[2].starts_with("7,ToniKroos,2,1,"));
+    }
+
+    #[actix_web::test]
+    async fn test_calendar() {
+        let resp = get_response_by_url("/calendar.ics").await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/calendar; charset=utf-8");
+
+        let body = test::read_body(resp).await;
+        let ics = String::from_utf8(body.to_vec()).unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 6);
+        assert!(ics.contains("X-WR-CALNAME:EURO 2024\r\n"));
+        assert!(ics.contains("SUMMARY:England - Netherlands\r\nLOCATION:Düsseldorf Arena\r\n"));
+        assert!(!ics.contains("tip"));
+
+        let resp = get_response_by_url("/tournaments/wm2022/calendar.ics").await;
+        let body = test::read_body(resp).await;
+        let ics = String::from_utf8(body.to_vec()).unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("SUMMARY:Argentina - France\r\n"));
+    }
+
+    #[actix_web::test]
+    async fn test_user_calendar() {
+        let resp = get_response_by_url("/calendar/c4l3nd4rc4l3nd4rc4l3nd4rc4l3nd4r.ics").await;
+        assert!(resp.status().is_success());
+        assert_eq!(resp.headers().get(header::CONTENT_TYPE).unwrap(), "text/calendar; charset=utf-8");
+
+        let body = test::read_body(resp).await;
+        let ics = String::from_utf8(body.to_vec()).unwrap();
+        let event = |match_id: i32| ics.split("BEGIN:VEVENT").find(|event| event.contains(&format!("UID:em2024-match-{}@", match_id))).unwrap();
+
+        assert!(event(1).contains("Result: 2:0\\nYour tip: 2:0"));
+        assert!(event(3).contains("You haven't tipped yet!"));
+        assert!(event(3).contains("BEGIN:VALARM"));
+        assert!(event(6).contains("Live: 1:0\\nYou did not tip this match."));
+
+        let resp = get_response_by_url("/tournaments/wm2022/calendar/c4l3nd4rc4l3nd4rc4l3nd4rc4l3nd4r.ics").await;
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8(body.to_vec()).unwrap().contains("Result: 3:3\\nYour tip: 3:3"));
+    }
+
+    #[actix_web::test]
+    async fn test_my_calendar() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/me/calendar")).await;
+        assert!(resp.status().is_success());
+
+        let result: CalendarResponse = test::read_body_json(resp).await;
+        assert_eq!(result.token, "c4l3nd4rc4l3nd4rc4l3nd4rc4l3nd4r");
+        assert_eq!(result.path, "/calendar/c4l3nd4rc4l3nd4rc4l3nd4rc4l3nd4r.ics");
+
+        let resp = send_request(as_user(test::TestRequest::get(), 2).uri("/me/calendar")).await;
+        let result: CalendarResponse = test::read_body_json(resp).await;
+        assert_eq!(result.token.len(), 32);
+
+        let resp = send_request(as_user(test::TestRequest::post(), 1).uri("/me/calendar/reset")).await;
+        let result: CalendarResponse = test::read_body_json(resp).await;
+        assert_ne!(result.token, "c4l3nd4rc4l3nd4rc4l3nd4rc4l3nd4r");
+
+        let resp = get_response_by_url("/me/calendar").await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_unknown_calendar() {
+        let resp = get_response_by_url("/calendar/0123456789abcdef0123456789abcdef.ics").await;
+        assert_eq!(resp.status(), 404);
+
+        let result: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(result.error, "Calendar not found");
+    }
+
+    #[actix_web::test]
+    async fn test_tournaments() {
+        let resp = get_response_by_url("/tournaments").await;
+        assert!(resp.status().is_success());
+
+        let result: Vec<Tournament> = test::read_body_json(resp).await;
+        let slugs: Vec<&str> = result.iter().map(|tournament| tournament.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["em2024", "wm2022"]);
+        assert_eq!(result[1].champion, Some(String::from("ARG")));
+    }
+
+    #[actix_web::test]
+    async fn test_tournament_rating() {
+        let resp = get_response_by_url("/tournaments/wm2022/rating").await;
+        assert!(resp.status().is_success());
+
+        let result: Response = test::read_body_json(resp).await;
+        let global = &result.table.global;
+        assert_eq!(global[0].name, "ToniKroos");
+        assert_eq!(global[0].score_sum, 16);
+        assert_eq!(global[0].extra_point, 15);
+        assert_eq!(global[1].name, "JohnDoe");
+        assert_eq!(global[1].score_sum, 4);
+        assert_eq!(global[1].sum_win_exact, 1);
+
+        let resp = get_response_by_url("/tournaments/em2024/rating").await;
+        let result: Response = test::read_body_json(resp).await;
+        assert_eq!(result.table.global[0].score_sum, 21);
+    }
+
+    #[actix_web::test]
+    async fn test_tournament_routes_are_scoped() {
+        let resp = send_request(as_user(test::TestRequest::get(), 1).uri("/tournaments/wm2022/user/2")).await;
+        assert!(resp.status().is_success());
+
+        let result: UserResponse = test::read_body_json(resp).await;
+        assert_eq!(result.data.score_sum, 16);
+
+        let resp = get_response_by_url("/tournaments/wm2022/game/3/stats").await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = get_response_by_url("/tournaments/wm2022/game/7/stats").await;
+        assert!(resp.status().is_success());
+
+        let resp = send_request(as_user(test::TestRequest::get(), 2).uri("/tournaments/wm2022/me/picks")).await;
+        let result: PicksResponse = test::read_body_json(resp).await;
+        assert_eq!(result.winner, Some(String::from("ARG")));
+        assert!(result.locked);
+    }
+
+    #[actix_web::test]
+    async fn test_unknown_tournament() {
+        let resp = get_response_by_url("/tournaments/wm1954/rating").await;
+        assert_eq!(resp.status(), 404);
+
+        let result: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(result.error, "Tournament not found");
+    }
+
+    #[actix_web::test]
+    async fn test_tournament_admin_routes_are_scoped() {
+        let body = serde_json::json!({"home_score": 1, "away_score": 0});
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/tournaments/wm2022/admin/game/3/result", body.clone()).await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/tournaments/wm2022/admin/game/7/result", body).await;
+        assert_eq!(resp.status(), 204);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_save_tournament() {
+        let body = serde_json::json!({"name": "EURO 2028", "rule_set": "em2024"});
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/tournaments/em2028", body.clone()).await;
+        assert_eq!(resp.status(), 201);
+
+        let result: Tournament = test::read_body_json(resp).await;
+        assert_eq!(result.slug, "em2028");
+        assert_eq!(result.champion, None);
+
+        let body = serde_json::json!({"name": "World Cup 2022", "rule_set": "em2024", "champion": "arg"});
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/tournaments/wm2022", body.clone()).await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 2), "/admin/tournaments/wm_2022", body.clone()).await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = send_json(as_user(test::TestRequest::put(), 1), "/admin/tournaments/wm2022", body).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    async fn get_response_by_url(url: &str) -> ServiceResponse {
+        send_request(test::TestRequest::get().uri(url)).await
+    }
+
+    async fn send_json(request: test::TestRequest, url: &str, body: serde_json::Value) -> ServiceResponse {
+        send_request(request.uri(url).set_json(body)).await
+    }
+
+    fn test_secret() -> AuthSecret {
+        AuthSecret::new("test-secret").unwrap()
+    }
+
+    fn as_user(request: test::TestRequest, user_id: i32) -> test::TestRequest {
+        env::set_var("MODE", "test");
+        let (token, _) = auth::issue_token(&test_secret(), user_id);
+
+        request.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+    }
+
+    async fn send_request(request: test::TestRequest) -> ServiceResponse {
+        send_request_with_broadcaster(request, web::Data::new(Broadcaster::new())).await
+    }
+
+    async fn send_request_with_broadcaster(request: test::TestRequest, broadcaster: web::Data<Broadcaster>) -> ServiceResponse {
+        let sync_job = web::Data::new(SyncJob::new(None, broadcaster.get_ref().clone()));
+        send_request_with_app_data(request, broadcaster, sync_job).await
+    }
+
+    async fn send_request_with_app_data(request: test::TestRequest, broadcaster: web::Data<Broadcaster>, sync_job: web::Data<SyncJob>) -> ServiceResponse {
+        env::set_var("MODE", "test");
+
+        let app = test::init_service(
+            App::new()
+                .app_data(broadcaster)
+                .app_data(sync_job)
+                .app_data(web::Data::new(test_secret()))
+                .configure(configure)
+        ).await;
 
-        let resp = test::call_service(&app, req).await;
+        let resp = test::call_service(&app, request.to_request()).await;
         resp
     }
 }
\ No newline at end of file
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use crate::service::ranking::RuleSet;
+use crate::service::{calculate_positions, UserRating};
+
+/// The rating of one tournament, as input for the all-time table.
+#[derive(Debug, Clone)]
+pub struct TournamentRating {
+    pub slug: String,
+    /// The most a user could get on the finished matches.
+    pub max_points: i32,
+    /// The rules the positions are taken under.
+    pub rule_set: RuleSet,
+    /// Every match has a result, so the final positions are known.
+    pub finished: bool,
+    /// Ratings with tips, positions are not needed.
+    pub user_rating_list: Vec<UserRating>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentResult {
+    pub tournament: String,
+    pub position: i32,
+    pub score_sum: i32,
+    pub max_points: i32,
+    pub normalized_score: f64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllTimeRating {
+    pub user_id: i32,
+    pub name: String,
+    pub department: String,
+    pub position: i32,
+    pub tournaments: i32,
+    pub score_sum: i32,
+    pub max_points: i32,
+    /// `score_sum` in percent of `max_points`.
+    pub normalized_score: f64,
+    pub titles: i32,
+    pub podiums: i32,
+    pub results: Vec<TournamentResult>,
+}
+
+/// Adds up the tournaments per user. A user took part in a tournament if they
+/// tipped at least one of its rated matches; positions are taken among those
+/// users. Titles (first place) and podiums (top 3) count for finished
+/// tournaments only. The table is sorted by points, users with the same points
+/// share a position.
+pub fn get_all_time_rating(tournament_ratings: Vec<TournamentRating>) -> Vec<AllTimeRating> {
+    let mut all_time_by_user: BTreeMap<i32, AllTimeRating> = BTreeMap::new();
+
+    for tournament_rating in tournament_ratings {
+        let mut participants: Vec<UserRating> = tournament_rating.user_rating_list.into_iter()
+            .filter(|user_rating| user_rating.tips.iter().any(|tip| tip.tip_home.is_some()))
+            .collect();
+        calculate_positions(&mut participants, tournament_rating.rule_set, true);
+
+        for user_rating in participants {
+            let all_time = all_time_by_user.entry(user_rating.user_id).or_insert_with(|| AllTimeRating {
+                user_id: user_rating.user_id,
+                name: user_rating.name.clone(),
+                department: user_rating.department.clone(),
+                position: 0,
+                tournaments: 0,
+                score_sum: 0,
+                max_points: 0,
+                normalized_score: 0.0,
+                titles: 0,
+                podiums: 0,
+                results: Vec::new(),
+            });
+
+            all_time.tournaments += 1;
+            all_time.score_sum += user_rating.score_sum;
+            all_time.max_points += tournament_rating.max_points;
+            if tournament_rating.finished && user_rating.position == 1 {
+                all_time.titles += 1;
+            }
+            if tournament_rating.finished && user_rating.position <= 3 {
+                all_time.podiums += 1;
+            }
+            all_time.results.push(TournamentResult {
+                tournament: tournament_rating.slug.clone(),
+                position: user_rating.position,
+                score_sum: user_rating.score_sum,
+                max_points: tournament_rating.max_points,
+                normalized_score: percent(user_rating.score_sum, tournament_rating.max_points),
+                finished: tournament_rating.finished,
+            });
+        }
+    }
+
+    let mut all_time_rating_list: Vec<AllTimeRating> = all_time_by_user.into_values()
+        .map(|all_time| AllTimeRating { normalized_score: percent(all_time.score_sum, all_time.max_points), ..all_time })
+        .collect();
+
+    calculate_all_time_positions(&mut all_time_rating_list);
+
+    all_time_rating_list
+}
+
+fn calculate_all_time_positions(all_time_rating_list: &mut [AllTimeRating]) {
+    all_time_rating_list.sort_by_key(|all_time| Reverse(all_time.score_sum));
+
+    let mut position = 0;
+    let mut last_score = None;
+    let mut position_for_frontend = 0;
+
+    for all_time in all_time_rating_list {
+        position += 1;
+        if last_score != Some(all_time.score_sum) {
+            position_for_frontend = position;
+        }
+
+        all_time.position = position_for_frontend;
+
+        last_score = Some(all_time.score_sum);
+    }
+}
+
+fn percent(score: i32, max_points: i32) -> f64 {
+    if max_points == 0 {
+        return 0.0;
+    }
+
+    (score as f64 * 1000.0 / max_points as f64).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::MatchInfo;
+    use crate::service::test_builder::{match_info, user_rating};
+    use super::*;
+
+    // A user with one finished match, tipped or not.
+    fn participant(user_id: i32, score_sum: i32, tipped: bool) -> UserRating {
+        let tip = MatchInfo {
+            score: score_sum,
+            tip_home: tipped.then_some(1),
+            tip_away: tipped.then_some(0),
+            ..match_info(1, user_id)
+        };
+
+        UserRating { score_sum, tips: vec![tip], ..user_rating(user_id) }
+    }
+
+    fn tournament_rating(slug: &str, max_points: i32, finished: bool, user_rating_list: Vec<UserRating>) -> TournamentRating {
+        TournamentRating { slug: slug.to_string(), max_points, rule_set: RuleSet::Em2024, finished, user_rating_list }
+    }
+
+    #[test]
+    fn test_get_all_time_rating() {
+        let all_time_rating_list = get_all_time_rating(vec![
+            tournament_rating("em2021", 20, true, vec![
+                participant(1, 10, true),
+                participant(2, 15, true),
+                participant(3, 5, true),
+                participant(4, 2, true),
+            ]),
+            tournament_rating("em2024", 40, false, vec![
+                participant(1, 30, true),
+                participant(2, 5, true),
+                participant(3, 0, false),
+            ]),
+        ]);
+
+        assert_eq!(all_time_rating_list.len(), 4);
+
+        let first = &all_time_rating_list[0];
+        assert_eq!(first.user_id, 1);
+        assert_eq!(first.position, 1);
+        assert_eq!(first.tournaments, 2);
+        assert_eq!(first.score_sum, 40);
+        assert_eq!(first.max_points, 60);
+        assert_eq!(first.normalized_score, 66.7);
+        assert_eq!(first.titles, 0);
+        assert_eq!(first.podiums, 1);
+        assert_eq!(first.results[1].position, 1);
+        assert_eq!(first.results[1].normalized_score, 75.0);
+
+        let second = &all_time_rating_list[1];
+        assert_eq!(second.user_id, 2);
+        assert_eq!(second.titles, 1);
+        assert_eq!(second.podiums, 1);
+
+        // Did not tip in em2024, so it does not count against them.
+        let third = &all_time_rating_list[2];
+        assert_eq!(third.user_id, 3);
+        assert_eq!(third.tournaments, 1);
+        assert_eq!(third.max_points, 20);
+        assert_eq!(third.normalized_score, 25.0);
+        assert_eq!(third.podiums, 1);
+
+        assert_eq!(all_time_rating_list[3].podiums, 0);
+    }
+
+    #[test]
+    fn test_get_all_time_rating_shares_positions() {
+        let all_time_rating_list = get_all_time_rating(vec![
+            tournament_rating("em2021", 8, true, vec![
+                participant(1, 4, true),
+                participant(2, 4, true),
+                participant(3, 1, true),
+            ]),
+        ]);
+
+        assert_eq!(all_time_rating_list.iter().map(|all_time| all_time.position).collect::<Vec<_>>(), vec![1, 1, 3]);
+        assert_eq!(all_time_rating_list[0].titles, 1);
+        assert_eq!(all_time_rating_list[1].titles, 1);
+    }
+
+    #[test]
+    fn test_get_all_time_rating_without_matches() {
+        let all_time_rating_list = get_all_time_rating(vec![tournament_rating("wm2026", 0, false, Vec::new())]);
+
+        assert!(all_time_rating_list.is_empty());
+        assert_eq!(percent(0, 0), 0.0);
+    }
+}
@@ -0,0 +1,237 @@
+use chrono::DateTime;
+use std::collections::HashMap;
+use crate::db::calendar::CalendarGame;
+use crate::db::{is_live_status, Tip};
+use crate::service::Team;
+
+// A match with extra time and penalties is over after about two hours.
+const MATCH_DURATION: u64 = 2 * 60 * 60;
+// Lines longer than this (in bytes, without the line break) are folded.
+const MAX_LINE_LENGTH: usize = 75;
+// Reminder before the kickoff of an untipped match, when tips close.
+const TIP_REMINDER: &str = "-PT1H";
+
+/// Writes the matches as an iCalendar (RFC 5545) feed with one event per match.
+/// With the `tips` of a user the description shows their tip, and untipped
+/// matches that have not kicked off get a reminder.
+pub fn to_ics(calendar_name: &str, slug: &str, games: &[CalendarGame], tips: Option<&HashMap<i32, Tip>>, now: u64) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//football-betting//em2024-api//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+        String::from("METHOD:PUBLISH"),
+        format!("X-WR-CALNAME:{}", escape(calendar_name)),
+        String::from("REFRESH-INTERVAL;VALUE=DURATION:PT1H"),
+        String::from("X-PUBLISHED-TTL:PT1H"),
+    ];
+
+    for game in games {
+        let summary = format!("{} - {}", team_name(&game.home_team), team_name(&game.away_team));
+
+        let mut description = vec![round(game)];
+        if let (Some(home), Some(away)) = (game.home_score, game.away_score) {
+            let label = if is_live_status(&game.status) { "Live" } else { "Result" };
+            description.push(format!("{}: {}:{}", label, home, away));
+        }
+        let remind = match tips.map(|tips| tips.get(&game.id)) {
+            None => false,
+            Some(Some(tip)) => {
+                description.push(format!("Your tip: {}:{}", tip.score_home, tip.score_away));
+                false
+            },
+            Some(None) if game.date > now => {
+                description.push(String::from("You haven't tipped yet!"));
+                true
+            },
+            Some(None) => {
+                description.push(String::from("You did not tip this match."));
+                false
+            },
+        };
+
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!("UID:{}-match-{}@em2024-api", slug, game.id));
+        lines.push(format!("DTSTAMP:{}", timestamp(now)));
+        lines.push(format!("DTSTART:{}", timestamp(game.date)));
+        lines.push(format!("DTEND:{}", timestamp(game.date + MATCH_DURATION)));
+        lines.push(format!("SUMMARY:{}", escape(&summary)));
+        if let Some(venue) = &game.venue {
+            lines.push(format!("LOCATION:{}", escape(venue)));
+        }
+        lines.push(format!("DESCRIPTION:{}", escape(&description.join("\n"))));
+        if remind {
+            lines.push(String::from("BEGIN:VALARM"));
+            lines.push(String::from("ACTION:DISPLAY"));
+            lines.push(format!("TRIGGER:{}", TIP_REMINDER));
+            lines.push(format!("DESCRIPTION:{}", escape(&format!("Tip {} before kickoff!", summary))));
+            lines.push(String::from("END:VALARM"));
+        }
+        lines.push(String::from("END:VEVENT"));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+// Undecided knockout teams have no name yet.
+fn team_name(team_json: &str) -> String {
+    serde_json::from_str::<Team>(team_json).ok()
+        .map(|team| team.name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("TBD"))
+}
+
+// "Group A" for group matches, otherwise the stage, e.g. "Last 16".
+fn round(game: &CalendarGame) -> String {
+    let name = game.group.as_deref().or(game.stage.as_deref()).unwrap_or_default();
+
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .enumerate()
+        .map(|(index, word)| {
+            let word = word.to_lowercase();
+            let mut chars = word.chars();
+            match (index, chars.next()) {
+                // Group letters stay upper case.
+                (_, Some(first)) if chars.as_str().is_empty() => first.to_uppercase().collect(),
+                (0, Some(first)) => first.to_uppercase().chain(chars).collect(),
+                _ => word,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn timestamp(seconds: u64) -> String {
+    DateTime::from_timestamp(seconds as i64, 0).unwrap_or_default().format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Continuation lines start with a space, which counts towards their length.
+// Multi-byte characters are never split.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1718370000;
+
+    fn game(id: i32, date: u64, score: Option<(i32, i32)>) -> CalendarGame {
+        CalendarGame {
+            id,
+            home_team: String::from(r#"{"name":"Germany","tla":"GER"}"#),
+            away_team: String::from(r#"{"name":"Scotland","tla":"SCO"}"#),
+            stage: Some(String::from("GROUP_STAGE")),
+            group: Some(String::from("GROUP_A")),
+            venue: Some(String::from("Munich Football Arena")),
+            status: String::from(if score.is_some() { "FINISHED" } else { "TIMED" }),
+            home_score: score.map(|score| score.0),
+            away_score: score.map(|score| score.1),
+            date,
+        }
+    }
+
+    fn tip(match_id: i32, score_home: i32, score_away: i32) -> Tip {
+        Tip { id: match_id, user_id: 1, match_id, score_home, score_away }
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let ics = to_ics("EURO 2024", "em2024", &[game(1, NOW + 3600, None)], None, NOW);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nX-WR-CALNAME:EURO 2024\r\n"));
+        assert!(ics.contains("\r\nBEGIN:VEVENT\r\nUID:em2024-match-1@em2024-api\r\nDTSTAMP:20240614T130000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20240614T140000Z\r\nDTEND:20240614T160000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Germany - Scotland\r\n"));
+        assert!(ics.contains("\r\nLOCATION:Munich Football Arena\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:Group A\r\n"));
+        assert!(!ics.contains("VALARM"));
+    }
+
+    #[test]
+    fn test_to_ics_with_tips() {
+        let games = vec![game(1, NOW - 86400, Some((5, 1))), game(2, NOW + 3600, None), game(3, NOW + 7200, None), game(4, NOW - 3600, None)];
+        let tips = HashMap::from([(1, tip(1, 2, 0)), (3, tip(3, 1, 1))]);
+
+        let ics = to_ics("EURO 2024", "em2024", &games, Some(&tips), NOW);
+        let events: Vec<&str> = ics.split("BEGIN:VEVENT").skip(1).collect();
+
+        assert!(events[0].contains("DESCRIPTION:Group A\\nResult: 5:1\\nYour tip: 2:0\r\n"));
+        assert!(events[1].contains("DESCRIPTION:Group A\\nYou haven't tipped yet!\r\n"));
+        assert!(events[1].contains("BEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT1H\r\n"));
+        assert!(events[2].contains("DESCRIPTION:Group A\\nYour tip: 1:1\r\n"));
+        assert!(!events[2].contains("VALARM"));
+        assert!(events[3].contains("DESCRIPTION:Group A\\nYou did not tip this match.\r\n"));
+        assert!(!events[3].contains("VALARM"));
+    }
+
+    #[test]
+    fn test_to_ics_with_undecided_teams() {
+        let mut final_match = game(5, NOW, None);
+        final_match.home_team = String::from(r#"{"name":"","tla":""}"#);
+        final_match.stage = Some(String::from("FINAL"));
+        final_match.group = None;
+        final_match.venue = None;
+
+        let ics = to_ics("EURO 2024", "em2024", &[final_match], None, NOW);
+
+        assert!(ics.contains("\r\nSUMMARY:TBD - Scotland\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:Final\r\n"));
+        assert!(!ics.contains("LOCATION"));
+    }
+
+    #[test]
+    fn test_round() {
+        let mut knockout = game(1, NOW, None);
+        knockout.group = None;
+        knockout.stage = Some(String::from("LAST_16"));
+
+        assert_eq!(round(&knockout), "Last 16");
+        assert_eq!(round(&game(1, NOW, None)), "Group A");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Köln; Düsseldorf, \\ Bonn\nNRW"), "Köln\\; Düsseldorf\\, \\\\ Bonn\\nNRW");
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("SUMMARY:short"), "SUMMARY:short");
+
+        let line = format!("DESCRIPTION:{}", "ü".repeat(40));
+        let folded = fold(&line);
+        let parts: Vec<&str> = folded.split("\r\n").collect();
+
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.len() <= MAX_LINE_LENGTH));
+        assert!(parts[1].starts_with(' '));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::service::UserRating;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RankingChange {
+    pub user_id: i32,
+    pub name: String,
+    pub department: String,
+    pub old_position: Option<i32>,
+    pub new_position: i32,
+    pub old_score: Option<i32>,
+    pub new_score: i32,
+}
+
+/// Compares two ranked tables and returns the users whose position or score
+/// changed, in the order of the new table. Users missing before are included
+/// with no old position.
+pub fn get_ranking_changes(before: &[UserRating], after: &[UserRating]) -> Vec<RankingChange> {
+    let before_by_user: HashMap<i32, &UserRating> = before.iter()
+        .map(|user_rating| (user_rating.user_id, user_rating))
+        .collect();
+
+    after.iter()
+        .filter_map(|user_rating| {
+            let old = before_by_user.get(&user_rating.user_id);
+            let unchanged = old.is_some_and(|old| old.position == user_rating.position && old.score_sum == user_rating.score_sum);
+            if unchanged {
+                return None;
+            }
+
+            Some(RankingChange {
+                user_id: user_rating.user_id,
+                name: user_rating.name.clone(),
+                department: user_rating.department.clone(),
+                old_position: old.map(|old| old.position),
+                new_position: user_rating.position,
+                old_score: old.map(|old| old.score_sum),
+                new_score: user_rating.score_sum,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::test_builder::user_rating;
+    use super::*;
+
+    fn ranked(user_id: i32, position: i32, score_sum: i32) -> UserRating {
+        UserRating { position, score_sum, ..user_rating(user_id) }
+    }
+
+    #[test]
+    fn test_get_ranking_changes() {
+        let before = vec![ranked(1, 1, 10), ranked(2, 2, 8), ranked(3, 3, 5)];
+        let after = vec![ranked(2, 1, 12), ranked(1, 2, 10), ranked(3, 3, 6), ranked(4, 4, 0)];
+
+        let changes = get_ranking_changes(&before, &after);
+
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0].user_id, 2);
+        assert_eq!(changes[0].old_position, Some(2));
+        assert_eq!(changes[0].new_position, 1);
+        assert_eq!(changes[0].old_score, Some(8));
+        assert_eq!(changes[0].new_score, 12);
+        assert_eq!(changes[1].user_id, 1);
+        assert_eq!(changes[1].old_score, Some(10));
+        assert_eq!(changes[2].user_id, 3);
+        assert_eq!(changes[2].old_position, Some(3));
+        assert_eq!(changes[3].user_id, 4);
+        assert_eq!(changes[3].old_position, None);
+    }
+
+    #[test]
+    fn test_get_ranking_changes_without_changes() {
+        let table = vec![ranked(1, 1, 10), ranked(2, 2, 8)];
+
+        assert!(get_ranking_changes(&table, &table).is_empty());
+    }
+}
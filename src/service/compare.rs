@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::db::{ScheduledGame, Tip};
+use crate::service::{MatchInfo, UserRating};
+
+/// A finished match with the tips of both users. `balance` is the points
+/// difference (user a minus user b) up to and including this match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparedGame {
+    pub match_id: String,
+    pub date: u64,
+    pub tip_a: MatchInfo,
+    pub tip_b: MatchInfo,
+    pub winner: Option<i32>,
+    pub balance: i32,
+}
+
+/// A match that kicked off but has no result yet, on which the users tipped differently.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferingTip {
+    pub match_id: String,
+    pub date: u64,
+    pub tip_a: MatchInfo,
+    pub tip_b: MatchInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    pub user_a: i32,
+    pub user_b: i32,
+    pub score_a: i32,
+    pub score_b: i32,
+    pub wins_a: i32,
+    pub wins_b: i32,
+    pub draws: i32,
+    pub balance: i32,
+    pub games: Vec<ComparedGame>,
+    pub differing_tips: Vec<DifferingTip>,
+}
+
+/// Lines up the finished matches of two ratings and lists the revealed
+/// matches (`started`) on which the two tips differ.
+pub fn compare_users(
+    user_a: &UserRating,
+    user_b: &UserRating,
+    started: &[ScheduledGame],
+    tips_a: &[Tip],
+    tips_b: &[Tip],
+) -> Result<Comparison, serde_json::Error> {
+    let tips_by_match: HashMap<&str, &MatchInfo> = user_b.tips.iter()
+        .map(|tip| (tip.match_id.as_str(), tip))
+        .collect();
+
+    let mut pairs: Vec<(&MatchInfo, &MatchInfo)> = user_a.tips.iter()
+        .filter_map(|tip_a| tips_by_match.get(tip_a.match_id.as_str()).map(|tip_b| (tip_a, *tip_b)))
+        .collect();
+    pairs.sort_by_key(|(tip_a, _)| tip_a.date);
+
+    let mut comparison = Comparison {
+        user_a: user_a.user_id,
+        user_b: user_b.user_id,
+        score_a: 0,
+        score_b: 0,
+        wins_a: 0,
+        wins_b: 0,
+        draws: 0,
+        balance: 0,
+        games: Vec::new(),
+        differing_tips: Vec::new(),
+    };
+
+    for (tip_a, tip_b) in pairs {
+        comparison.score_a += tip_a.score;
+        comparison.score_b += tip_b.score;
+        comparison.balance += tip_a.score - tip_b.score;
+
+        let winner = match tip_a.score.cmp(&tip_b.score) {
+            Ordering::Greater => {
+                comparison.wins_a += 1;
+                Some(user_a.user_id)
+            },
+            Ordering::Less => {
+                comparison.wins_b += 1;
+                Some(user_b.user_id)
+            },
+            Ordering::Equal => {
+                comparison.draws += 1;
+                None
+            },
+        };
+
+        comparison.games.push(ComparedGame {
+            match_id: tip_a.match_id.clone(),
+            date: tip_a.date,
+            tip_a: tip_a.clone(),
+            tip_b: tip_b.clone(),
+            winner,
+            balance: comparison.balance,
+        });
+    }
+
+    for game in started {
+        let tip_a = find_tip(tips_a, game.id);
+        let tip_b = find_tip(tips_b, game.id);
+
+        if tip_a != tip_b {
+            comparison.differing_tips.push(DifferingTip {
+                match_id: game.id.to_string(),
+                date: game.date,
+                tip_a: open_match_info(game, user_a, tip_a)?,
+                tip_b: open_match_info(game, user_b, tip_b)?,
+            });
+        }
+    }
+
+    Ok(comparison)
+}
+
+fn find_tip(tips: &[Tip], match_id: i32) -> Option<(i32, i32)> {
+    tips.iter()
+        .find(|tip| tip.match_id == match_id)
+        .map(|tip| (tip.score_home, tip.score_away))
+}
+
+fn open_match_info(game: &ScheduledGame, user_rating: &UserRating, tip: Option<(i32, i32)>) -> Result<MatchInfo, serde_json::Error> {
+    Ok(MatchInfo {
+        match_id: game.id.to_string(),
+        user: user_rating.name.clone(),
+        user_id: user_rating.user_id,
+        score: 0,
+        team1: serde_json::from_str(&game.home_team)?,
+        team2: serde_json::from_str(&game.away_team)?,
+        tip_home: tip.map(|tip| tip.0),
+        tip_away: tip.map(|tip| tip.1),
+        score_home: None,
+        score_away: None,
+        date: game.date,
+        provisional: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::test_builder::{match_info, scheduled_game, tip, user_rating};
+    use super::*;
+
+    fn scored(match_id: i32, user_id: i32, score: i32, date: u64) -> MatchInfo {
+        MatchInfo { score, date, ..match_info(match_id, user_id) }
+    }
+
+    fn with_tips(user_id: i32, tips: Vec<MatchInfo>) -> UserRating {
+        UserRating { position: 1, score_sum: tips.iter().map(|tip| tip.score).sum(), tips, ..user_rating(user_id) }
+    }
+
+    fn started_game(id: i32) -> ScheduledGame {
+        ScheduledGame { date: 500, ..scheduled_game(id, "GER", "ESP") }
+    }
+
+    #[test]
+    fn test_compare_users() {
+        let user_a = with_tips(1, vec![scored(2, 1, 0, 200), scored(1, 1, 4, 100), scored(3, 1, 1, 300)]);
+        let user_b = with_tips(2, vec![scored(1, 2, 1, 100), scored(2, 2, 2, 200), scored(3, 2, 1, 300)]);
+
+        let comparison = compare_users(&user_a, &user_b, &[], &[], &[]).unwrap();
+
+        assert_eq!(comparison.score_a, 5);
+        assert_eq!(comparison.score_b, 4);
+        assert_eq!(comparison.wins_a, 1);
+        assert_eq!(comparison.wins_b, 1);
+        assert_eq!(comparison.draws, 1);
+        assert_eq!(comparison.balance, 1);
+
+        assert_eq!(comparison.games.len(), 3);
+        assert_eq!(comparison.games[0].match_id, "1");
+        assert_eq!(comparison.games[0].winner, Some(1));
+        assert_eq!(comparison.games[0].balance, 3);
+        assert_eq!(comparison.games[1].winner, Some(2));
+        assert_eq!(comparison.games[1].balance, 1);
+        assert_eq!(comparison.games[2].winner, None);
+        assert_eq!(comparison.games[2].balance, 1);
+    }
+
+    #[test]
+    fn test_compare_users_lists_differing_revealed_tips() {
+        let user_a = with_tips(1, Vec::new());
+        let user_b = with_tips(2, Vec::new());
+        let started = vec![started_game(4), started_game(5), started_game(6)];
+        let tips_a = vec![tip(1, 4, 1, 0), tip(1, 5, 2, 2)];
+        let tips_b = vec![tip(2, 4, 1, 0), tip(2, 5, 0, 2), tip(2, 6, 3, 3)];
+
+        let comparison = compare_users(&user_a, &user_b, &started, &tips_a, &tips_b).unwrap();
+
+        assert_eq!(comparison.differing_tips.len(), 2);
+        assert_eq!(comparison.differing_tips[0].match_id, "5");
+        assert_eq!(comparison.differing_tips[0].tip_a.tip_home, Some(2));
+        assert_eq!(comparison.differing_tips[0].tip_b.tip_home, Some(0));
+        assert_eq!(comparison.differing_tips[0].tip_b.user_id, 2);
+        assert_eq!(comparison.differing_tips[1].match_id, "6");
+        assert_eq!(comparison.differing_tips[1].tip_a.tip_home, None);
+        assert_eq!(comparison.differing_tips[1].tip_b.team1.tla, "GER");
+    }
+}
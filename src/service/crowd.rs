@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::db::{Game, Tip, User};
+use crate::service::{rate_user, UserRating};
+use crate::service::ranking::RuleSet;
+
+pub const CROWD_USER_ID: i32 = 0;
+pub const CROWD_NAME: &str = "Crowd";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrowdMode {
+    /// The most common exact score.
+    Mode,
+    /// The median of the home and of the away goals.
+    Median,
+}
+
+/// Rates a virtual participant that tips what the community tipped on each
+/// match. Matches nobody tipped stay without a crowd tip.
+pub fn get_crowd_rating(games: &[Game], tips: &[Tip], crowd_mode: CrowdMode, rules: RuleSet) -> UserRating {
+    let mut tips_by_match: HashMap<i32, Vec<(i32, i32)>> = HashMap::new();
+    for tip in tips {
+        tips_by_match.entry(tip.match_id).or_default().push((tip.score_home, tip.score_away));
+    }
+
+    let crowd_tips: Vec<Tip> = tips_by_match.into_iter()
+        .map(|(match_id, scores)| {
+            let (score_home, score_away) = match crowd_mode {
+                CrowdMode::Mode => most_common(&scores),
+                CrowdMode::Median => median(&scores),
+            };
+            Tip { id: 0, user_id: CROWD_USER_ID, match_id, score_home, score_away }
+        })
+        .collect();
+
+    let crowd = User {
+        id: CROWD_USER_ID,
+        username: CROWD_NAME.to_string(),
+        department: String::new(),
+        winner: String::new(),
+        secret_winner: String::new(),
+    };
+
+    let mut user_rating = rate_user(&crowd, crowd_tips, games, rules, None);
+    user_rating.is_crowd = true;
+
+    user_rating
+}
+
+// On a tie the score with fewer goals wins, then the one with fewer home goals.
+fn most_common(scores: &[(i32, i32)]) -> (i32, i32) {
+    let mut counts: HashMap<(i32, i32), i32> = HashMap::new();
+    for score in scores {
+        *counts.entry(*score).or_default() += 1;
+    }
+
+    counts.into_iter()
+        .min_by_key(|((home, away), count)| (-count, home + away, *home))
+        .map(|(score, _)| score)
+        .unwrap()
+}
+
+// Tips are whole goals, so an even number of tips takes the lower middle.
+fn median(scores: &[(i32, i32)]) -> (i32, i32) {
+    let mut home: Vec<i32> = scores.iter().map(|score| score.0).collect();
+    let mut away: Vec<i32> = scores.iter().map(|score| score.1).collect();
+    home.sort_unstable();
+    away.sort_unstable();
+
+    let middle = (scores.len() - 1) / 2;
+    (home[middle], away[middle])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(id: i32, home_score: i32, away_score: i32) -> Game {
+        Game {
+            id,
+            home_team: String::from(r#"{"name":"Germany","tla":"GER"}"#),
+            away_team: String::from(r#"{"name":"Spain","tla":"ESP"}"#),
+            home_score,
+            away_score,
+            date: id as u64,
+            is_live: false,
+        }
+    }
+
+    fn tip(match_id: i32, score_home: i32, score_away: i32) -> Tip {
+        Tip { id: 1, user_id: 1, match_id, score_home, score_away }
+    }
+
+    #[test]
+    fn test_most_common() {
+        assert_eq!(most_common(&[(2, 0), (1, 1), (2, 0), (3, 1)]), (2, 0));
+        assert_eq!(most_common(&[(2, 1), (1, 1), (3, 1)]), (1, 1));
+        assert_eq!(most_common(&[(2, 0), (0, 2)]), (0, 2));
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[(2, 0), (1, 1), (4, 1)]), (2, 1));
+        assert_eq!(median(&[(3, 0), (1, 2), (2, 1), (0, 0)]), (1, 0));
+    }
+
+    #[test]
+    fn test_get_crowd_rating() {
+        let games = vec![game(1, 2, 0), game(2, 1, 1), game(3, 0, 1)];
+        let tips = vec![tip(1, 2, 0), tip(1, 2, 0), tip(1, 1, 0), tip(2, 2, 1), tip(2, 0, 0), tip(2, 1, 2)];
+
+        let mode = get_crowd_rating(&games, &tips, CrowdMode::Mode, RuleSet::Em2024);
+        assert!(mode.is_crowd);
+        assert_eq!(mode.user_id, CROWD_USER_ID);
+        assert_eq!(mode.name, CROWD_NAME);
+        assert_eq!(mode.tips.len(), 3);
+        assert_eq!(mode.tips[0].tip_home, Some(2));
+        assert_eq!(mode.tips[1].tip_home, Some(0));
+        assert_eq!(mode.tips[2].tip_home, None);
+        assert_eq!(mode.score_sum, 5);
+
+        let median = get_crowd_rating(&games, &tips, CrowdMode::Median, RuleSet::Em2024);
+        assert_eq!(median.tips[1].tip_home, Some(1));
+        assert_eq!(median.tips[1].tip_away, Some(1));
+        assert_eq!(median.score_sum, 8);
+    }
+}
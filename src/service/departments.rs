@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use crate::service::UserRating;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepartmentRating {
+    pub department: String,
+    pub position: i32,
+    pub member_count: i32,
+    pub counted_members: i32,
+    pub score: i32,
+    pub score_sum: i32,
+    pub average: f64,
+    pub median: f64,
+}
+
+/// Builds the table between departments. `score` is what the table is sorted
+/// by: the points of all members, or only of the `best` N members if given.
+pub fn get_department_rating(user_rating_list: &[UserRating], best: Option<usize>) -> Vec<DepartmentRating> {
+    let mut scores_by_department: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+
+    for user_rating in user_rating_list {
+        scores_by_department.entry(user_rating.department.clone()).or_default().push(user_rating.score_sum);
+    }
+
+    let mut department_rating_list: Vec<DepartmentRating> = scores_by_department.into_iter()
+        .map(|(department, mut scores)| {
+            scores.sort_by_key(|score| Reverse(*score));
+
+            let counted = best.map_or(scores.len(), |best| best.min(scores.len()));
+            let score_sum: i32 = scores.iter().sum();
+
+            DepartmentRating {
+                department,
+                position: 0,
+                member_count: scores.len() as i32,
+                counted_members: counted as i32,
+                score: scores[..counted].iter().sum(),
+                score_sum,
+                average: score_sum as f64 / scores.len() as f64,
+                median: median(&scores),
+            }
+        })
+        .collect();
+
+    calculate_department_positions(&mut department_rating_list);
+
+    department_rating_list
+}
+
+fn calculate_department_positions(department_rating_list: &mut [DepartmentRating]) {
+    department_rating_list.sort_by_key(|department_rating| Reverse(department_rating.score));
+
+    let mut position = 0;
+    let mut last_score = None;
+    let mut position_for_frontend = 0;
+
+    for department_rating in department_rating_list {
+        position += 1;
+        if last_score != Some(department_rating.score) {
+            position_for_frontend = position;
+        }
+
+        department_rating.position = position_for_frontend;
+
+        last_score = Some(department_rating.score);
+    }
+}
+
+fn median(sorted_scores: &[i32]) -> f64 {
+    let middle = sorted_scores.len() / 2;
+
+    if sorted_scores.len().is_multiple_of(2) {
+        (sorted_scores[middle - 1] + sorted_scores[middle]) as f64 / 2.0
+    } else {
+        sorted_scores[middle] as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::test_builder::user_rating;
+    use super::*;
+
+    fn member(name: &str, department: &str, score_sum: i32) -> UserRating {
+        UserRating { name: name.to_string(), department: department.to_string(), score_sum, ..user_rating(1) }
+    }
+
+    fn user_rating_list() -> Vec<UserRating> {
+        vec![
+            member("a", "Big", 10),
+            member("b", "Big", 2),
+            member("c", "Big", 3),
+            member("d", "Big", 1),
+            member("e", "Small", 9),
+            member("f", "Small", 5),
+            member("g", "Tiny", 12),
+        ]
+    }
+
+    #[test]
+    fn test_get_department_rating() {
+        let department_rating_list = get_department_rating(&user_rating_list(), None);
+
+        assert_eq!(department_rating_list.len(), 3);
+
+        assert_eq!(department_rating_list[0].department, "Big");
+        assert_eq!(department_rating_list[0].position, 1);
+        assert_eq!(department_rating_list[0].member_count, 4);
+        assert_eq!(department_rating_list[0].counted_members, 4);
+        assert_eq!(department_rating_list[0].score, 16);
+        assert_eq!(department_rating_list[0].score_sum, 16);
+        assert_eq!(department_rating_list[0].average, 4.0);
+        assert_eq!(department_rating_list[0].median, 2.5);
+
+        assert_eq!(department_rating_list[1].department, "Small");
+        assert_eq!(department_rating_list[1].position, 2);
+        assert_eq!(department_rating_list[1].median, 7.0);
+
+        assert_eq!(department_rating_list[2].department, "Tiny");
+        assert_eq!(department_rating_list[2].position, 3);
+        assert_eq!(department_rating_list[2].median, 12.0);
+    }
+
+    #[test]
+    fn test_get_department_rating_with_best_members() {
+        let department_rating_list = get_department_rating(&user_rating_list(), Some(2));
+
+        assert_eq!(department_rating_list[0].department, "Small");
+        assert_eq!(department_rating_list[0].score, 14);
+        assert_eq!(department_rating_list[0].position, 1);
+
+        assert_eq!(department_rating_list[1].department, "Big");
+        assert_eq!(department_rating_list[1].score, 13);
+        assert_eq!(department_rating_list[1].score_sum, 16);
+        assert_eq!(department_rating_list[1].counted_members, 2);
+        assert_eq!(department_rating_list[1].position, 2);
+
+        assert_eq!(department_rating_list[2].department, "Tiny");
+        assert_eq!(department_rating_list[2].counted_members, 1);
+    }
+
+    #[test]
+    fn test_get_department_rating_shares_positions() {
+        let department_rating_list = get_department_rating(&user_rating_list(), Some(1));
+
+        assert_eq!(department_rating_list[0].department, "Tiny");
+        assert_eq!(department_rating_list[0].position, 1);
+        assert_eq!(department_rating_list[1].department, "Big");
+        assert_eq!(department_rating_list[1].position, 2);
+        assert_eq!(department_rating_list[2].department, "Small");
+        assert_eq!(department_rating_list[2].position, 3);
+
+        let department_rating_list = get_department_rating(&[
+            member("a", "One", 5),
+            member("b", "Two", 5),
+            member("c", "Three", 4),
+        ], None);
+
+        assert_eq!(department_rating_list[0].position, 1);
+        assert_eq!(department_rating_list[1].position, 1);
+        assert_eq!(department_rating_list[2].position, 3);
+    }
+}
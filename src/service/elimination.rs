@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::db::{ScheduledGame, User};
+use crate::service::ranking::RuleSet;
+use crate::service::UserRating;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaxPoints {
+    pub user_id: i32,
+    pub name: String,
+    pub department: String,
+    pub score_sum: i32,
+    pub open_matches: i32,
+    pub max_possible_points: i32,
+    pub can_win_global: bool,
+    pub can_win_department: bool,
+}
+
+/// Best case for every user: an exact tip on every open match plus the champion
+/// bonus their picks could still bring. A match is open if it has not kicked
+/// off yet (a tip can still be entered) or the user tipped it before kickoff.
+/// The champion bonus counts as unresolved until the champion is set or every
+/// match has a result.
+///
+/// A user can still win a table if their best case reaches the current leader,
+/// assuming everyone else scores no more points.
+pub fn get_max_points(
+    user_rating_list: &[UserRating],
+    users: &[User],
+    unfinished: &[ScheduledGame],
+    tipped_matches: &HashMap<i32, HashSet<i32>>,
+    rules: RuleSet,
+    champion: Option<&str>,
+    now: u64,
+) -> Vec<MaxPoints> {
+    let win_exact = rules.category_points().win_exact;
+    let picks: HashMap<i32, &User> = users.iter().map(|user| (user.id, user)).collect();
+    let no_tips = HashSet::new();
+
+    let mut max_points_list: Vec<MaxPoints> = user_rating_list.iter()
+        .map(|user_rating| {
+            let tipped = tipped_matches.get(&user_rating.user_id).unwrap_or(&no_tips);
+            let open_matches = unfinished.iter()
+                .filter(|game| game.date > now || tipped.contains(&game.id))
+                .count() as i32;
+
+            let unresolved_bonus = match picks.get(&user_rating.user_id) {
+                Some(user) if champion.is_none() && !unfinished.is_empty() => (best_champion_bonus(user, rules) - user_rating.extra_point).max(0),
+                _ => 0,
+            };
+
+            MaxPoints {
+                user_id: user_rating.user_id,
+                name: user_rating.name.clone(),
+                department: user_rating.department.clone(),
+                score_sum: user_rating.score_sum,
+                open_matches,
+                max_possible_points: user_rating.score_sum + open_matches * win_exact + unresolved_bonus,
+                can_win_global: false,
+                can_win_department: false,
+            }
+        })
+        .collect();
+
+    let global_leader = user_rating_list.iter().map(|user_rating| user_rating.score_sum).max().unwrap_or(0);
+    let mut department_leaders: HashMap<&str, i32> = HashMap::new();
+    for user_rating in user_rating_list {
+        let leader = department_leaders.entry(user_rating.department.as_str()).or_insert(user_rating.score_sum);
+        *leader = (*leader).max(user_rating.score_sum);
+    }
+
+    for max_points in &mut max_points_list {
+        max_points.can_win_global = max_points.max_possible_points >= global_leader;
+        max_points.can_win_department = max_points.max_possible_points >= department_leaders[max_points.department.as_str()];
+    }
+
+    max_points_list.sort_by(|a, b| b.max_possible_points.cmp(&a.max_possible_points).then_with(|| a.user_id.cmp(&b.user_id)));
+
+    max_points_list
+}
+
+// The bonus if one of the user's picks becomes champion.
+fn best_champion_bonus(user: &User, rules: RuleSet) -> i32 {
+    [&user.winner, &user.secret_winner].into_iter()
+        .filter(|pick| !pick.is_empty())
+        .map(|pick| rules.extra_points(user, Some(pick)))
+        .max()
+        .unwrap_or_else(|| rules.extra_points(user, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::test_builder::{scheduled_game, user_rating};
+    use super::*;
+
+    fn user(id: i32, department: &str, winner: &str, secret_winner: &str) -> User {
+        User {
+            id,
+            username: format!("user{}", id),
+            department: department.to_string(),
+            winner: winner.to_string(),
+            secret_winner: secret_winner.to_string(),
+        }
+    }
+
+    fn rated(user_id: i32, department: &str, score_sum: i32, extra_point: i32) -> UserRating {
+        UserRating { department: department.to_string(), score_sum, extra_point, ..user_rating(user_id) }
+    }
+
+    fn unfinished_game(id: i32, date: u64) -> ScheduledGame {
+        ScheduledGame { date, ..scheduled_game(id, "FRA", "AUT") }
+    }
+
+    #[test]
+    fn test_get_max_points() {
+        let users = vec![
+            user(1, "Langenfeld", "ESP", ""),
+            user(2, "Langenfeld", "", "GER"),
+            user(3, "London", "", ""),
+        ];
+        let user_rating_list = vec![
+            rated(1, "Langenfeld", 40, 15),
+            rated(2, "Langenfeld", 20, 0),
+            rated(3, "London", 10, 0),
+        ];
+        // Match 3 kicked off already, only user 2 tipped it.
+        let unfinished = vec![unfinished_game(3, 900), unfinished_game(4, 2000)];
+        let tipped_matches = HashMap::from([(2, HashSet::from([3]))]);
+
+        let max_points = get_max_points(&user_rating_list, &users, &unfinished, &tipped_matches, RuleSet::Em2024, None, 1000);
+
+        assert_eq!(max_points[0].user_id, 1);
+        assert_eq!(max_points[0].open_matches, 1);
+        assert_eq!(max_points[0].max_possible_points, 44);
+        assert!(max_points[0].can_win_global);
+
+        assert_eq!(max_points[1].user_id, 2);
+        assert_eq!(max_points[1].open_matches, 2);
+        assert_eq!(max_points[1].max_possible_points, 35);
+        assert!(!max_points[1].can_win_global);
+        assert!(!max_points[1].can_win_department);
+
+        assert_eq!(max_points[2].user_id, 3);
+        assert_eq!(max_points[2].max_possible_points, 14);
+        assert!(!max_points[2].can_win_global);
+        assert!(max_points[2].can_win_department);
+    }
+
+    #[test]
+    fn test_get_max_points_with_classic_rules() {
+        let users = vec![user(1, "Langenfeld", "ESP", ""), user(2, "Langenfeld", "", "")];
+        let user_rating_list = vec![rated(1, "Langenfeld", 10, 0), rated(2, "Langenfeld", 4, 0)];
+        let unfinished = vec![unfinished_game(3, 2000), unfinished_game(4, 3000)];
+
+        let max_points = get_max_points(&user_rating_list, &users, &unfinished, &HashMap::new(), RuleSet::Classic2021, None, 1000);
+
+        // Three points per exact tip and no champion bonus.
+        assert_eq!(max_points[0].user_id, 1);
+        assert_eq!(max_points[0].max_possible_points, 16);
+        assert_eq!(max_points[1].max_possible_points, 10);
+        assert!(max_points[1].can_win_global);
+    }
+
+    #[test]
+    fn test_get_max_points_after_last_match() {
+        let users = vec![user(1, "Langenfeld", "GER", "")];
+        let user_rating_list = vec![rated(1, "Langenfeld", 12, 0)];
+
+        let max_points = get_max_points(&user_rating_list, &users, &[], &HashMap::new(), RuleSet::Em2024, None, 1000);
+
+        assert_eq!(max_points[0].open_matches, 0);
+        assert_eq!(max_points[0].max_possible_points, 12);
+        assert!(max_points[0].can_win_global);
+    }
+
+    #[test]
+    fn test_get_max_points_after_champion_is_set() {
+        let users = vec![user(1, "Langenfeld", "ESP", ""), user(2, "Langenfeld", "", "GER")];
+        let user_rating_list = vec![rated(1, "Langenfeld", 30, 0), rated(2, "Langenfeld", 20, 0)];
+        let unfinished = vec![unfinished_game(4, 2000)];
+
+        let max_points = get_max_points(&user_rating_list, &users, &unfinished, &HashMap::new(), RuleSet::Em2024, Some("FRA"), 1000);
+
+        // Neither pick won, so only the open match is left.
+        assert_eq!(max_points[0].max_possible_points, 34);
+        assert_eq!(max_points[1].max_possible_points, 24);
+        assert!(!max_points[1].can_win_global);
+    }
+}
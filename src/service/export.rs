@@ -0,0 +1,265 @@
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use crate::service::{MatchInfo, UserRating};
+
+// Excel only reads a CSV file as UTF-8 (and shows umlauts) if it starts with a BOM.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+const RATING_COLUMNS: [&str; 9] = [
+    "position", "name", "user_id", "department", "score_sum", "sum_win_exact", "sum_score_diff", "sum_team", "extra_point",
+];
+
+// Characters Excel does not allow in sheet names, and their maximum length.
+const INVALID_SHEET_CHARS: [char; 7] = ['[', ']', ':', '*', '?', '/', '\\'];
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+
+/// The columns of `UserRating` without the tips.
+#[derive(Serialize)]
+struct RatingRow<'a> {
+    position: i32,
+    name: &'a str,
+    user_id: i32,
+    department: &'a str,
+    score_sum: i32,
+    sum_win_exact: i32,
+    sum_score_diff: i32,
+    sum_team: i32,
+    extra_point: i32,
+}
+
+/// The columns of `MatchInfo`, with the names of the teams.
+#[derive(Serialize)]
+struct TipRow<'a> {
+    match_id: &'a str,
+    user: &'a str,
+    user_id: i32,
+    score: i32,
+    team1: &'a str,
+    team2: &'a str,
+    tip_home: Option<i32>,
+    tip_away: Option<i32>,
+    score_home: Option<i32>,
+    score_away: Option<i32>,
+    date: u64,
+    provisional: bool,
+}
+
+impl<'a> From<&'a UserRating> for RatingRow<'a> {
+    fn from(user_rating: &'a UserRating) -> Self {
+        RatingRow {
+            position: user_rating.position,
+            name: &user_rating.name,
+            user_id: user_rating.user_id,
+            department: &user_rating.department,
+            score_sum: user_rating.score_sum,
+            sum_win_exact: user_rating.sum_win_exact,
+            sum_score_diff: user_rating.sum_score_diff,
+            sum_team: user_rating.sum_team,
+            extra_point: user_rating.extra_point,
+        }
+    }
+}
+
+impl<'a> From<&'a MatchInfo> for TipRow<'a> {
+    fn from(match_info: &'a MatchInfo) -> Self {
+        TipRow {
+            match_id: &match_info.match_id,
+            user: &match_info.user,
+            user_id: match_info.user_id,
+            score: match_info.score,
+            team1: &match_info.team1.name,
+            team2: &match_info.team2.name,
+            tip_home: match_info.tip_home,
+            tip_away: match_info.tip_away,
+            score_home: match_info.score_home,
+            score_away: match_info.score_away,
+            date: match_info.date,
+            provisional: match_info.provisional,
+        }
+    }
+}
+
+/// The ranking as CSV, one row per user in the given order.
+pub fn rating_csv(user_rating_list: &[UserRating]) -> Result<Vec<u8>, Box<dyn Error>> {
+    to_csv(user_rating_list.iter().map(RatingRow::from))
+}
+
+/// The tips as CSV, one row per tip. Matches a user did not tip are left out.
+/// Sorted by kickoff, match and user.
+pub fn tips_csv(user_rating_list: &[UserRating]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut tips: Vec<&MatchInfo> = user_rating_list.iter()
+        .flat_map(|user_rating| &user_rating.tips)
+        .filter(|match_info| match_info.tip_home.is_some())
+        .collect();
+    tips.sort_by_key(|match_info| (match_info.date, match_info.match_id.parse::<i32>().unwrap_or_default(), match_info.user_id));
+
+    to_csv(tips.into_iter().map(TipRow::from))
+}
+
+fn to_csv<T: Serialize>(rows: impl Iterator<Item = T>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(UTF8_BOM.to_vec());
+    for row in rows {
+        writer.serialize(row)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+/// The ranking as an Excel workbook: the global table on the first sheet and
+/// the table of every department, sorted by name, on a sheet of its own.
+pub fn rating_xlsx(global: &[UserRating], departments: &BTreeMap<String, Vec<UserRating>>) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let mut sheet_names = HashSet::new();
+
+    write_rating_sheet(workbook.add_worksheet(), &sheet_name("Global", &mut sheet_names), global)?;
+    for (department, user_rating_list) in departments {
+        write_rating_sheet(workbook.add_worksheet(), &sheet_name(department, &mut sheet_names), user_rating_list)?;
+    }
+
+    workbook.save_to_buffer()
+}
+
+fn write_rating_sheet(worksheet: &mut Worksheet, name: &str, user_rating_list: &[UserRating]) -> Result<(), XlsxError> {
+    let header_format = Format::new().set_bold();
+
+    worksheet.set_name(name)?;
+    for (col, column) in RATING_COLUMNS.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *column, &header_format)?;
+    }
+
+    for (index, user_rating) in user_rating_list.iter().enumerate() {
+        let row = index as u32 + 1;
+        worksheet.write_number(row, 0, user_rating.position)?;
+        worksheet.write_string(row, 1, &user_rating.name)?;
+        worksheet.write_number(row, 2, user_rating.user_id)?;
+        worksheet.write_string(row, 3, &user_rating.department)?;
+        worksheet.write_number(row, 4, user_rating.score_sum)?;
+        worksheet.write_number(row, 5, user_rating.sum_win_exact)?;
+        worksheet.write_number(row, 6, user_rating.sum_score_diff)?;
+        worksheet.write_number(row, 7, user_rating.sum_team)?;
+        worksheet.write_number(row, 8, user_rating.extra_point)?;
+    }
+
+    Ok(())
+}
+
+// A valid sheet name that is not taken yet. Excel compares sheet names ignoring case.
+fn sheet_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let base: String = name.chars()
+        .map(|c| if INVALID_SHEET_CHARS.contains(&c) { '_' } else { c })
+        .take(MAX_SHEET_NAME_LENGTH)
+        .collect();
+    let base = if base.trim().is_empty() { String::from("_") } else { base };
+
+    let mut candidate = base.clone();
+    let mut number = 1;
+    while !taken.insert(candidate.to_lowercase()) {
+        number += 1;
+        let suffix = format!(" ({})", number);
+        let prefix: String = base.chars().take(MAX_SHEET_NAME_LENGTH - suffix.len()).collect();
+        candidate = format!("{}{}", prefix, suffix);
+    }
+
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::Team;
+    use crate::service::test_builder::{match_info, user_rating};
+    use super::*;
+
+    fn rated(name: &str, user_id: i32, department: &str, position: i32, score_sum: i32) -> UserRating {
+        UserRating {
+            name: name.to_string(),
+            department: department.to_string(),
+            position,
+            score_sum,
+            sum_win_exact: 1,
+            sum_team: 2,
+            ..user_rating(user_id)
+        }
+    }
+
+    fn tipped(match_id: i32, user_id: i32, tip: Option<(i32, i32)>, date: u64) -> MatchInfo {
+        MatchInfo {
+            score: 4,
+            team1: Team { name: String::from("Deutschland"), tla: String::from("GER") },
+            team2: Team { name: String::from("Côte d'Ivoire"), tla: String::from("CIV") },
+            tip_home: tip.map(|tip| tip.0),
+            tip_away: tip.map(|tip| tip.1),
+            score_home: Some(2),
+            score_away: Some(1),
+            date,
+            ..match_info(match_id, user_id)
+        }
+    }
+
+    fn csv_text(csv: Vec<u8>) -> String {
+        assert!(csv.starts_with(UTF8_BOM));
+        String::from_utf8(csv[UTF8_BOM.len()..].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_rating_csv() {
+        let csv = rating_csv(&[
+            rated("Müller, Thomas", 1, "Düsseldorf", 1, 10),
+            rated("Say \"Hi\"", 2, "London", 2, 8),
+        ]).unwrap();
+
+        assert_eq!(csv_text(csv), "\
+position,name,user_id,department,score_sum,sum_win_exact,sum_score_diff,sum_team,extra_point
+1,\"Müller, Thomas\",1,Düsseldorf,10,1,0,2,0
+2,\"Say \"\"Hi\"\"\",2,London,8,1,0,2,0
+");
+    }
+
+    #[test]
+    fn test_rating_csv_header_matches_columns() {
+        let csv = csv_text(rating_csv(&[rated("a", 1, "b", 1, 0)]).unwrap());
+
+        assert_eq!(csv.lines().next().unwrap(), RATING_COLUMNS.join(","));
+    }
+
+    #[test]
+    fn test_tips_csv() {
+        let mut first = rated("a", 1, "b", 1, 0);
+        first.tips = vec![tipped(2, 1, Some((2, 1)), 200), tipped(1, 1, None, 100)];
+        let mut second = rated("c", 2, "b", 2, 0);
+        second.tips = vec![tipped(2, 2, Some((0, 0)), 200), tipped(1, 2, Some((1, 0)), 100)];
+
+        let csv = csv_text(tips_csv(&[first, second]).unwrap());
+
+        assert_eq!(csv, "\
+match_id,user,user_id,score,team1,team2,tip_home,tip_away,score_home,score_away,date,provisional
+1,user2,2,4,Deutschland,Côte d'Ivoire,1,0,2,1,100,false
+2,user1,1,4,Deutschland,Côte d'Ivoire,2,1,2,1,200,false
+2,user2,2,4,Deutschland,Côte d'Ivoire,0,0,2,1,200,false
+");
+    }
+
+    #[test]
+    fn test_rating_xlsx() {
+        let global = vec![rated("Müller, Thomas", 1, "Düsseldorf", 1, 10)];
+        let departments = BTreeMap::from([(String::from("Düsseldorf"), global.clone())]);
+
+        let xlsx = rating_xlsx(&global, &departments).unwrap();
+
+        // An xlsx file is a zip archive.
+        assert!(xlsx.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn test_sheet_name() {
+        let mut taken = HashSet::new();
+
+        assert_eq!(sheet_name("Global", &mut taken), "Global");
+        assert_eq!(sheet_name("global", &mut taken), "global (2)");
+        assert_eq!(sheet_name("R&D / QA", &mut taken), "R&D _ QA");
+        assert_eq!(sheet_name("", &mut taken), "_");
+        assert_eq!(sheet_name("Research and Development Department", &mut taken), "Research and Development Depart");
+        assert_eq!(sheet_name("Research and Development Department 2", &mut taken), "Research and Development De (2)");
+    }
+}
@@ -0,0 +1,345 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::db::{Game, ScheduledGame, TeamStrength, Tip};
+use crate::service::ranking::RuleSet;
+use crate::service::{Team, UserRating};
+
+const DEFAULT_GOALS_PER_TEAM: f64 = 1.3;
+// Results so far are blended with this many average matches, so a single
+// result does not make a team unbeatable.
+const PRIOR_MATCHES: f64 = 2.0;
+// Playing time of a match including half-time, in seconds. A running match
+// only has the goals of its remaining time left.
+const MATCH_DURATION: f64 = 105.0 * 60.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserForecast {
+    pub user_id: i32,
+    pub name: String,
+    pub department: String,
+    pub score_sum: i32,
+    pub average_points: f64,
+    pub first: f64,
+    pub top_3: f64,
+    pub top_10: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Forecast {
+    pub seed: u64,
+    pub iterations: u32,
+    pub users: Vec<UserForecast>,
+}
+
+/// How often to simulate, with which seed and at what time.
+#[derive(Debug, Clone, Copy)]
+pub struct ForecastRun {
+    pub iterations: u32,
+    pub seed: u64,
+    pub now: u64,
+}
+
+/// Poisson goal model. The expected goals of a team are the average goals per
+/// team times its `attack` times the opponent's `defence`.
+#[derive(Debug, Clone)]
+pub struct GoalModel {
+    goals_per_team: f64,
+    strengths: HashMap<String, (f64, f64)>,
+}
+
+impl GoalModel {
+    /// Derives attack and defence of every team from the results so far.
+    /// Strengths set by an admin take precedence.
+    pub fn new(past_games: &[Game], admin_strengths: &[TeamStrength]) -> Result<GoalModel, serde_json::Error> {
+        let mut goals: HashMap<String, (f64, f64, f64)> = HashMap::new();
+        let mut total_goals = 0.0;
+
+        for game in past_games {
+            let home_team: Team = serde_json::from_str(&game.home_team)?;
+            let away_team: Team = serde_json::from_str(&game.away_team)?;
+
+            for (team, scored, conceded) in [(home_team, game.home_score, game.away_score), (away_team, game.away_score, game.home_score)] {
+                let entry = goals.entry(team.tla).or_insert((0.0, 0.0, 0.0));
+                entry.0 += scored as f64;
+                entry.1 += conceded as f64;
+                entry.2 += 1.0;
+            }
+            total_goals += (game.home_score + game.away_score) as f64;
+        }
+
+        let goals_per_team = if past_games.is_empty() || total_goals == 0.0 {
+            DEFAULT_GOALS_PER_TEAM
+        } else {
+            total_goals / (2 * past_games.len()) as f64
+        };
+
+        let mut strengths: HashMap<String, (f64, f64)> = goals.into_iter()
+            .map(|(tla, (scored, conceded, matches))| {
+                let prior = goals_per_team * PRIOR_MATCHES;
+                let attack = (scored + prior) / (matches + PRIOR_MATCHES) / goals_per_team;
+                let defence = (conceded + prior) / (matches + PRIOR_MATCHES) / goals_per_team;
+                (tla, (attack, defence))
+            })
+            .collect();
+
+        for strength in admin_strengths {
+            strengths.insert(strength.tla.clone(), (strength.attack, strength.defence));
+        }
+
+        Ok(GoalModel { goals_per_team, strengths })
+    }
+
+    fn expected_goals(&self, team: &str, opponent: &str) -> f64 {
+        let attack = self.strengths.get(team).map_or(1.0, |strength| strength.0);
+        let defence = self.strengths.get(opponent).map_or(1.0, |strength| strength.1);
+
+        self.goals_per_team * attack * defence
+    }
+}
+
+/// Simulates the unfinished matches `iterations` times and scores the existing
+/// tips of every user against each outcome. Running matches continue from their
+/// current score. The same seed gives the same forecast. Ties share the better
+/// position, like in the rating.
+pub fn get_forecast(
+    user_rating_list: &[UserRating],
+    unfinished: &[ScheduledGame],
+    tips_by_user: &HashMap<i32, Vec<Tip>>,
+    model: &GoalModel,
+    rules: RuleSet,
+    run: ForecastRun,
+) -> Result<Forecast, serde_json::Error> {
+    let ForecastRun { iterations, seed, now } = run;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Current score and expected further goals of every match.
+    let mut expected_goals = Vec::new();
+    for game in unfinished {
+        let home_team: Team = serde_json::from_str(&game.home_team)?;
+        let away_team: Team = serde_json::from_str(&game.away_team)?;
+        let (current, remaining) = match (game.home_score, game.away_score) {
+            (Some(home_score), Some(away_score)) => {
+                let played = now.saturating_sub(game.date) as f64 / MATCH_DURATION;
+                ((home_score, away_score), (1.0 - played).clamp(0.0, 1.0))
+            },
+            _ => ((0, 0), 1.0),
+        };
+        expected_goals.push((
+            current,
+            model.expected_goals(&home_team.tla, &away_team.tla) * remaining,
+            model.expected_goals(&away_team.tla, &home_team.tla) * remaining,
+        ));
+    }
+
+    // Tips per user in the order of the unfinished matches.
+    let no_tips = Vec::new();
+    let tips: Vec<Vec<Option<(i32, i32)>>> = user_rating_list.iter()
+        .map(|user_rating| {
+            let user_tips = tips_by_user.get(&user_rating.user_id).unwrap_or(&no_tips);
+            unfinished.iter()
+                .map(|game| user_tips.iter().find(|tip| tip.match_id == game.id).map(|tip| (tip.score_home, tip.score_away)))
+                .collect()
+        })
+        .collect();
+
+    let mut first = vec![0u32; user_rating_list.len()];
+    let mut top_3 = vec![0u32; user_rating_list.len()];
+    let mut top_10 = vec![0u32; user_rating_list.len()];
+    let mut points = vec![0u64; user_rating_list.len()];
+    let mut scores = vec![0; user_rating_list.len()];
+    let mut ranked = vec![0; user_rating_list.len()];
+
+    for _ in 0..iterations {
+        let results: Vec<(i32, i32)> = expected_goals.iter()
+            .map(|((home_score, away_score), home, away)| (home_score + sample_poisson(&mut rng, *home), away_score + sample_poisson(&mut rng, *away)))
+            .collect();
+
+        for (index, user_rating) in user_rating_list.iter().enumerate() {
+            let gained: i32 = tips[index].iter().zip(&results)
+                .filter_map(|(tip, result)| tip.map(|(tip_home, tip_away)| rules.score_tip(result.0, result.1, tip_home, tip_away)))
+                .sum();
+            scores[index] = user_rating.score_sum + gained;
+            points[index] += gained as u64;
+        }
+
+        // The position is one more than the number of users with more points.
+        ranked.copy_from_slice(&scores);
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+        for index in 0..user_rating_list.len() {
+            let position = 1 + ranked.partition_point(|score| *score > scores[index]);
+            if position == 1 {
+                first[index] += 1;
+            }
+            if position <= 3 {
+                top_3[index] += 1;
+            }
+            if position <= 10 {
+                top_10[index] += 1;
+            }
+        }
+    }
+
+    let share = |count: u32| if iterations == 0 { 0.0 } else { count as f64 / iterations as f64 };
+
+    let mut users: Vec<UserForecast> = user_rating_list.iter().enumerate()
+        .map(|(index, user_rating)| UserForecast {
+            user_id: user_rating.user_id,
+            name: user_rating.name.clone(),
+            department: user_rating.department.clone(),
+            score_sum: user_rating.score_sum,
+            average_points: if iterations == 0 { 0.0 } else { points[index] as f64 / iterations as f64 },
+            first: share(first[index]),
+            top_3: share(top_3[index]),
+            top_10: share(top_10[index]),
+        })
+        .collect();
+    users.sort_by(|a, b| b.first.total_cmp(&a.first)
+        .then_with(|| b.top_3.total_cmp(&a.top_3))
+        .then_with(|| b.score_sum.cmp(&a.score_sum))
+        .then_with(|| a.user_id.cmp(&b.user_id)));
+
+    Ok(Forecast { seed, iterations, users })
+}
+
+// Knuth's algorithm, fast enough for the few goals of a football match.
+fn sample_poisson(rng: &mut StdRng, lambda: f64) -> i32 {
+    let limit = (-lambda).exp();
+    let mut goals = 0;
+    let mut product: f64 = rng.gen();
+
+    while product > limit {
+        goals += 1;
+        product *= rng.gen::<f64>();
+    }
+
+    goals
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::test_builder::{scheduled_game, tip, user_rating};
+    use super::*;
+
+    fn game(id: i32, home: &str, away: &str, home_score: i32, away_score: i32) -> Game {
+        Game {
+            id,
+            home_team: format!(r#"{{"name":"{}","tla":"{}"}}"#, home, home),
+            away_team: format!(r#"{{"name":"{}","tla":"{}"}}"#, away, away),
+            home_score,
+            away_score,
+            date: 0,
+            is_live: false,
+        }
+    }
+
+    fn rated(user_id: i32, score_sum: i32) -> UserRating {
+        UserRating { score_sum, ..user_rating(user_id) }
+    }
+
+    fn run(iterations: u32, seed: u64, now: u64) -> ForecastRun {
+        ForecastRun { iterations, seed, now }
+    }
+
+    #[test]
+    fn test_goal_model() {
+        let past_games = vec![game(1, "GER", "SCO", 5, 1), game(2, "HUN", "SUI", 1, 3)];
+
+        let model = GoalModel::new(&past_games, &[]).unwrap();
+        assert_eq!(model.goals_per_team, 2.5);
+        assert!(model.expected_goals("GER", "SCO") > model.expected_goals("HUN", "SCO"));
+        assert_eq!(model.expected_goals("ITA", "ALB"), 2.5);
+
+        let admin_strengths = vec![TeamStrength { tla: String::from("GER"), attack: 2.0, defence: 0.5 }];
+        let model = GoalModel::new(&past_games, &admin_strengths).unwrap();
+        assert_eq!(model.expected_goals("GER", "ITA"), 5.0);
+        assert_eq!(model.expected_goals("ITA", "GER"), 1.25);
+    }
+
+    #[test]
+    fn test_goal_model_without_results() {
+        let model = GoalModel::new(&[], &[]).unwrap();
+
+        assert_eq!(model.expected_goals("GER", "ESP"), DEFAULT_GOALS_PER_TEAM);
+    }
+
+    #[test]
+    fn test_get_forecast_is_deterministic() {
+        let user_rating_list = vec![rated(1, 10), rated(2, 9), rated(3, 0)];
+        let unfinished = vec![scheduled_game(3, "GER", "SCO"), scheduled_game(4, "ESP", "CRO")];
+        let tips_by_user = HashMap::from([
+            (1, vec![tip(1, 3, 2, 1)]),
+            (2, vec![tip(2, 3, 1, 0), tip(2, 4, 1, 1)]),
+        ]);
+        let model = GoalModel::new(&[], &[]).unwrap();
+
+        let forecast = get_forecast(&user_rating_list, &unfinished, &tips_by_user, &model, RuleSet::Em2024, run(2000, 42, 0)).unwrap();
+        let again = get_forecast(&user_rating_list, &unfinished, &tips_by_user, &model, RuleSet::Em2024, run(2000, 42, 0)).unwrap();
+
+        assert_eq!(forecast.seed, 42);
+        assert_eq!(forecast.iterations, 2000);
+        for (user, other) in forecast.users.iter().zip(&again.users) {
+            assert_eq!(user.user_id, other.user_id);
+            assert_eq!(user.first, other.first);
+            assert_eq!(user.average_points, other.average_points);
+        }
+
+        let user_1 = forecast.users.iter().find(|user| user.user_id == 1).unwrap();
+        let user_2 = forecast.users.iter().find(|user| user.user_id == 2).unwrap();
+        let user_3 = forecast.users.iter().find(|user| user.user_id == 3).unwrap();
+        assert!(user_1.first > 0.0 && user_1.first < 1.0);
+        assert!(user_2.first > 0.0 && user_2.first < 1.0);
+        assert_eq!(user_3.first, 0.0);
+        assert_eq!(user_3.top_3, 1.0);
+        assert_eq!(user_3.average_points, 0.0);
+        assert_eq!(forecast.users[0].user_id, 1);
+    }
+
+    #[test]
+    fn test_get_forecast_with_classic_rules() {
+        let user_rating_list = vec![rated(1, 0)];
+        let unfinished = vec![scheduled_game(3, "GER", "SCO")];
+        let tips_by_user = HashMap::from([(1, vec![tip(1, 3, 0, 0)])]);
+        // Without goals every match ends 0:0.
+        let model = GoalModel { goals_per_team: 0.0, strengths: HashMap::new() };
+
+        let em2024 = get_forecast(&user_rating_list, &unfinished, &tips_by_user, &model, RuleSet::Em2024, run(10, 1, 0)).unwrap();
+        let classic = get_forecast(&user_rating_list, &unfinished, &tips_by_user, &model, RuleSet::Classic2021, run(10, 1, 0)).unwrap();
+
+        assert_eq!(em2024.users[0].average_points, 4.0);
+        assert_eq!(classic.users[0].average_points, 3.0);
+    }
+
+    #[test]
+    fn test_get_forecast_continues_running_matches() {
+        let user_rating_list = vec![rated(1, 0), rated(2, 0)];
+        let mut running = scheduled_game(6, "NED", "ESP");
+        running.home_score = Some(1);
+        running.away_score = Some(0);
+        running.date = 1000;
+        let tips_by_user = HashMap::from([(1, vec![tip(1, 6, 1, 0)]), (2, vec![tip(2, 6, 0, 0)])]);
+        let model = GoalModel::new(&[], &[]).unwrap();
+
+        // At full time no more goals fall, so the current score is the result.
+        let forecast = get_forecast(&user_rating_list, &[running], &tips_by_user, &model, RuleSet::Em2024, run(100, 3, 1000 + MATCH_DURATION as u64)).unwrap();
+
+        assert_eq!(forecast.users[0].user_id, 1);
+        assert_eq!(forecast.users[0].first, 1.0);
+        assert_eq!(forecast.users[0].average_points, 4.0);
+        assert_eq!(forecast.users[1].first, 0.0);
+        assert_eq!(forecast.users[1].average_points, 0.0);
+    }
+
+    #[test]
+    fn test_get_forecast_without_unfinished_matches() {
+        let user_rating_list = vec![rated(1, 10), rated(2, 10), rated(3, 4)];
+
+        let forecast = get_forecast(&user_rating_list, &[], &HashMap::new(), &GoalModel::new(&[], &[]).unwrap(), RuleSet::Em2024, run(100, 1, 0)).unwrap();
+
+        assert_eq!(forecast.users[0].first, 1.0);
+        assert_eq!(forecast.users[1].first, 1.0);
+        assert_eq!(forecast.users[2].first, 0.0);
+        assert_eq!(forecast.users[2].top_3, 1.0);
+    }
+}
@@ -0,0 +1,343 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use crate::db::GroupGame;
+use crate::service::Team;
+
+pub const BEST_THIRD_PLACED_QUALIFIERS: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupStanding {
+    pub position: i32,
+    pub team: Team,
+    pub played: i32,
+    pub won: i32,
+    pub drawn: i32,
+    pub lost: i32,
+    pub goals_for: i32,
+    pub goals_against: i32,
+    pub goal_difference: i32,
+    pub points: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupTable {
+    pub group: String,
+    pub standings: Vec<GroupStanding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThirdPlacedStanding {
+    pub group: String,
+    pub qualified: bool,
+    pub standing: GroupStanding,
+}
+
+struct GroupResult {
+    home: String,
+    away: String,
+    home_score: i32,
+    away_score: i32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Record {
+    played: i32,
+    won: i32,
+    drawn: i32,
+    lost: i32,
+    goals_for: i32,
+    goals_against: i32,
+}
+
+impl Record {
+    fn add(&mut self, goals_for: i32, goals_against: i32) {
+        self.played += 1;
+        self.goals_for += goals_for;
+        self.goals_against += goals_against;
+
+        if goals_for > goals_against {
+            self.won += 1;
+        } else if goals_for == goals_against {
+            self.drawn += 1;
+        } else {
+            self.lost += 1;
+        }
+    }
+
+    fn points(&self) -> i32 {
+        self.won * 3 + self.drawn
+    }
+
+    fn goal_difference(&self) -> i32 {
+        self.goals_for - self.goals_against
+    }
+}
+
+pub fn get_group_tables(games: &[GroupGame]) -> Result<Vec<GroupTable>, Box<dyn std::error::Error>> {
+    let mut teams_by_group: BTreeMap<String, HashMap<String, Team>> = BTreeMap::new();
+    let mut results_by_group: HashMap<String, Vec<GroupResult>> = HashMap::new();
+
+    for game in games {
+        let home_team: Team = serde_json::from_str(&game.home_team)?;
+        let away_team: Team = serde_json::from_str(&game.away_team)?;
+
+        if let (Some(home_score), Some(away_score)) = (game.home_score, game.away_score) {
+            results_by_group.entry(game.group.clone()).or_default().push(GroupResult {
+                home: home_team.tla.clone(),
+                away: away_team.tla.clone(),
+                home_score,
+                away_score,
+            });
+        }
+
+        let teams = teams_by_group.entry(game.group.clone()).or_default();
+        teams.insert(home_team.tla.clone(), home_team);
+        teams.insert(away_team.tla.clone(), away_team);
+    }
+
+    let mut group_tables = Vec::new();
+    for (group, teams) in teams_by_group {
+        let results = results_by_group.remove(&group).unwrap_or_default();
+        let team_list: Vec<String> = teams.keys().cloned().collect();
+        let overall = tally(&team_list, &results);
+
+        let standings = rank_teams(team_list, &results, &overall)
+            .into_iter()
+            .enumerate()
+            .map(|(index, tla)| {
+                let record = &overall[&tla];
+                GroupStanding {
+                    position: index as i32 + 1,
+                    team: teams[&tla].clone(),
+                    played: record.played,
+                    won: record.won,
+                    drawn: record.drawn,
+                    lost: record.lost,
+                    goals_for: record.goals_for,
+                    goals_against: record.goals_against,
+                    goal_difference: record.goal_difference(),
+                    points: record.points(),
+                }
+            })
+            .collect();
+
+        group_tables.push(GroupTable { group, standings });
+    }
+
+    Ok(group_tables)
+}
+
+/// Ranks the third-placed teams across all groups: points, goal difference,
+/// goals scored, wins. Disciplinary points and the qualifying ranking are not
+/// stored, so the team name is the last resort.
+pub fn rank_third_placed(group_tables: &[GroupTable]) -> Vec<ThirdPlacedStanding> {
+    let mut third_placed: Vec<ThirdPlacedStanding> = group_tables.iter()
+        .filter_map(|table| table.standings.get(2).map(|standing| ThirdPlacedStanding {
+            group: table.group.clone(),
+            qualified: false,
+            standing: standing.clone(),
+        }))
+        .collect();
+
+    third_placed.sort_by(|a, b| {
+        let a = &a.standing;
+        let b = &b.standing;
+        (b.points, b.goal_difference, b.goals_for, b.won)
+            .cmp(&(a.points, a.goal_difference, a.goals_for, a.won))
+            .then_with(|| a.team.name.cmp(&b.team.name))
+    });
+
+    for (index, third) in third_placed.iter_mut().enumerate() {
+        third.standing.position = index as i32 + 1;
+        third.qualified = index < BEST_THIRD_PLACED_QUALIFIERS;
+    }
+
+    third_placed
+}
+
+fn tally(teams: &[String], results: &[GroupResult]) -> HashMap<String, Record> {
+    let mut records: HashMap<String, Record> = teams.iter()
+        .map(|tla| (tla.clone(), Record::default()))
+        .collect();
+
+    for result in results {
+        if !records.contains_key(&result.home) || !records.contains_key(&result.away) {
+            continue;
+        }
+
+        records.get_mut(&result.home).unwrap().add(result.home_score, result.away_score);
+        records.get_mut(&result.away).unwrap().add(result.away_score, result.home_score);
+    }
+
+    records
+}
+
+// UEFA EURO 2024 regulations, article 20: points first, then head-to-head
+// points, goal difference and goals scored among the tied teams. Those three
+// are reapplied to any subset that is still level before falling back to the
+// overall goal difference and goals scored.
+fn rank_teams(mut teams: Vec<String>, results: &[GroupResult], overall: &HashMap<String, Record>) -> Vec<String> {
+    teams.sort_by_key(|tla| Reverse(overall[tla].points()));
+
+    split_by(teams, |tla| overall[tla].points())
+        .into_iter()
+        .flat_map(|tied| if tied.len() > 1 { break_tie(tied, results, overall) } else { tied })
+        .collect()
+}
+
+fn break_tie(mut tied: Vec<String>, results: &[GroupResult], overall: &HashMap<String, Record>) -> Vec<String> {
+    let head_to_head = tally(&tied, results);
+    let key = |tla: &String| {
+        let record = &head_to_head[tla];
+        (record.points(), record.goal_difference(), record.goals_for)
+    };
+
+    tied.sort_by_key(|tla| Reverse(key(tla)));
+    let subsets = split_by(tied, key);
+
+    if subsets.len() == 1 {
+        let mut still_tied = subsets.into_iter().next().unwrap();
+        still_tied.sort_by(|a, b| {
+            let (a_record, b_record) = (&overall[a], &overall[b]);
+            (b_record.goal_difference(), b_record.goals_for)
+                .cmp(&(a_record.goal_difference(), a_record.goals_for))
+                .then_with(|| a.cmp(b))
+        });
+        return still_tied;
+    }
+
+    subsets.into_iter()
+        .flat_map(|subset| if subset.len() > 1 { break_tie(subset, results, overall) } else { subset })
+        .collect()
+}
+
+fn split_by<K: PartialEq>(sorted: Vec<String>, key: impl Fn(&String) -> K) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = Vec::new();
+
+    for tla in sorted {
+        match groups.last_mut() {
+            Some(group) if key(&group[0]) == key(&tla) => group.push(tla),
+            _ => groups.push(vec![tla]),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team_json(tla: &str) -> String {
+        serde_json::to_string(&Team { name: format!("Team {}", tla), tla: tla.to_string() }).unwrap()
+    }
+
+    fn game(id: i32, group: &str, home: &str, away: &str, score: Option<(i32, i32)>) -> GroupGame {
+        GroupGame {
+            id,
+            group: group.to_string(),
+            home_team: team_json(home),
+            away_team: team_json(away),
+            home_score: score.map(|score| score.0),
+            away_score: score.map(|score| score.1),
+        }
+    }
+
+    fn order(table: &GroupTable) -> Vec<&str> {
+        table.standings.iter().map(|standing| standing.team.tla.as_str()).collect()
+    }
+
+    #[test]
+    fn test_get_group_tables_counts_results() {
+        let games = vec![
+            game(1, "GROUP_A", "AAA", "BBB", Some((2, 0))),
+            game(2, "GROUP_A", "CCC", "DDD", Some((1, 1))),
+            game(3, "GROUP_A", "AAA", "CCC", None),
+        ];
+
+        let tables = get_group_tables(&games).unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(order(&tables[0]), vec!["AAA", "CCC", "DDD", "BBB"]);
+
+        let first = &tables[0].standings[0];
+        assert_eq!(first.position, 1);
+        assert_eq!(first.played, 1);
+        assert_eq!(first.won, 1);
+        assert_eq!(first.goals_for, 2);
+        assert_eq!(first.goal_difference, 2);
+        assert_eq!(first.points, 3);
+
+        let last = &tables[0].standings[3];
+        assert_eq!(last.lost, 1);
+        assert_eq!(last.points, 0);
+    }
+
+    #[test]
+    fn test_head_to_head_beats_goal_difference() {
+        // BBB has the better overall goal difference, but lost to AAA.
+        let games = vec![
+            game(1, "GROUP_A", "AAA", "BBB", Some((1, 0))),
+            game(2, "GROUP_A", "BBB", "CCC", Some((5, 0))),
+            game(3, "GROUP_A", "AAA", "DDD", Some((0, 1))),
+            game(4, "GROUP_A", "CCC", "DDD", Some((0, 0))),
+        ];
+
+        let tables = get_group_tables(&games).unwrap();
+        assert_eq!(order(&tables[0]), vec!["DDD", "AAA", "BBB", "CCC"]);
+    }
+
+    #[test]
+    fn test_three_way_tie_reapplies_head_to_head_to_remaining_teams() {
+        // AAA, BBB and CCC all finish on 6 points and level on head-to-head
+        // points and goal difference. CCC scored fewest goals among them; AAA
+        // and BBB are still level, so the criteria are reapplied to their game.
+        let games = vec![
+            game(1, "GROUP_A", "AAA", "BBB", Some((3, 1))),
+            game(2, "GROUP_A", "BBB", "CCC", Some((2, 0))),
+            game(3, "GROUP_A", "CCC", "AAA", Some((2, 0))),
+            game(4, "GROUP_A", "AAA", "DDD", Some((1, 0))),
+            game(5, "GROUP_A", "BBB", "DDD", Some((1, 0))),
+            game(6, "GROUP_A", "CCC", "DDD", Some((1, 0))),
+        ];
+
+        let tables = get_group_tables(&games).unwrap();
+        assert_eq!(order(&tables[0]), vec!["AAA", "BBB", "CCC", "DDD"]);
+        assert_eq!(tables[0].standings[1].points, 6);
+        assert_eq!(tables[0].standings[2].points, 6);
+    }
+
+    #[test]
+    fn test_unbroken_head_to_head_falls_back_to_overall_goals() {
+        let games = vec![
+            game(1, "GROUP_A", "AAA", "BBB", Some((1, 1))),
+            game(2, "GROUP_A", "AAA", "CCC", Some((1, 0))),
+            game(3, "GROUP_A", "BBB", "CCC", Some((3, 0))),
+        ];
+
+        let tables = get_group_tables(&games).unwrap();
+        assert_eq!(order(&tables[0]), vec!["BBB", "AAA", "CCC"]);
+    }
+
+    #[test]
+    fn test_rank_third_placed() {
+        let games = vec![
+            game(1, "GROUP_A", "AAA", "BBB", Some((2, 0))),
+            game(2, "GROUP_A", "BBB", "CCC", Some((1, 0))),
+            game(3, "GROUP_B", "DDD", "EEE", Some((1, 0))),
+            game(4, "GROUP_B", "EEE", "FFF", Some((1, 1))),
+        ];
+
+        let tables = get_group_tables(&games).unwrap();
+        let third_placed = rank_third_placed(&tables);
+
+        assert_eq!(third_placed.len(), 2);
+        assert_eq!(third_placed[0].group, "GROUP_B");
+        assert_eq!(third_placed[0].standing.team.tla, "EEE");
+        assert_eq!(third_placed[0].standing.position, 1);
+        assert!(third_placed[0].qualified);
+        assert_eq!(third_placed[1].group, "GROUP_A");
+        assert_eq!(third_placed[1].standing.team.tla, "CCC");
+        assert_eq!(third_placed[1].standing.position, 2);
+    }
+}
@@ -0,0 +1,185 @@
+use chrono::DateTime;
+use std::collections::BTreeMap;
+use crate::api::firebase;
+use crate::api::match_client::{self, Match, ScoreDetail};
+use crate::db::matches::{MatchRecord, TeamRecord};
+use crate::db::tournaments::TipRecord;
+use crate::service::Team;
+
+/// Maps football-data.org matches onto our tables. Teams that are not decided
+/// yet are stored with an empty name and code.
+pub fn to_records(matches: &[Match]) -> Result<(Vec<TeamRecord>, Vec<MatchRecord>), chrono::ParseError> {
+    let mut teams: BTreeMap<i32, TeamRecord> = BTreeMap::new();
+    let mut match_records = Vec::new();
+
+    for game in matches {
+        for team in [&game.homeTeam, &game.awayTeam] {
+            if let (Some(id), Some(name), Some(tla)) = (team.id, &team.name, &team.tla) {
+                teams.insert(id, TeamRecord {
+                    id,
+                    name: name.clone(),
+                    short_name: team.shortName.clone(),
+                    tla: tla.clone(),
+                    crest: team.crest.clone(),
+                });
+            }
+        }
+
+        let (home_score, away_score) = regular_score(&game.score);
+        let extra_time = game.score.extraTime.clone().unwrap_or_default();
+        let penalties = game.score.penalties.clone().unwrap_or_default();
+
+        match_records.push(MatchRecord {
+            id: game.id,
+            home_team: team_json(&game.homeTeam),
+            away_team: team_json(&game.awayTeam),
+            status: game.status.clone(),
+            stage: game.stage.clone(),
+            group: game.group.clone(),
+            venue: game.venue.clone(),
+            utc_date: DateTime::parse_from_rfc3339(&game.utcDate)?.timestamp() as u64,
+            home_score,
+            away_score,
+            duration: game.score.duration.clone(),
+            home_score_extra_time: extra_time.home,
+            away_score_extra_time: extra_time.away,
+            home_penalties: penalties.home,
+            away_penalties: penalties.away,
+        });
+    }
+
+    Ok((teams.into_values().collect(), match_records))
+}
+
+/// Maps the tips of a Firebase export onto tips by username.
+pub fn to_tip_records(tips: &[firebase::Tip]) -> Vec<TipRecord> {
+    tips.iter()
+        .map(|tip| TipRecord {
+            username: tip.user.trim().to_string(),
+            match_id: tip.id,
+            score_home: tip.score1,
+            score_away: tip.score2,
+        })
+        .collect()
+}
+
+// Tips are scored on the result after extra time, so the goals of a penalty
+// shoot-out are taken out of the full-time score again.
+fn regular_score(score: &match_client::Score) -> (Option<i32>, Option<i32>) {
+    let ScoreDetail { home, away } = score.fullTime;
+    let penalties = score.penalties.clone().unwrap_or_default();
+
+    match (home, away) {
+        (Some(home), Some(away)) => (Some(home - penalties.home.unwrap_or(0)), Some(away - penalties.away.unwrap_or(0))),
+        _ => (None, None),
+    }
+}
+
+fn team_json(team: &match_client::Team) -> String {
+    serde_json::to_string(&Team {
+        name: team.name.clone().unwrap_or_default(),
+        tla: team.tla.clone().unwrap_or_default(),
+    }).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team(id: i32, name: &str, tla: &str) -> match_client::Team {
+        match_client::Team {
+            id: Some(id),
+            name: Some(name.to_string()),
+            shortName: None,
+            tla: Some(tla.to_string()),
+            crest: None,
+        }
+    }
+
+    fn score(home: i32, away: i32) -> ScoreDetail {
+        ScoreDetail { home: Some(home), away: Some(away) }
+    }
+
+    fn game(id: i32, status: &str, score: match_client::Score) -> Match {
+        Match {
+            id,
+            utcDate: String::from("2024-06-30T19:00:00Z"),
+            status: status.to_string(),
+            stage: Some(String::from("LAST_16")),
+            group: None,
+            venue: Some(String::from("BVB Stadion Dortmund")),
+            homeTeam: team(759, "Germany", "GER"),
+            awayTeam: team(782, "Denmark", "DEN"),
+            score,
+        }
+    }
+
+    #[test]
+    fn test_to_records() {
+        let matches = vec![
+            game(1, "FINISHED", match_client::Score { duration: Some(String::from("REGULAR")), fullTime: score(2, 0), extraTime: None, penalties: None }),
+            game(2, "TIMED", match_client::Score { duration: None, fullTime: ScoreDetail::default(), extraTime: None, penalties: None }),
+        ];
+
+        let (teams, match_records) = to_records(&matches).unwrap();
+
+        assert_eq!(teams.len(), 2);
+        assert_eq!(teams[0].tla, "GER");
+        assert_eq!(match_records[0].utc_date, 1719774000);
+        assert_eq!(match_records[0].home_score, Some(2));
+        assert_eq!(match_records[0].home_team, r#"{"name":"Germany","tla":"GER"}"#);
+        assert_eq!(match_records[0].venue.as_deref(), Some("BVB Stadion Dortmund"));
+        assert_eq!(match_records[1].status, "TIMED");
+        assert_eq!(match_records[1].home_score, None);
+    }
+
+    #[test]
+    fn test_to_records_takes_penalties_out_of_the_score() {
+        let matches = vec![game(1, "FINISHED", match_client::Score {
+            duration: Some(String::from("PENALTY_SHOOTOUT")),
+            fullTime: score(6, 5),
+            extraTime: Some(score(0, 0)),
+            penalties: Some(score(5, 4)),
+        })];
+
+        let (_, match_records) = to_records(&matches).unwrap();
+
+        assert_eq!(match_records[0].home_score, Some(1));
+        assert_eq!(match_records[0].away_score, Some(1));
+        assert_eq!(match_records[0].home_score_extra_time, Some(0));
+        assert_eq!(match_records[0].home_penalties, Some(5));
+        assert_eq!(match_records[0].away_penalties, Some(4));
+        assert_eq!(match_records[0].duration.as_deref(), Some("PENALTY_SHOOTOUT"));
+    }
+
+    #[test]
+    fn test_to_records_with_undecided_teams() {
+        let mut final_match = game(3, "TIMED", match_client::Score { duration: None, fullTime: ScoreDetail::default(), extraTime: None, penalties: None });
+        final_match.homeTeam = match_client::Team { id: None, name: None, shortName: None, tla: None, crest: None };
+
+        let (teams, match_records) = to_records(&[final_match]).unwrap();
+
+        assert_eq!(teams.len(), 1);
+        assert_eq!(match_records[0].home_team, r#"{"name":"","tla":""}"#);
+    }
+
+    #[test]
+    fn test_to_tip_records() {
+        let tips = vec![firebase::Tip { user: String::from(" ToniKroos "), id: 285418, score1: 2, score2: 1 }];
+
+        assert_eq!(to_tip_records(&tips), vec![TipRecord {
+            username: String::from("ToniKroos"),
+            match_id: 285418,
+            score_home: 2,
+            score_away: 1,
+        }]);
+    }
+
+    #[test]
+    fn test_to_records_rejects_invalid_dates() {
+        let mut invalid = game(1, "TIMED", match_client::Score { duration: None, fullTime: ScoreDetail::default(), extraTime: None, penalties: None });
+        invalid.utcDate = String::from("30.06.2024");
+
+        assert!(to_records(&[invalid]).is_err());
+    }
+}
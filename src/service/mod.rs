@@ -1,9 +1,26 @@
-
+pub mod all_time;
+pub mod calendar;
+pub mod changes;
+pub mod compare;
+pub mod crowd;
+pub mod departments;
+pub mod elimination;
+pub mod export;
+pub mod forecast;
+pub mod groups;
+pub mod import;
+pub mod picks;
+pub mod profiles;
+pub mod ranking;
+pub mod simulation;
+pub mod stats;
+pub mod tables;
 
 use serde::{Deserialize, Serialize};
 use crate::db::{Game, get_tips_by_user, Tip, User};
+use crate::service::ranking::RuleSet;
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use serde_json;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Team {
@@ -22,6 +39,8 @@ pub struct UserRating {
     pub sum_score_diff: i32,
     pub sum_team: i32,
     pub extra_point: i32,
+    /// Set for the crowd tip pseudo-user, which is not a real participant.
+    pub is_crowd: bool,
     pub tips: Vec<MatchInfo>,
 }
 
@@ -38,6 +57,8 @@ pub struct MatchInfo {
     pub score_home: Option<i32>,
     pub score_away: Option<i32>,
     pub date: u64,
+    /// The match is still running, so `score` is provisional.
+    pub provisional: bool,
 }
 
 struct ScoreConfig;
@@ -47,91 +68,111 @@ impl ScoreConfig {
     pub const WIN_EXACT: i32 = 4;
     pub const WIN_SCORE_DIFF: i32 = 2;
     pub const WIN_TEAM: i32 = 1;
+    pub const WINNER: i32 = 15;
+    pub const SECRET_WINNER: i32 = 7;
 }
 
-pub fn get_user_rating(games: Vec<Game>, users: Vec<User>) -> Result<Vec<UserRating>, Box<dyn std::error::Error>> {
+/// Rates the tips of the users on the games under the rules of a tournament.
+/// Champion picks count once the `champion` (a team code) is known.
+pub fn get_user_rating(games: Vec<Game>, users: Vec<User>, rules: RuleSet, champion: Option<&str>) -> Result<Vec<UserRating>, Box<dyn std::error::Error>> {
     let mut user_rating_list = Vec::new();
 
     for user in &users {
+        user_rating_list.push(rate_user(user, get_tips_by_user(user.id)?, &games, rules, champion));
+    }
 
-        let mut extra_point = ScoreConfig::NO_WIN_TEAM;
-        if user.winner == "ESP"
-        {
-            extra_point = 15;
-        }
+    Ok(user_rating_list)
+}
 
-        if user.secret_winner == "ESP"
-        {
-            extra_point = 7;
-        }
+fn champion_points(user: &User, champion: &str) -> i32 {
+    let mut extra_point = ScoreConfig::NO_WIN_TEAM;
+    if user.winner == champion
+    {
+        extra_point = ScoreConfig::WINNER;
+    }
 
-        let mut user_rating = UserRating {
-            name: user.username.clone(),
-            user_id: user.id.clone(),
-            department: user.department.clone(),
-            position: 0,
-            score_sum: extra_point,
-            sum_win_exact: 0,
-            sum_score_diff: 0,
-            sum_team: 0,
-            extra_point,
-            tips: Vec::new(),
-        };
-        let tips_by_user: HashMap<i32, Tip> = get_tips_by_user(user.id)?
-            .into_iter()
-            .map(|tip| (tip.match_id, tip))
-            .collect();
+    if user.secret_winner == champion
+    {
+        extra_point = ScoreConfig::SECRET_WINNER;
+    }
 
-        for game in &games {
-            let mut match_info = MatchInfo {
-                match_id: game.id.to_string(),
-                user: user.username.clone(),
-                user_id: user.id.clone(),
-                score: 0,
-                team1: serde_json::from_str(&game.home_team).unwrap(),
-                team2: serde_json::from_str(&game.away_team).unwrap(),
-                tip_home: None,
-                tip_away: None,
-                score_home: Some(game.home_score),
-                score_away: Some(game.away_score),
-                date: game.date.clone(),
-            };
+    extra_point
+}
+
+fn rate_user(user: &User, tips: Vec<Tip>, games: &[Game], rules: RuleSet, champion: Option<&str>) -> UserRating {
+    let extra_point = rules.extra_points(user, champion);
+    let category_points = rules.category_points();
+
+    let mut user_rating = UserRating {
+        name: user.username.clone(),
+        user_id: user.id,
+        department: user.department.clone(),
+        position: 0,
+        score_sum: extra_point,
+        sum_win_exact: 0,
+        sum_score_diff: 0,
+        sum_team: 0,
+        extra_point,
+        is_crowd: false,
+        tips: Vec::new(),
+    };
+    let tips_by_user: HashMap<i32, Tip> = tips
+        .into_iter()
+        .map(|tip| (tip.match_id, tip))
+        .collect();
+
+    for game in games {
+        let mut match_info = MatchInfo {
+            match_id: game.id.to_string(),
+            user: user.username.clone(),
+            user_id: user.id,
+            score: 0,
+            team1: serde_json::from_str(&game.home_team).unwrap(),
+            team2: serde_json::from_str(&game.away_team).unwrap(),
+            tip_home: None,
+            tip_away: None,
+            score_home: Some(game.home_score),
+            score_away: Some(game.away_score),
+            date: game.date,
+            provisional: game.is_live,
+        };
 
-            if let Some(tip) = tips_by_user.get(&game.id) {
-                match_info.tip_home = Some(tip.score_home);
-                match_info.tip_away = Some(tip.score_away);
+        if let Some(tip) = tips_by_user.get(&game.id) {
+            match_info.tip_home = Some(tip.score_home);
+            match_info.tip_away = Some(tip.score_away);
 
-                calculate_score(&mut match_info);
+            calculate_score(&mut match_info, rules);
 
-                user_rating.score_sum += match_info.score;
-                if match_info.score == ScoreConfig::WIN_EXACT {
-                    user_rating.sum_win_exact += 1;
-                } else if match_info.score == ScoreConfig::WIN_SCORE_DIFF {
-                    user_rating.sum_score_diff += 1;
-                } else if match_info.score == ScoreConfig::WIN_TEAM {
-                    user_rating.sum_team += 1;
-                }
+            user_rating.score_sum += match_info.score;
+            if match_info.score == category_points.win_exact {
+                user_rating.sum_win_exact += 1;
+            } else if match_info.score == category_points.win_score_diff {
+                user_rating.sum_score_diff += 1;
+            } else if match_info.score == category_points.win_team {
+                user_rating.sum_team += 1;
             }
-
-            user_rating.tips.push(match_info);
         }
-        user_rating_list.push(user_rating);
+
+        user_rating.tips.push(match_info);
     }
 
-    Ok(user_rating_list)
+    user_rating
 }
 
-pub fn calculate_positions(user_rating_list: &mut Vec<UserRating>, clear_tips: bool) {
-    user_rating_list.sort_by(|a, b| b.score_sum.cmp(&a.score_sum));
+/// Users with the same points share a position. After a tie the next position
+/// skips the shared places (1, 2, 2, 4), under the classic rules it does not (1, 2, 2, 3).
+pub fn calculate_positions(user_rating_list: &mut [UserRating], rules: RuleSet, clear_tips: bool) {
+    user_rating_list.sort_by_key(|user_rating| Reverse(user_rating.score_sum));
 
-    let mut position = 0;
     let mut last_point = -1;
     let mut position_for_frontend = 0;
 
-    for user_rating in user_rating_list {
-        position += 1;
+    for (index, user_rating) in user_rating_list.iter_mut().enumerate() {
         if user_rating.score_sum != last_point {
-            position_for_frontend = position;
+            position_for_frontend = match rules {
+                RuleSet::Em2024 => index as i32 + 1,
+                RuleSet::Classic2021 => position_for_frontend + 1,
+            };
         }
 
         user_rating.position = position_for_frontend;
@@ -144,27 +185,96 @@ pub fn calculate_positions(user_rating_list: &mut Vec<UserRating>, clear_tips: b
     }
 }
 
-fn calculate_score(match_info: &mut MatchInfo) {
+fn calculate_score(match_info: &mut MatchInfo, rules: RuleSet) {
     if let (Some(score_home), Some(score_away), Some(tip_home), Some(tip_away)) =
         (match_info.score_home, match_info.score_away, match_info.tip_home, match_info.tip_away) {
-        if (score_home > score_away && tip_home > tip_away) || (score_home < score_away && tip_home < tip_away) {
-            match_info.score = ScoreConfig::WIN_TEAM;
+        match_info.score = rules.score_tip(score_home, score_away, tip_home, tip_away);
+    }
+}
+
+fn score_tip(score_home: i32, score_away: i32, tip_home: i32, tip_away: i32) -> i32 {
+    if score_home == tip_home && score_away == tip_away {
+        return ScoreConfig::WIN_EXACT;
+    }
+
+    if score_home - score_away == tip_home - tip_away {
+        if score_home == score_away {
+            return ScoreConfig::WIN_TEAM;
         }
+        return ScoreConfig::WIN_SCORE_DIFF;
+    }
 
-        if score_home - score_away == tip_home - tip_away {
-            if score_home == score_away {
-                match_info.score = ScoreConfig::WIN_TEAM;
-            } else {
-                match_info.score = ScoreConfig::WIN_SCORE_DIFF;
-            }
+    if (score_home > score_away && tip_home > tip_away) || (score_home < score_away && tip_home < tip_away) {
+        return ScoreConfig::WIN_TEAM;
+    }
+
+    ScoreConfig::NO_WIN_TEAM
+}
+
+
+/// Builders for the tests of the service modules. Fields a test does not care
+/// about are zero or empty; set the others with struct update syntax.
+#[cfg(test)]
+pub(crate) mod test_builder {
+    use crate::db::{ScheduledGame, Tip};
+    use super::{MatchInfo, Team, UserRating};
+
+    pub fn team(tla: &str) -> Team {
+        Team { name: tla.to_string(), tla: tla.to_string() }
+    }
+
+    /// A user of Langenfeld without points or tips.
+    pub fn user_rating(user_id: i32) -> UserRating {
+        UserRating {
+            name: format!("user{}", user_id),
+            user_id,
+            department: String::from("Langenfeld"),
+            position: 0,
+            score_sum: 0,
+            sum_win_exact: 0,
+            sum_score_diff: 0,
+            sum_team: 0,
+            extra_point: 0,
+            is_crowd: false,
+            tips: Vec::new(),
         }
+    }
 
-        if score_home == tip_home && score_away == tip_away {
-            match_info.score = ScoreConfig::WIN_EXACT;
+    /// A 1:0 tip of the user on a GER - ESP match that ended 1:0, without points.
+    pub fn match_info(match_id: i32, user_id: i32) -> MatchInfo {
+        MatchInfo {
+            match_id: match_id.to_string(),
+            user: format!("user{}", user_id),
+            user_id,
+            score: 0,
+            team1: team("GER"),
+            team2: team("ESP"),
+            tip_home: Some(1),
+            tip_away: Some(0),
+            score_home: Some(1),
+            score_away: Some(0),
+            date: 0,
+            provisional: false,
         }
     }
-}
 
+    /// A match without a result.
+    pub fn scheduled_game(id: i32, home: &str, away: &str) -> ScheduledGame {
+        ScheduledGame {
+            id,
+            home_team: format!(r#"{{"name":"{}","tla":"{}"}}"#, home, home),
+            away_team: format!(r#"{{"name":"{}","tla":"{}"}}"#, away, away),
+            home_score: None,
+            away_score: None,
+            date: 0,
+            status: String::from("TIMED"),
+        }
+    }
+
+    pub fn tip(user_id: i32, match_id: i32, score_home: i32, score_away: i32) -> Tip {
+        Tip { id: 1, user_id, match_id, score_home, score_away }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -172,8 +282,7 @@ mod tests {
 
     use rstest::rstest;
 
-    #[cfg(test)]
-    mod tests {
+    mod positions {
         use super::*;
 
         #[test]
@@ -189,6 +298,7 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
                 UserRating {
@@ -201,6 +311,7 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
                 UserRating {
@@ -213,6 +324,7 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
                 UserRating {
@@ -225,6 +337,7 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
                 UserRating {
@@ -237,6 +350,7 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
                 UserRating {
@@ -249,6 +363,7 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
                 UserRating {
@@ -261,11 +376,12 @@ mod tests {
                     sum_score_diff: 0,
                     sum_team: 0,
                     extra_point: 0,
+                    is_crowd: false,
                     tips: Vec::new(),
                 },
             ];
 
-            calculate_positions(&mut user_rating_list, true);
+            calculate_positions(&mut user_rating_list, RuleSet::Em2024, true);
 
             assert_eq!(user_rating_list[0].position, 1);
             assert_eq!(user_rating_list[0].name, "babo");
@@ -297,6 +413,7 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
             UserRating {
@@ -309,6 +426,7 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
             UserRating {
@@ -321,6 +439,7 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
             UserRating {
@@ -333,6 +452,7 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
             UserRating {
@@ -345,6 +465,7 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
             UserRating {
@@ -357,6 +478,7 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
             UserRating {
@@ -369,11 +491,12 @@ mod tests {
                 sum_score_diff: 0,
                 sum_team: 0,
                 extra_point: 0,
+                is_crowd: false,
                 tips: Vec::new(),
             },
         ];
 
-        calculate_positions(&mut user_rating_list, true);
+        calculate_positions(&mut user_rating_list, RuleSet::Em2024, true);
 
         assert_eq!(user_rating_list[0].position, 1);
         assert_eq!(user_rating_list[0].name, "ninja");
@@ -391,6 +514,20 @@ mod tests {
         assert_eq!(user_rating_list[6].name, "theBest");
     }
 
+    #[rstest]
+    #[case(RuleSet::Em2024, [1, 2, 2, 4])]
+    #[case(RuleSet::Classic2021, [1, 2, 2, 3])]
+    fn test_calculate_positions_after_tie(#[case] rules: RuleSet, #[case] expected: [i32; 4]) {
+        let mut user_rating_list: Vec<UserRating> = [(1, 12), (2, 9), (3, 9), (4, 5)].into_iter()
+            .map(|(user_id, score_sum)| UserRating { score_sum, ..test_builder::user_rating(user_id) })
+            .collect();
+
+        calculate_positions(&mut user_rating_list, rules, true);
+
+        let positions: Vec<i32> = user_rating_list.iter().map(|user_rating| user_rating.position).collect();
+        assert_eq!(positions, expected);
+    }
+
     #[rstest]
     #[case(1, 2, 1, 2, ScoreConfig::WIN_EXACT)]
     #[case(2, 1, 2, 1, ScoreConfig::WIN_EXACT)]
@@ -425,9 +562,10 @@ mod tests {
             score_home: Some(score_home),
             score_away: Some(score_away),
             date: 1718048296,
+            provisional: false,
         };
 
-        calculate_score(&mut match_info);
+        calculate_score(&mut match_info, RuleSet::Em2024);
 
         assert_eq!(match_info.score, expected, "Error: score_home: {}, score_away: {}, tip_home: {}, tip_away: {}", score_home, score_away, tip_home, tip_away);
     }
@@ -452,9 +590,10 @@ mod tests {
             score_home,
             score_away,
             date: 1718048296,
+            provisional: false,
         };
 
-        calculate_score(&mut match_info);
+        calculate_score(&mut match_info, RuleSet::Em2024);
 
         assert_eq!(match_info.score, expected);
     }
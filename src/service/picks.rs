@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::db::User;
+use crate::service::Team;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickCount {
+    pub team: String,
+    pub count: i32,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickDistribution {
+    pub winner: Vec<PickCount>,
+    pub secret_winner: Vec<PickCount>,
+}
+
+/// All teams that play in the tournament, sorted by name. Knockout matches
+/// whose teams are not decided yet have teams without a code, which are skipped.
+pub fn get_participating_teams(team_json_list: &[String]) -> Result<Vec<Team>, serde_json::Error> {
+    let mut teams: HashMap<String, Team> = HashMap::new();
+
+    for team_json in team_json_list {
+        let team: Team = serde_json::from_str(team_json)?;
+        if !team.tla.is_empty() {
+            teams.insert(team.tla.clone(), team);
+        }
+    }
+
+    let mut team_list: Vec<Team> = teams.into_values().collect();
+    team_list.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(team_list)
+}
+
+/// A pick is either empty (not picked yet) or the code of a participating team.
+pub fn validate_pick(pick: &str, teams: &[Team]) -> Result<(), &'static str> {
+    if pick.is_empty() || teams.iter().any(|team| team.tla == pick) {
+        return Ok(());
+    }
+
+    Err("Champion picks must be a participating team")
+}
+
+pub fn get_pick_distribution(users: &[User]) -> PickDistribution {
+    PickDistribution {
+        winner: count_picks(users.iter().map(|user| user.winner.as_str())),
+        secret_winner: count_picks(users.iter().map(|user| user.secret_winner.as_str())),
+    }
+}
+
+fn count_picks<'a>(picks: impl Iterator<Item = &'a str>) -> Vec<PickCount> {
+    let mut counts: HashMap<&str, i32> = HashMap::new();
+    let mut total = 0;
+
+    for pick in picks.filter(|pick| !pick.is_empty()) {
+        *counts.entry(pick).or_default() += 1;
+        total += 1;
+    }
+
+    let mut pick_counts: Vec<PickCount> = counts.into_iter()
+        .map(|(team, count)| PickCount {
+            team: team.to_string(),
+            count,
+            percentage: count as f64 * 100.0 / total as f64,
+        })
+        .collect();
+    pick_counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.team.cmp(&b.team)));
+
+    pick_counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(winner: &str, secret_winner: &str) -> User {
+        User {
+            id: 1,
+            username: String::from("user"),
+            department: String::from("test"),
+            winner: winner.to_string(),
+            secret_winner: secret_winner.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_get_participating_teams() {
+        let team_json_list = vec![
+            String::from(r#"{"name":"Spain","tla":"ESP"}"#),
+            String::from(r#"{"name":"Germany","tla":"GER"}"#),
+            String::from(r#"{"name":"Spain","tla":"ESP"}"#),
+            String::from(r#"{"name":"","tla":""}"#),
+        ];
+
+        let teams = get_participating_teams(&team_json_list).unwrap();
+
+        assert_eq!(teams.len(), 2);
+        assert_eq!(teams[0].tla, "GER");
+        assert_eq!(teams[1].tla, "ESP");
+    }
+
+    #[test]
+    fn test_validate_pick() {
+        let teams = vec![Team { name: String::from("Spain"), tla: String::from("ESP") }];
+
+        assert!(validate_pick("ESP", &teams).is_ok());
+        assert!(validate_pick("", &teams).is_ok());
+        assert!(validate_pick("GER", &teams).is_err());
+        assert!(validate_pick("esp", &teams).is_err());
+    }
+
+    #[test]
+    fn test_get_pick_distribution() {
+        let users = vec![user("ESP", "GER"), user("GER", ""), user("ESP", "ENG"), user("", "")];
+
+        let distribution = get_pick_distribution(&users);
+
+        assert_eq!(distribution.winner.len(), 2);
+        assert_eq!(distribution.winner[0].team, "ESP");
+        assert_eq!(distribution.winner[0].count, 2);
+        assert!((distribution.winner[0].percentage - 66.666).abs() < 0.01);
+        assert_eq!(distribution.winner[1].team, "GER");
+
+        assert_eq!(distribution.secret_winner.len(), 2);
+        assert_eq!(distribution.secret_winner[0].team, "ENG");
+        assert_eq!(distribution.secret_winner[0].percentage, 50.0);
+    }
+}
@@ -0,0 +1,107 @@
+use rusqlite::Result as SqliteResult;
+use std::env;
+use crate::db;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Departments users may register with: `ALLOWED_DEPARTMENTS` (comma separated)
+/// if set, otherwise the departments that already exist.
+pub fn allowed_departments() -> SqliteResult<Vec<String>> {
+    match env::var("ALLOWED_DEPARTMENTS") {
+        Ok(departments) => Ok(departments.split(',')
+            .map(|department| department.trim().to_string())
+            .filter(|department| !department.is_empty())
+            .collect()),
+        Err(_) => db::profiles::get_departments(),
+    }
+}
+
+/// Champion picks can be changed until the first match kicks off.
+pub fn picks_locked(first_kickoff: Option<u64>, now: u64) -> bool {
+    first_kickoff.is_some_and(|first_kickoff| now >= first_kickoff)
+}
+
+pub fn validate_email(email: &str) -> Result<(), &'static str> {
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace) => Ok(()),
+        _ => Err("Invalid email address"),
+    }
+}
+
+pub fn validate_username(username: &str) -> Result<(), &'static str> {
+    let length = username.chars().count();
+    if !(3..=32).contains(&length) {
+        return Err("Username must have between 3 and 32 characters");
+    }
+
+    if !username.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.') {
+        return Err("Username may only contain letters, digits, '_', '-' and '.'");
+    }
+
+    Ok(())
+}
+
+pub fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err("First and last name must have between 1 and 64 characters");
+    }
+
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), &'static str> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err("Password must have at least 8 characters");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_picks_locked() {
+        assert!(!picks_locked(None, 1000));
+        assert!(!picks_locked(Some(1001), 1000));
+        assert!(picks_locked(Some(1000), 1000));
+        assert!(picks_locked(Some(999), 1000));
+    }
+
+    #[rstest]
+    #[case("toni@kroos.de", true)]
+    #[case("a@b.co", true)]
+    #[case("toni", false)]
+    #[case("@kroos.de", false)]
+    #[case("toni@kroos", false)]
+    #[case("toni kroos@kroos.de", false)]
+    fn test_validate_email(#[case] email: &str, #[case] valid: bool) {
+        assert_eq!(validate_email(email).is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case("ToniKroos", true)]
+    #[case("müller_13", true)]
+    #[case("to", false)]
+    #[case("Toni Kroos", false)]
+    #[case("abcdefghijklmnopqrstuvwxyz1234567", false)]
+    fn test_validate_username(#[case] username: &str, #[case] valid: bool) {
+        assert_eq!(validate_username(username).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_validate_password() {
+        assert!(validate_password("12345678").is_ok());
+        assert!(validate_password("1234567").is_err());
+    }
+
+    #[test]
+    fn test_allowed_departments_falls_back_to_existing_departments() {
+        env::set_var("MODE", "test");
+
+        assert_eq!(allowed_departments().unwrap(), vec!["Langenfeld", "London"]);
+    }
+}
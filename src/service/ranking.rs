@@ -1,141 +1,175 @@
-use chrono::{DateTime, FixedOffset, Utc};
-use serde_derive::{Deserialize, Serialize};
-use crate::api::match_client::Match;
-use crate::service::firebase_connector::Tip;
-
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct UserRanking {
-    pub user: Vec<UserRankingUser>,
+use serde::{Deserialize, Serialize};
+use crate::db::User;
+use crate::service::{champion_points, score_tip, ScoreConfig};
+
+/// The scoring rules tips are rated with. Old tournaments are recomputed
+/// under the rules they were played with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSet {
+    /// 4 points for the exact score, 2 for the goal difference, 1 for the
+    /// winner or a draw, plus a bonus for the champion picks.
+    #[default]
+    Em2024,
+    /// The rules of the EM2021 pool: 3 points for the exact score, 2 for the
+    /// goal difference (a draw with another score included), 1 for the winner.
+    /// There were no champion picks.
+    Classic2021,
 }
 
-#[allow(non_snake_case)]
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct UserRankingUser {
-    pub name: String,
-    pub position: isize,
-    pub scoreSum: isize,
-    pub tips: Vec<UserTip>
+struct ClassicScoreConfig;
+
+impl ClassicScoreConfig {
+    pub const NO_WIN_TEAM: i32 = 0;
+    pub const WIN_EXACT: i32 = 3;
+    pub const WIN_SCORE_DIFF: i32 = 2;
+    pub const WIN_TEAM: i32 = 1;
 }
 
-#[allow(non_snake_case)]
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct UserTip {
-    pub matchId: isize,
-    pub score: isize,
-    pub tipTeam1: isize,
-    pub tipTeam2: isize,
+/// Points of a tip in each category: exact score, goal difference, winner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CategoryPoints {
+    pub win_exact: i32,
+    pub win_score_diff: i32,
+    pub win_team: i32,
 }
 
-pub struct Ranking {}
-
-impl Ranking {
-    pub async fn get_user_ranking(matches: Vec<Match>, tips: Vec<Tip>) -> UserRanking {
-        let mut user_ranking = UserRanking{ user: vec![] };
-
-        for tip in tips {
-            let index_option = user_ranking.user.iter().position(|r| r.name == tip.user);
-
-            let mut user_ranking_user = UserRankingUser {
-                name: tip.user,
-                position: 0,
-                scoreSum: 0,
-                tips: vec![],
-            };
-
-            let mut already_exists = false;
-            if index_option.is_some() {
-                already_exists = true;
-            }
-
-            let found_match_option = matches.iter().find(|s| s.id == tip.id);
-
-            if found_match_option.is_some() {
-                let found_match = found_match_option.unwrap();
-                let match_start_date_time = found_match.utcDate.parse::<DateTime<FixedOffset>>().unwrap();
-                let now_date_time = Utc::now().with_timezone(&FixedOffset::west_opt(0).unwrap());
-
-                if match_start_date_time.timestamp() < now_date_time.timestamp() {
-                    let home_score = found_match.score.fullTime.home;
-                    let away_score = found_match.score.fullTime.away;
-
-                    let mut match_score: isize = 0;
-                    if home_score.unwrap() == tip.score1 && away_score.unwrap() == tip.score2 {
-                        // exactly right
-                        match_score = 3;
-                    } else if (home_score.unwrap() - tip.score1) == (away_score.unwrap() - tip.score2) {
-                        // correct difference
-                        match_score = 2;
-                    } else if (home_score > away_score && tip.score1 > tip.score2) || (home_score < away_score && tip.score1 < tip.score2) {
-                        // correct winner
-                        match_score = 1;
-                    }
-
-                    if already_exists {
-                        let index = index_option.unwrap();
-                        user_ranking.user[index].tips.push(
-                            UserTip {
-                                matchId: tip.id,
-                                score: match_score,
-                                tipTeam1: tip.score1,
-                                tipTeam2: tip.score2,
-                            }
-                        );
-                    } else {
-                        user_ranking_user.tips.push(
-                            UserTip {
-                                matchId: tip.id,
-                                score: match_score,
-                                tipTeam1: tip.score1,
-                                tipTeam2: tip.score2,
-                            }
-                        );
-
-                        user_ranking.user.push(user_ranking_user);
-                    }
-                }
-            }
+impl RuleSet {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RuleSet::Em2024 => "em2024",
+            RuleSet::Classic2021 => "classic2021",
         }
+    }
 
-        set_sum_score(&mut user_ranking.user);
-        sort_user(&mut user_ranking);
-        add_position(&mut user_ranking.user);
+    pub fn from_db(rule_set: &str) -> RuleSet {
+        match rule_set {
+            "classic2021" => RuleSet::Classic2021,
+            _ => RuleSet::Em2024,
+        }
+    }
 
-        user_ranking
+    pub fn score_tip(self, score_home: i32, score_away: i32, tip_home: i32, tip_away: i32) -> i32 {
+        match self {
+            RuleSet::Em2024 => score_tip(score_home, score_away, tip_home, tip_away),
+            RuleSet::Classic2021 => score_tip_classic(score_home, score_away, tip_home, tip_away),
+        }
     }
-}
 
-fn set_sum_score(user_ranking_user: &mut Vec<UserRankingUser>) {
-    for u in user_ranking_user {
-        let mut sum = 0;
-        for user_tip in &u.tips {
-            sum += user_tip.score;
+    /// Points for the champion picks, once the champion is known.
+    pub fn extra_points(self, user: &User, champion: Option<&str>) -> i32 {
+        match (self, champion) {
+            (RuleSet::Em2024, Some(champion)) => champion_points(user, champion),
+            (RuleSet::Em2024, None) => ScoreConfig::NO_WIN_TEAM,
+            (RuleSet::Classic2021, _) => ClassicScoreConfig::NO_WIN_TEAM,
         }
+    }
+
+    /// The most a user can get on `match_count` finished matches: an exact
+    /// tip on each, plus the winner bonus once the champion is known.
+    pub fn max_points(self, match_count: usize, champion: Option<&str>) -> i32 {
+        let bonus = match (self, champion) {
+            (RuleSet::Em2024, Some(_)) => ScoreConfig::WINNER,
+            _ => ScoreConfig::NO_WIN_TEAM,
+        };
 
-        u.scoreSum = sum;
+        match_count as i32 * self.category_points().win_exact + bonus
+    }
+
+    pub fn category_points(self) -> CategoryPoints {
+        match self {
+            RuleSet::Em2024 => CategoryPoints {
+                win_exact: ScoreConfig::WIN_EXACT,
+                win_score_diff: ScoreConfig::WIN_SCORE_DIFF,
+                win_team: ScoreConfig::WIN_TEAM,
+            },
+            RuleSet::Classic2021 => CategoryPoints {
+                win_exact: ClassicScoreConfig::WIN_EXACT,
+                win_score_diff: ClassicScoreConfig::WIN_SCORE_DIFF,
+                win_team: ClassicScoreConfig::WIN_TEAM,
+            },
+        }
     }
 }
 
-fn sort_user(user_ranking: &mut UserRanking) {
-    user_ranking.user.sort_by(|a, b| b.scoreSum.cmp(&a.scoreSum));
+fn score_tip_classic(score_home: i32, score_away: i32, tip_home: i32, tip_away: i32) -> i32 {
+    if score_home == tip_home && score_away == tip_away {
+        return ClassicScoreConfig::WIN_EXACT;
+    }
+
+    if score_home - score_away == tip_home - tip_away {
+        return ClassicScoreConfig::WIN_SCORE_DIFF;
+    }
+
+    if (score_home > score_away && tip_home > tip_away) || (score_home < score_away && tip_home < tip_away) {
+        return ClassicScoreConfig::WIN_TEAM;
+    }
+
+    ClassicScoreConfig::NO_WIN_TEAM
 }
 
-fn add_position(user_ranking_user: &mut Vec<UserRankingUser>) {
-    for u in user_ranking_user.clone() {
-        let index = user_ranking_user.iter().position(|r| r.name == u.name).unwrap();
-        let user_ranking_user_clone = user_ranking_user.clone();
-        let user = &mut user_ranking_user[index];
-
-        if index as i32 == 0 {
-            user.position = 1;
-        } else {
-            let user_in_front_of_u = &user_ranking_user_clone[index - 1];
-
-            if user.scoreSum == user_in_front_of_u.scoreSum {
-                user.position = user_in_front_of_u.position;
-            } else {
-                // user_in_front_of_u.position + 1; or index + 1
-                user.position = user_in_front_of_u.position + 1;
-            }
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use super::*;
+
+    fn user(winner: &str) -> User {
+        User {
+            id: 1,
+            username: String::from("user"),
+            department: String::from("test"),
+            winner: winner.to_string(),
+            secret_winner: String::new(),
         }
     }
+
+    #[rstest]
+    #[case(2, 1, 2, 1, 3)]
+    #[case(0, 0, 0, 0, 3)]
+    #[case(3, 1, 2, 0, 2)]
+    #[case(1, 1, 2, 2, 2)]
+    #[case(0, 2, 1, 4, 1)]
+    #[case(3, 0, 1, 0, 1)]
+    #[case(1, 1, 2, 1, 0)]
+    #[case(0, 1, 1, 0, 0)]
+    fn test_score_tip_classic(#[case] score_home: i32, #[case] score_away: i32, #[case] tip_home: i32, #[case] tip_away: i32, #[case] expected: i32) {
+        assert_eq!(RuleSet::Classic2021.score_tip(score_home, score_away, tip_home, tip_away), expected);
+    }
+
+    #[test]
+    fn test_rule_sets_differ_on_draws() {
+        assert_eq!(RuleSet::Em2024.score_tip(1, 1, 2, 2), ScoreConfig::WIN_TEAM);
+        assert_eq!(RuleSet::Classic2021.score_tip(1, 1, 2, 2), ClassicScoreConfig::WIN_SCORE_DIFF);
+        assert_eq!(RuleSet::Em2024.score_tip(2, 1, 2, 1), 4);
+    }
+
+    #[test]
+    fn test_extra_points() {
+        assert_eq!(RuleSet::Em2024.extra_points(&user("ESP"), Some("ESP")), ScoreConfig::WINNER);
+        assert_eq!(RuleSet::Em2024.extra_points(&user("ESP"), None), 0);
+        assert_eq!(RuleSet::Em2024.extra_points(&user("ESP"), Some("ARG")), 0);
+        assert_eq!(RuleSet::Classic2021.extra_points(&user("ESP"), Some("ESP")), 0);
+    }
+
+    #[test]
+    fn test_max_points() {
+        assert_eq!(RuleSet::Em2024.max_points(3, Some("ESP")), 27);
+        assert_eq!(RuleSet::Em2024.max_points(3, None), 12);
+        assert_eq!(RuleSet::Classic2021.max_points(3, Some("ESP")), 9);
+        assert_eq!(RuleSet::Classic2021.max_points(0, None), 0);
+    }
+
+    #[test]
+    fn test_rule_set_from_query_value() {
+        assert_eq!(serde_json::from_str::<RuleSet>(r#""classic2021""#).unwrap(), RuleSet::Classic2021);
+        assert_eq!(serde_json::from_str::<RuleSet>(r#""em2024""#).unwrap(), RuleSet::Em2024);
+        assert_eq!(RuleSet::default(), RuleSet::Em2024);
+    }
+
+    #[test]
+    fn test_rule_set_from_db() {
+        assert_eq!(RuleSet::from_db(RuleSet::Classic2021.as_str()), RuleSet::Classic2021);
+        assert_eq!(RuleSet::from_db(RuleSet::Em2024.as_str()), RuleSet::Em2024);
+        assert_eq!(RuleSet::from_db("unknown"), RuleSet::Em2024);
+    }
 }
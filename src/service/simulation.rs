@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use crate::db::{Game, ScheduledGame};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HypotheticalResult {
+    pub match_id: i32,
+    pub home_score: i32,
+    pub away_score: i32,
+}
+
+/// Adds hypothetical results for unfinished matches to the finished ones, so
+/// the rating can be calculated as if they were played. Nothing is stored.
+pub fn with_hypothetical_results(
+    mut games: Vec<Game>,
+    unfinished: &[ScheduledGame],
+    results: &[HypotheticalResult],
+) -> Result<Vec<Game>, &'static str> {
+    let mut simulated: HashSet<i32> = HashSet::new();
+
+    for result in results {
+        if result.home_score < 0 || result.away_score < 0 {
+            return Err("Scores must not be negative");
+        }
+
+        if !simulated.insert(result.match_id) {
+            return Err("Each match can only be simulated once");
+        }
+
+        let game = match unfinished.iter().find(|game| game.id == result.match_id) {
+            Some(game) => game,
+            None => return Err("Only upcoming matches can be simulated"),
+        };
+
+        games.push(Game {
+            id: game.id,
+            home_team: game.home_team.clone(),
+            away_team: game.away_team.clone(),
+            home_score: result.home_score,
+            away_score: result.away_score,
+            date: game.date,
+            is_live: false,
+        });
+    }
+
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unfinished_game(id: i32) -> ScheduledGame {
+        ScheduledGame {
+            id,
+            home_team: String::from(r#"{"name":"France","tla":"FRA"}"#),
+            away_team: String::from(r#"{"name":"Austria","tla":"AUT"}"#),
+            home_score: None,
+            away_score: None,
+            date: 1000,
+            status: String::from("TIMED"),
+        }
+    }
+
+    fn result(match_id: i32, home_score: i32, away_score: i32) -> HypotheticalResult {
+        HypotheticalResult { match_id, home_score, away_score }
+    }
+
+    #[test]
+    fn test_with_hypothetical_results() {
+        let past = vec![Game {
+            id: 1,
+            home_team: String::from(r#"{"name":"Germany","tla":"GER"}"#),
+            away_team: String::from(r#"{"name":"Spain","tla":"ESP"}"#),
+            home_score: 2,
+            away_score: 0,
+            date: 500,
+            is_live: false,
+        }];
+
+        let games = with_hypothetical_results(past, &[unfinished_game(3), unfinished_game(4)], &[result(4, 2, 0)]).unwrap();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].id, 4);
+        assert_eq!(games[1].home_score, 2);
+        assert_eq!(games[1].away_score, 0);
+        assert_eq!(games[1].date, 1000);
+    }
+
+    #[test]
+    fn test_with_hypothetical_results_rejects_invalid_results() {
+        let unfinished = vec![unfinished_game(3)];
+
+        assert!(with_hypothetical_results(Vec::new(), &unfinished, &[result(3, -1, 0)]).is_err());
+        assert!(with_hypothetical_results(Vec::new(), &unfinished, &[result(1, 1, 0)]).is_err());
+        assert!(with_hypothetical_results(Vec::new(), &unfinished, &[result(3, 1, 0), result(3, 0, 0)]).is_err());
+    }
+}
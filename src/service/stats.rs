@@ -0,0 +1,466 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use crate::db::{is_live_status, ScheduledGame, Tip};
+use crate::service::ranking::{CategoryPoints, RuleSet};
+use crate::service::{MatchInfo, Team, UserRating};
+
+const COMMON_SCORES: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutcomeShare {
+    pub home_win: f64,
+    pub draw: f64,
+    pub away_win: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreCount {
+    pub tip_home: i32,
+    pub tip_away: i32,
+    pub count: i32,
+    pub percentage: f64,
+}
+
+/// How many users got an exact tip, the goal difference, the winner or no points for a finished match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PointsCount {
+    pub win_exact: i32,
+    pub win_score_diff: i32,
+    pub win_team: i32,
+    pub no_win_team: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameStats {
+    pub match_id: i32,
+    pub tip_count: i32,
+    pub outcomes: OutcomeShare,
+    pub common_scores: Vec<ScoreCount>,
+    pub average_home_goals: f64,
+    pub average_away_goals: f64,
+    pub points: Option<PointsCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HitRate {
+    pub count: i32,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HitRates {
+    pub win_exact: HitRate,
+    pub win_score_diff: HitRate,
+    pub win_team: HitRate,
+    pub no_win_team: HitRate,
+}
+
+/// Points of all finished matches on one (UTC) day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchDay {
+    pub date: String,
+    pub score: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamAccuracy {
+    pub team: Team,
+    pub tip_count: i32,
+    pub score: i32,
+    pub average: f64,
+}
+
+/// Positive values mean the user predicts more goals or home wins than there are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bias {
+    pub goals_per_match: f64,
+    pub home_win_share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserStats {
+    pub user_id: i32,
+    pub name: String,
+    pub tip_count: i32,
+    pub missing_tips: i32,
+    pub hit_rates: HitRates,
+    pub average_points: f64,
+    pub best_match_day: Option<MatchDay>,
+    pub worst_match_day: Option<MatchDay>,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    pub teams: Vec<TeamAccuracy>,
+    pub bias: Bias,
+}
+
+/// Aggregates the tips of a match. `points` is only set once the match has a final result.
+pub fn get_game_stats(game: &ScheduledGame, tips: &[Tip], rules: RuleSet) -> GameStats {
+    let tip_count = tips.len() as i32;
+    let mut outcome_counts = [0; 3];
+    let mut score_counts: HashMap<(i32, i32), i32> = HashMap::new();
+
+    for tip in tips {
+        let outcome = match tip.score_home.cmp(&tip.score_away) {
+            Ordering::Greater => 0,
+            Ordering::Equal => 1,
+            Ordering::Less => 2,
+        };
+        outcome_counts[outcome] += 1;
+        *score_counts.entry((tip.score_home, tip.score_away)).or_default() += 1;
+    }
+
+    let mut common_scores: Vec<ScoreCount> = score_counts.into_iter()
+        .map(|((tip_home, tip_away), count)| ScoreCount {
+            tip_home,
+            tip_away,
+            count,
+            percentage: percentage(count, tip_count),
+        })
+        .collect();
+    common_scores.sort_by(|a, b| b.count.cmp(&a.count)
+        .then_with(|| (a.tip_home, a.tip_away).cmp(&(b.tip_home, b.tip_away))));
+    common_scores.truncate(COMMON_SCORES);
+
+    let points = match (game.home_score, game.away_score) {
+        (Some(score_home), Some(score_away)) if !is_live_status(&game.status) => Some(count_points(tips, score_home, score_away, rules)),
+        _ => None,
+    };
+
+    GameStats {
+        match_id: game.id,
+        tip_count,
+        outcomes: OutcomeShare {
+            home_win: percentage(outcome_counts[0], tip_count),
+            draw: percentage(outcome_counts[1], tip_count),
+            away_win: percentage(outcome_counts[2], tip_count),
+        },
+        common_scores,
+        average_home_goals: average(tips.iter().map(|tip| tip.score_home).sum(), tip_count),
+        average_away_goals: average(tips.iter().map(|tip| tip.score_away).sum(), tip_count),
+        points,
+    }
+}
+
+/// Builds the statistics of a user from the finished matches in their rating.
+/// A streak is a run of matches with points, a missing tip ends it.
+pub fn get_user_stats(user_rating: &UserRating, rules: RuleSet) -> UserStats {
+    let category_points = rules.category_points();
+
+    let mut finished: Vec<&MatchInfo> = user_rating.tips.iter()
+        .filter(|tip| tip.score_home.is_some() && tip.score_away.is_some())
+        .collect();
+    finished.sort_by_key(|tip| tip.date);
+
+    let tipped: Vec<&MatchInfo> = finished.iter().copied().filter(|tip| tip.tip_home.is_some() && tip.tip_away.is_some()).collect();
+    let tip_count = tipped.len() as i32;
+    let score: i32 = tipped.iter().map(|tip| tip.score).sum();
+
+    let mut points = PointsCount { win_exact: 0, win_score_diff: 0, win_team: 0, no_win_team: 0 };
+    let mut teams: BTreeMap<String, TeamAccuracy> = BTreeMap::new();
+    let mut goal_difference = 0;
+    let mut predicted_home_wins = 0;
+    let mut home_wins = 0;
+
+    for tip in &tipped {
+        points.add(tip.score, &category_points);
+
+        for team in [&tip.team1, &tip.team2] {
+            let accuracy = teams.entry(team.tla.clone()).or_insert_with(|| TeamAccuracy {
+                team: team.clone(),
+                tip_count: 0,
+                score: 0,
+                average: 0.0,
+            });
+            accuracy.tip_count += 1;
+            accuracy.score += tip.score;
+        }
+
+        let (tip_home, tip_away) = (tip.tip_home.unwrap(), tip.tip_away.unwrap());
+        let (score_home, score_away) = (tip.score_home.unwrap(), tip.score_away.unwrap());
+        goal_difference += tip_home + tip_away - score_home - score_away;
+        if tip_home > tip_away {
+            predicted_home_wins += 1;
+        }
+        if score_home > score_away {
+            home_wins += 1;
+        }
+    }
+
+    let mut team_list: Vec<TeamAccuracy> = teams.into_values()
+        .map(|mut accuracy| {
+            accuracy.average = average(accuracy.score, accuracy.tip_count);
+            accuracy
+        })
+        .collect();
+    team_list.sort_by(|a, b| b.average.total_cmp(&a.average).then_with(|| b.tip_count.cmp(&a.tip_count)));
+
+    let match_days = get_match_days(&finished);
+    let (current_streak, longest_streak) = get_streaks(&finished);
+
+    UserStats {
+        user_id: user_rating.user_id,
+        name: user_rating.name.clone(),
+        tip_count,
+        missing_tips: finished.len() as i32 - tip_count,
+        hit_rates: HitRates {
+            win_exact: hit_rate(points.win_exact, tip_count),
+            win_score_diff: hit_rate(points.win_score_diff, tip_count),
+            win_team: hit_rate(points.win_team, tip_count),
+            no_win_team: hit_rate(points.no_win_team, tip_count),
+        },
+        average_points: average(score, tip_count),
+        best_match_day: match_days.iter().max_by_key(|match_day| match_day.score).cloned(),
+        worst_match_day: match_days.iter().min_by_key(|match_day| match_day.score).cloned(),
+        current_streak,
+        longest_streak,
+        teams: team_list,
+        bias: Bias {
+            goals_per_match: average(goal_difference, tip_count),
+            home_win_share: percentage(predicted_home_wins, tip_count) - percentage(home_wins, tip_count),
+        },
+    }
+}
+
+fn get_match_days(finished: &[&MatchInfo]) -> Vec<MatchDay> {
+    let mut scores_by_day: BTreeMap<String, i32> = BTreeMap::new();
+
+    for tip in finished {
+        let date = DateTime::from_timestamp(tip.date as i64, 0).unwrap().date_naive().to_string();
+        *scores_by_day.entry(date).or_default() += tip.score;
+    }
+
+    scores_by_day.into_iter().map(|(date, score)| MatchDay { date, score }).collect()
+}
+
+fn get_streaks(finished: &[&MatchInfo]) -> (i32, i32) {
+    let mut current = 0;
+    let mut longest = 0;
+
+    for tip in finished {
+        if tip.score > 0 {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+
+    (current, longest)
+}
+
+fn hit_rate(count: i32, total: i32) -> HitRate {
+    HitRate { count, percentage: percentage(count, total) }
+}
+
+fn count_points(tips: &[Tip], score_home: i32, score_away: i32, rules: RuleSet) -> PointsCount {
+    let category_points = rules.category_points();
+    let mut points = PointsCount { win_exact: 0, win_score_diff: 0, win_team: 0, no_win_team: 0 };
+
+    for tip in tips {
+        points.add(rules.score_tip(score_home, score_away, tip.score_home, tip.score_away), &category_points);
+    }
+
+    points
+}
+
+impl PointsCount {
+    // Counts a tip in the category its points belong to under the rule set.
+    fn add(&mut self, score: i32, category_points: &CategoryPoints) {
+        if score == category_points.win_exact {
+            self.win_exact += 1;
+        } else if score == category_points.win_score_diff {
+            self.win_score_diff += 1;
+        } else if score == category_points.win_team {
+            self.win_team += 1;
+        } else {
+            self.no_win_team += 1;
+        }
+    }
+}
+
+fn percentage(count: i32, total: i32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    count as f64 * 100.0 / total as f64
+}
+
+fn average(sum: i32, count: i32) -> f64 {
+    if count == 0 {
+        return 0.0;
+    }
+
+    sum as f64 / count as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::test_builder::{self, match_info, scheduled_game, team, user_rating};
+    use super::*;
+
+    fn game(home_score: Option<i32>, away_score: Option<i32>) -> ScheduledGame {
+        ScheduledGame { home_score, away_score, ..scheduled_game(1, "GER", "ESP") }
+    }
+
+    fn tip(score_home: i32, score_away: i32) -> Tip {
+        test_builder::tip(1, 1, score_home, score_away)
+    }
+
+    #[test]
+    fn test_get_game_stats() {
+        let tips = vec![tip(2, 0), tip(2, 0), tip(3, 1), tip(1, 1), tip(0, 2)];
+
+        let stats = get_game_stats(&game(Some(2), Some(0)), &tips, RuleSet::Em2024);
+
+        assert_eq!(stats.tip_count, 5);
+        assert_eq!(stats.outcomes.home_win, 60.0);
+        assert_eq!(stats.outcomes.draw, 20.0);
+        assert_eq!(stats.outcomes.away_win, 20.0);
+
+        assert_eq!(stats.common_scores.len(), 4);
+        assert_eq!((stats.common_scores[0].tip_home, stats.common_scores[0].tip_away), (2, 0));
+        assert_eq!(stats.common_scores[0].count, 2);
+        assert_eq!(stats.common_scores[0].percentage, 40.0);
+        assert_eq!((stats.common_scores[1].tip_home, stats.common_scores[1].tip_away), (0, 2));
+
+        assert_eq!(stats.average_home_goals, 1.6);
+        assert_eq!(stats.average_away_goals, 0.8);
+
+        let points = stats.points.unwrap();
+        assert_eq!(points.win_exact, 2);
+        assert_eq!(points.win_score_diff, 1);
+        assert_eq!(points.win_team, 0);
+        assert_eq!(points.no_win_team, 2);
+    }
+
+    fn finished(date: u64, home: &str, away: &str, tip: Option<(i32, i32)>, result: (i32, i32)) -> MatchInfo {
+        finished_with_rules(date, home, away, tip, result, RuleSet::Em2024)
+    }
+
+    // A match of user 1, scored under the rules if they tipped it.
+    fn finished_with_rules(date: u64, home: &str, away: &str, tip: Option<(i32, i32)>, result: (i32, i32), rules: RuleSet) -> MatchInfo {
+        MatchInfo {
+            score: tip.map_or(0, |(tip_home, tip_away)| rules.score_tip(result.0, result.1, tip_home, tip_away)),
+            team1: team(home),
+            team2: team(away),
+            tip_home: tip.map(|tip| tip.0),
+            tip_away: tip.map(|tip| tip.1),
+            score_home: Some(result.0),
+            score_away: Some(result.1),
+            date,
+            ..match_info(0, 1)
+        }
+    }
+
+    fn with_tips(tips: Vec<MatchInfo>) -> UserRating {
+        UserRating { position: 1, score_sum: tips.iter().map(|tip| tip.score).sum(), tips, ..user_rating(1) }
+    }
+
+    #[test]
+    fn test_get_user_stats() {
+        // 2024-06-14 19:00 UTC, then one and two days later
+        let day = 86400;
+        let first = 1718391600;
+        let user_rating = with_tips(vec![
+            finished(first + 2 * day, "GER", "HUN", Some((1, 0)), (2, 0)),
+            finished(first, "GER", "SCO", Some((2, 0)), (2, 0)),
+            finished(first + 3600, "HUN", "SUI", Some((1, 2)), (1, 3)),
+            finished(first + day, "ESP", "CRO", Some((3, 1)), (3, 0)),
+            finished(first + day + 3600, "ITA", "ALB", None, (2, 1)),
+            finished(first + 2 * day + 3600, "ESP", "ITA", Some((0, 1)), (1, 0)),
+        ]);
+
+        let stats = get_user_stats(&user_rating, RuleSet::Em2024);
+
+        assert_eq!(stats.tip_count, 5);
+        assert_eq!(stats.missing_tips, 1);
+        assert_eq!(stats.hit_rates.win_exact.count, 1);
+        assert_eq!(stats.hit_rates.win_exact.percentage, 20.0);
+        assert_eq!(stats.hit_rates.win_team.count, 3);
+        assert_eq!(stats.hit_rates.no_win_team.count, 1);
+        assert_eq!(stats.average_points, 1.4);
+
+        let best = stats.best_match_day.unwrap();
+        assert_eq!(best.date, "2024-06-14");
+        assert_eq!(best.score, 5);
+        let worst = stats.worst_match_day.unwrap();
+        assert_eq!(worst.date, "2024-06-15");
+        assert_eq!(worst.score, 1);
+
+        assert_eq!(stats.current_streak, 0);
+        assert_eq!(stats.longest_streak, 3);
+
+        assert_eq!(stats.teams[0].team.tla, "SCO");
+        let germany = stats.teams.iter().find(|team| team.team.tla == "GER").unwrap();
+        assert_eq!(germany.tip_count, 2);
+        assert_eq!(germany.average, 2.5);
+
+        assert_eq!(stats.bias.goals_per_match, -0.2);
+        assert_eq!(stats.bias.home_win_share, -20.0);
+    }
+
+    #[test]
+    fn test_get_user_stats_without_finished_matches() {
+        let stats = get_user_stats(&with_tips(Vec::new()), RuleSet::Em2024);
+
+        assert_eq!(stats.tip_count, 0);
+        assert_eq!(stats.average_points, 0.0);
+        assert!(stats.best_match_day.is_none());
+        assert!(stats.teams.is_empty());
+    }
+
+    #[test]
+    fn test_get_user_stats_with_classic_rules() {
+        let first = 1718391600;
+        let rules = RuleSet::Classic2021;
+        let user_rating = with_tips(vec![
+            finished_with_rules(first, "GER", "SCO", Some((2, 0)), (2, 0), rules),
+            finished_with_rules(first + 3600, "HUN", "SUI", Some((1, 2)), (0, 1), rules),
+            finished_with_rules(first + 7200, "ESP", "CRO", Some((3, 1)), (3, 0), rules),
+        ]);
+
+        let stats = get_user_stats(&user_rating, rules);
+
+        assert_eq!(stats.hit_rates.win_exact.count, 1);
+        assert_eq!(stats.hit_rates.win_score_diff.count, 1);
+        assert_eq!(stats.hit_rates.win_team.count, 1);
+        assert_eq!(stats.hit_rates.no_win_team.count, 0);
+        assert_eq!(stats.average_points, 2.0);
+    }
+
+    #[test]
+    fn test_get_game_stats_with_classic_rules() {
+        let tips = vec![tip(2, 0), tip(3, 1), tip(1, 0), tip(1, 1)];
+
+        let points = get_game_stats(&game(Some(2), Some(0)), &tips, RuleSet::Classic2021).points.unwrap();
+
+        assert_eq!(points.win_exact, 1);
+        assert_eq!(points.win_score_diff, 1);
+        assert_eq!(points.win_team, 1);
+        assert_eq!(points.no_win_team, 1);
+    }
+
+    #[test]
+    fn test_get_game_stats_without_result_or_tips() {
+        let stats = get_game_stats(&game(None, None), &[], RuleSet::Em2024);
+
+        assert_eq!(stats.tip_count, 0);
+        assert_eq!(stats.outcomes.home_win, 0.0);
+        assert!(stats.common_scores.is_empty());
+        assert_eq!(stats.average_home_goals, 0.0);
+        assert!(stats.points.is_none());
+    }
+
+    #[test]
+    fn test_get_game_stats_without_points_while_live() {
+        let live_game = ScheduledGame { status: String::from("IN_PLAY"), ..game(Some(1), Some(0)) };
+
+        let stats = get_game_stats(&live_game, &[tip(1, 0), tip(2, 1)], RuleSet::Em2024);
+
+        assert_eq!(stats.tip_count, 2);
+        assert!(stats.points.is_none());
+    }
+}
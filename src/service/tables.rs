@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::db::{self, Game};
+use crate::db::tournaments::Tournament;
+use crate::service::ranking::RuleSet;
+use crate::service::{calculate_positions, get_user_rating, UserRating};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RatingTable {
+    pub global: Vec<UserRating>,
+    pub departments: HashMap<String, Vec<UserRating>>,
+}
+
+/// Final results plus the provisional scores of running matches.
+pub fn live_games(tournament_id: i32) -> Vec<Game> {
+    let mut games = db::get_past_games(tournament_id).unwrap();
+    games.extend(db::get_live_games(tournament_id).unwrap());
+    games
+}
+
+/// The rating of the tournament, globally and per department.
+pub fn rating_table(tournament: &Tournament, games: Vec<Game>) -> RatingTable {
+    rating_table_with_rules(tournament, games, tournament.rule_set)
+}
+
+/// Like `rating_table`, under other rules, e.g. to compare rule sets.
+pub fn rating_table_with_rules(tournament: &Tournament, games: Vec<Game>, rules: RuleSet) -> RatingTable {
+    let users = db::get_users(tournament.id).unwrap();
+    let mut user_rating_list = get_user_rating(games, users, rules, tournament.champion.as_deref()).unwrap();
+
+    let cloned_user_rating_list = user_rating_list.clone();
+    let mut departments: HashSet<String> = HashSet::new();
+    let mut department_ratings: HashMap<String, Vec<UserRating>> = HashMap::new();
+
+    for user_rating in &cloned_user_rating_list {
+        departments.insert(user_rating.department.clone());
+    }
+
+    for department in departments {
+        let mut department_users: Vec<UserRating> = cloned_user_rating_list.iter().filter(|user| user.department == department).cloned().collect();
+
+        calculate_positions(&mut department_users, rules, true);
+        department_ratings.insert(department, department_users);
+    }
+
+    calculate_positions(&mut user_rating_list, rules, true);
+
+    RatingTable {
+        global: user_rating_list,
+        departments: department_ratings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::db::tournaments::get_default_tournament;
+    use super::*;
+
+    #[test]
+    fn test_live_games() {
+        env::set_var("MODE", "test");
+        let games = live_games(db::DEFAULT_TOURNAMENT_ID);
+
+        assert_eq!(games.len(), 3);
+        assert!(games.iter().any(|game| game.id == 6 && game.is_live));
+    }
+
+    #[test]
+    fn test_rating_table() {
+        env::set_var("MODE", "test");
+        let tournament = get_default_tournament().unwrap();
+
+        let table = rating_table(&tournament, db::get_past_games(tournament.id).unwrap());
+
+        assert_eq!(table.global.len(), 7);
+        assert_eq!(table.global[0].position, 1);
+        assert_eq!(table.departments.values().map(Vec::len).sum::<usize>(), 7);
+        assert!(table.departments.values().all(|users| users[0].position == 1));
+    }
+}
@@ -0,0 +1,291 @@
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::api::match_client::MatchSource;
+use crate::db::matches::ImportReport;
+use crate::db::{self, ScheduledGame};
+use crate::events::{Broadcaster, Event};
+use crate::service::tables::{live_games, rating_table};
+use crate::service;
+
+const DEFAULT_LIVE_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_IDLE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+// A match window starts a bit before kickoff, in case the kickoff is moved,
+// and lasts long enough for extra time and penalties.
+const WINDOW_BEFORE_KICKOFF: u64 = 15 * 60;
+const WINDOW_AFTER_KICKOFF: u64 = 3 * 60 * 60;
+
+#[derive(Debug, Clone)]
+pub struct SyncConfig {
+    pub source: MatchSource,
+    /// How often to sync while a match is being played.
+    pub live_interval: Duration,
+    /// How often to sync otherwise.
+    pub idle_interval: Duration,
+}
+
+impl SyncConfig {
+    /// Reads `SYNC_SOURCE` (a file or URL in the football-data.org format),
+    /// `FOOTBALL_DATA_TOKEN`, `SYNC_INTERVAL_LIVE` and `SYNC_INTERVAL_IDLE`
+    /// (seconds). Without a source there is nothing to sync.
+    pub fn from_env() -> Option<SyncConfig> {
+        let source = env::var("SYNC_SOURCE").ok().filter(|source| !source.is_empty())?;
+        let seconds = |name: &str, default: Duration| env::var(name).ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .map_or(default, Duration::from_secs);
+
+        Some(SyncConfig {
+            source: MatchSource::parse(&source, env::var("FOOTBALL_DATA_TOKEN").ok()),
+            live_interval: seconds("SYNC_INTERVAL_LIVE", DEFAULT_LIVE_INTERVAL),
+            idle_interval: seconds("SYNC_INTERVAL_IDLE", DEFAULT_IDLE_INTERVAL),
+        })
+    }
+
+    /// The live interval while any unfinished match is inside its match window.
+    pub fn poll_interval(&self, unfinished: &[ScheduledGame], now: u64) -> Duration {
+        let in_match_window = unfinished.iter()
+            .any(|game| game.date <= now + WINDOW_BEFORE_KICKOFF && now <= game.date + WINDOW_AFTER_KICKOFF);
+
+        if in_match_window { self.live_interval } else { self.idle_interval }
+    }
+}
+
+/// Doubles the delay with every failure in a row, but never waits longer than
+/// the regular interval.
+pub fn retry_delay(consecutive_failures: u32, interval: Duration) -> Duration {
+    let factor = 2u32.saturating_pow(consecutive_failures.saturating_sub(1));
+    RETRY_DELAY.saturating_mul(factor).min(interval)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub last_attempt_at: Option<u64>,
+    pub last_success_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    pub last_report: Option<ImportReport>,
+    pub next_sync_at: Option<u64>,
+}
+
+/// Imports match data from the configured source, on a schedule and on demand.
+/// Shared as app data.
+pub struct SyncJob {
+    config: Option<SyncConfig>,
+    broadcaster: Broadcaster,
+    // Keeps the scheduler and a manual trigger from importing at the same time.
+    running: tokio::sync::Mutex<()>,
+    status: Mutex<SyncStatus>,
+}
+
+impl SyncJob {
+    pub fn new(config: Option<SyncConfig>, broadcaster: Broadcaster) -> Self {
+        SyncJob {
+            config,
+            broadcaster,
+            running: tokio::sync::Mutex::new(()),
+            status: Mutex::new(SyncStatus::default()),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// The status of the last sync, or `None` if syncing is not configured.
+    pub fn status(&self) -> Option<SyncStatus> {
+        self.config.as_ref().map(|_| self.status.lock().unwrap().clone())
+    }
+
+    /// Imports once and publishes the changed results and rankings.
+    pub async fn sync(&self) -> Result<ImportReport, String> {
+        let config = self.config.as_ref().ok_or_else(|| String::from("No sync source configured"))?;
+        let _running = self.running.lock().await;
+
+        let started_at = now();
+        let result = self.import(config).await;
+
+        let mut status = self.status.lock().unwrap();
+        status.last_attempt_at = Some(started_at);
+        match &result {
+            Ok(report) => {
+                status.last_success_at = Some(started_at);
+                status.last_error = None;
+                status.consecutive_failures = 0;
+                status.last_report = Some(report.clone());
+            },
+            Err(error) => {
+                status.last_error = Some(error.clone());
+                status.consecutive_failures += 1;
+            },
+        }
+
+        result
+    }
+
+    async fn import(&self, config: &SyncConfig) -> Result<ImportReport, String> {
+        let matches = config.source.fetch().await.map_err(|error| error.to_string())?;
+        let (teams, match_records) = service::import::to_records(&matches)
+            .map_err(|error| format!("Invalid kickoff time: {}", error))?;
+
+        let tournament = db::tournaments::get_default_tournament().map_err(|error| error.to_string())?;
+        let before = rating_table(&tournament, live_games(tournament.id)).global;
+        let report = db::matches::import_matches(tournament.id, &teams, &match_records).map_err(|error| error.to_string())?;
+
+        for game in match_records.iter().filter(|game| report.results_changed.contains(&game.id)) {
+            if let (Some(home_score), Some(away_score)) = (game.home_score, game.away_score) {
+                self.broadcaster.publish(Event::ResultUpdated {
+                    tournament: tournament.slug.clone(),
+                    match_id: game.id,
+                    home_score,
+                    away_score,
+                    provisional: db::is_live_status(&game.status),
+                });
+            }
+        }
+
+        if !report.results_changed.is_empty() {
+            let changes = service::changes::get_ranking_changes(&before, &rating_table(&tournament, live_games(tournament.id)).global);
+            if !changes.is_empty() {
+                self.broadcaster.publish(Event::RankingChanged { tournament: tournament.slug.clone(), changes });
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn next_delay(&self, config: &SyncConfig) -> Duration {
+        let now = now();
+        let interval = config.poll_interval(&db::get_unfinished_games(db::DEFAULT_TOURNAMENT_ID).unwrap_or_default(), now);
+        let consecutive_failures = self.status.lock().unwrap().consecutive_failures;
+
+        let delay = if consecutive_failures == 0 { interval } else { retry_delay(consecutive_failures, interval) };
+        self.status.lock().unwrap().next_sync_at = Some(now + delay.as_secs());
+
+        delay
+    }
+}
+
+/// Syncs until the server stops. Returns at once if syncing is not configured.
+pub async fn run(job: web::Data<SyncJob>) {
+    let config = match &job.config {
+        Some(config) => config.clone(),
+        None => return,
+    };
+
+    loop {
+        // Failures are recorded in the status and retried with backoff.
+        let _ = job.sync().await;
+        tokio::time::sleep(job.next_delay(&config)).await;
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+
+    fn config(source: MatchSource) -> SyncConfig {
+        SyncConfig { source, live_interval: Duration::from_secs(60), idle_interval: Duration::from_secs(3600) }
+    }
+
+    fn game(date: u64) -> ScheduledGame {
+        ScheduledGame {
+            id: 1,
+            home_team: String::from(r#"{"name":"Germany","tla":"GER"}"#),
+            away_team: String::from(r#"{"name":"Spain","tla":"ESP"}"#),
+            home_score: None,
+            away_score: None,
+            date,
+            status: String::from("TIMED"),
+        }
+    }
+
+    #[test]
+    fn test_poll_interval() {
+        let config = config(MatchSource::File(PathBuf::from("matches.json")));
+        let now = 1_720_000_000;
+
+        assert_eq!(config.poll_interval(&[], now), Duration::from_secs(3600));
+        assert_eq!(config.poll_interval(&[game(now + 86400)], now), Duration::from_secs(3600));
+        assert_eq!(config.poll_interval(&[game(now + 600)], now), Duration::from_secs(60));
+        assert_eq!(config.poll_interval(&[game(now - 2 * 3600)], now), Duration::from_secs(60));
+        assert_eq!(config.poll_interval(&[game(now - 4 * 3600)], now), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let interval = Duration::from_secs(300);
+
+        assert_eq!(retry_delay(1, interval), Duration::from_secs(30));
+        assert_eq!(retry_delay(2, interval), Duration::from_secs(60));
+        assert_eq!(retry_delay(3, interval), Duration::from_secs(120));
+        assert_eq!(retry_delay(5, interval), interval);
+        assert_eq!(retry_delay(100, interval), interval);
+    }
+
+    #[actix_web::test]
+    async fn test_sync_records_failures() {
+        env::set_var("MODE", "test");
+        let job = SyncJob::new(Some(config(MatchSource::File(PathBuf::from("/nonexistent/matches.json")))), Broadcaster::new());
+
+        assert!(job.sync().await.is_err());
+        assert!(job.sync().await.is_err());
+
+        let status = job.status().unwrap();
+        assert_eq!(status.consecutive_failures, 2);
+        assert!(status.last_error.unwrap().starts_with("Could not read the match data"));
+        assert!(status.last_attempt_at.is_some());
+        assert_eq!(status.last_success_at, None);
+    }
+
+    #[actix_web::test]
+    async fn test_sync_imports_and_publishes_results() {
+        env::set_var("MODE", "test");
+        let path = std::env::temp_dir().join(format!("em2021_api_sync_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"matches": [{
+            "id": 428759,
+            "utcDate": "2024-06-16T19:00:00Z",
+            "status": "IN_PLAY",
+            "stage": "GROUP_STAGE",
+            "group": "GROUP_C",
+            "homeTeam": { "id": 780, "name": "Serbia", "shortName": "Serbia", "tla": "SRB", "crest": null },
+            "awayTeam": { "id": 770, "name": "England", "shortName": "England", "tla": "ENG", "crest": null },
+            "score": { "duration": "REGULAR", "fullTime": { "home": 0, "away": 1 } }
+        }]}"#).unwrap();
+
+        let broadcaster = Broadcaster::new();
+        let mut receiver = broadcaster.subscribe();
+        let job = SyncJob::new(Some(config(MatchSource::File(path.clone()))), broadcaster);
+
+        let report = job.sync().await;
+        std::fs::remove_file(&path).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.matches_inserted, 1);
+        assert_eq!(report.teams_inserted, 2);
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            Event::ResultUpdated { tournament: String::from("em2024"), match_id: 428759, home_score: 0, away_score: 1, provisional: true },
+        );
+
+        let status = job.status().unwrap();
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.last_report, Some(report));
+    }
+
+    #[actix_web::test]
+    async fn test_sync_without_source() {
+        let job = SyncJob::new(None, Broadcaster::new());
+
+        assert!(!job.is_configured());
+        assert!(job.status().is_none());
+        assert!(job.sync().await.is_err());
+    }
+}
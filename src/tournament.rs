@@ -0,0 +1,25 @@
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use crate::db::tournaments::{self, Tournament};
+use crate::error::ApiError;
+
+/// The tournament of a request: the one named in `/tournaments/{slug}/...`, or
+/// the default tournament for the URLs without one.
+impl FromRequest for Tournament {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let tournament = match req.match_info().get("slug") {
+            Some(slug) => tournaments::get_tournament(slug),
+            None => tournaments::get_default_tournament().map(Some),
+        };
+
+        ready(match tournament {
+            Ok(Some(tournament)) => Ok(tournament),
+            Ok(None) => Err(ApiError::not_found("Tournament not found")),
+            Err(_) => Err(ApiError::internal()),
+        })
+    }
+}
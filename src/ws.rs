@@ -0,0 +1,132 @@
+use actix_ws::{Message, MessageStream, Session};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::{interval, timeout, Instant};
+use crate::events::{Event, Subscriptions, Topic};
+
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+// A client that does not take a message within this time is disconnected,
+// so it cannot hold up its connection task forever.
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { topic: String },
+    Unsubscribe { topic: String },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed { topics: Vec<String> },
+    Lagged { skipped: u64 },
+    Error { error: String },
+}
+
+/// Applies a message of the client to its subscriptions and returns the reply.
+pub fn handle_client_message(subscriptions: &mut Subscriptions, text: &str) -> ServerMessage {
+    let (topic, subscribe) = match serde_json::from_str(text) {
+        Ok(ClientMessage::Subscribe { topic }) => (topic, true),
+        Ok(ClientMessage::Unsubscribe { topic }) => (topic, false),
+        Err(_) => return ServerMessage::Error { error: String::from("Invalid message") },
+    };
+
+    let topic = match Topic::parse(&topic) {
+        Some(topic) => topic,
+        None => return ServerMessage::Error { error: format!("Unknown topic {}", topic) },
+    };
+
+    if subscribe {
+        subscriptions.subscribe(topic);
+    } else {
+        subscriptions.unsubscribe(&topic);
+    }
+
+    ServerMessage::Subscribed { topics: subscriptions.topics() }
+}
+
+/// Serves one WebSocket connection until either side closes it, with the
+/// events of one tournament. Every connection reads events from its own
+/// receiver, so a slow client only skips events (and is told so) instead of
+/// blocking everyone else.
+pub async fn run_session(mut session: Session, mut stream: MessageStream, mut receiver: Receiver<Event>, tournament: String) {
+    let mut subscriptions = Subscriptions::default();
+    let mut heartbeat = interval(PING_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    let close_reason = loop {
+        let reply = tokio::select! {
+            message = stream.recv() => {
+                last_seen = Instant::now();
+                match message {
+                    Some(Ok(Message::Text(text))) => serde_json::to_string(&handle_client_message(&mut subscriptions, &text)).unwrap(),
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
+                        continue;
+                    },
+                    Some(Ok(Message::Close(reason))) => break reason,
+                    Some(Ok(_)) => continue,
+                    Some(Err(_)) | None => break None,
+                }
+            },
+            event = receiver.recv() => match event {
+                Ok(event) if event.tournament() != tournament => continue,
+                Ok(event) => match subscriptions.filter(&event) {
+                    Some(event) => serde_json::to_string(&event).unwrap(),
+                    None => continue,
+                },
+                Err(RecvError::Lagged(skipped)) => serde_json::to_string(&ServerMessage::Lagged { skipped }).unwrap(),
+                Err(RecvError::Closed) => break None,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT || session.ping(b"").await.is_err() {
+                    break None;
+                }
+                continue;
+            },
+        };
+
+        if !matches!(timeout(SEND_TIMEOUT, session.text(reply)).await, Ok(Ok(()))) {
+            break None;
+        }
+    };
+
+    let _ = session.close(close_reason).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_client_message() {
+        let mut subscriptions = Subscriptions::default();
+
+        let reply = handle_client_message(&mut subscriptions, r#"{"action": "subscribe", "topic": "match:3"}"#);
+        assert_eq!(reply, ServerMessage::Subscribed { topics: vec![String::from("match:3")] });
+
+        let reply = handle_client_message(&mut subscriptions, r#"{"action": "subscribe", "topic": "rating"}"#);
+        assert_eq!(reply, ServerMessage::Subscribed { topics: vec![String::from("rating"), String::from("match:3")] });
+
+        let reply = handle_client_message(&mut subscriptions, r#"{"action": "unsubscribe", "topic": "match:3"}"#);
+        assert_eq!(reply, ServerMessage::Subscribed { topics: vec![String::from("rating")] });
+    }
+
+    #[test]
+    fn test_handle_client_message_rejects_invalid_messages() {
+        let mut subscriptions = Subscriptions::default();
+
+        let reply = handle_client_message(&mut subscriptions, r#"{"action": "subscribe", "topic": "weather"}"#);
+        assert_eq!(reply, ServerMessage::Error { error: String::from("Unknown topic weather") });
+
+        let reply = handle_client_message(&mut subscriptions, "hello");
+        assert_eq!(reply, ServerMessage::Error { error: String::from("Invalid message") });
+
+        assert!(subscriptions.topics().is_empty());
+    }
+}